@@ -0,0 +1,104 @@
+use magenboy_core::mmu::carts::{CameraImageSource, camera_image_source::{CAMERA_IMAGE_HEIGHT, CAMERA_IMAGE_WIDTH}};
+
+/// Feeds the camera with a static image, supports binary PGM (P5) and uncompressed 24/32 bits BMP files.
+/// The image is scaled to the sensor size
+pub struct ImageFileSource{
+    image:[[u8; CAMERA_IMAGE_WIDTH]; CAMERA_IMAGE_HEIGHT]
+}
+
+// Grayscale image before scaling
+struct GrayImage{
+    width:usize,
+    height:usize,
+    pixels:Vec<u8>
+}
+
+impl ImageFileSource{
+    pub fn new(path:&str)->Result<Self, String>{
+        let file = std::fs::read(path).map_err(|e| format!("Could not read {}: {}", path, e))?;
+        let image = match &file[..2.min(file.len())]{
+            b"P5" => parse_pgm(&file)?,
+            b"BM" => parse_bmp(&file)?,
+            _ => return Err(format!("Unsupported image format: {}, only PGM and BMP are supported", path))
+        };
+        
+        let mut scaled = [[0; CAMERA_IMAGE_WIDTH]; CAMERA_IMAGE_HEIGHT];
+        for (y, row) in scaled.iter_mut().enumerate(){
+            for (x, pixel) in row.iter_mut().enumerate(){
+                let source_x = x * image.width / CAMERA_IMAGE_WIDTH;
+                let source_y = y * image.height / CAMERA_IMAGE_HEIGHT;
+                *pixel = image.pixels[source_y * image.width + source_x];
+            }
+        }
+        return Ok(Self{image: scaled});
+    }
+}
+
+impl CameraImageSource for ImageFileSource{
+    fn capture(&mut self, buffer:&mut [[u8; CAMERA_IMAGE_WIDTH]; CAMERA_IMAGE_HEIGHT]) {
+        *buffer = self.image;
+    }
+}
+
+fn parse_pgm(file:&[u8])->Result<GrayImage, String>{
+    // The header is 4 whitespace separated tokens (magic, width, height and max value) with optional comments
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    while tokens.len() < 4 && index < file.len(){
+        match file[index]{
+            b'#' => while index < file.len() && file[index] != b'\n' {index += 1},
+            c if c.is_ascii_whitespace() => index += 1,
+            _ => {
+                let start = index;
+                while index < file.len() && !file[index].is_ascii_whitespace() {index += 1}
+                tokens.push(std::str::from_utf8(&file[start..index]).map_err(|e| e.to_string())?);
+            }
+        }
+    }
+    // A single whitespace separates the header from the data
+    index += 1;
+    let parse = |token:Option<&&str>| token.and_then(|t| t.parse::<usize>().ok()).ok_or(String::from("Invalid PGM header"));
+    let width = parse(tokens.get(1))?;
+    let height = parse(tokens.get(2))?;
+    let max_value = parse(tokens.get(3))?;
+    if max_value == 0 || max_value > u8::MAX as usize || file.len() < index + (width * height){
+        return Err(String::from("Unsupported PGM file, only 8 bit images are supported"));
+    }
+    let pixels = file[index..index + (width * height)].iter().map(|p| (*p as usize * 0xFF / max_value) as u8).collect();
+    return Ok(GrayImage{width, height, pixels});
+}
+
+fn parse_bmp(file:&[u8])->Result<GrayImage, String>{
+    let read_u32 = |offset:usize| file.get(offset..offset + 4).map(|b| u32::from_le_bytes(b.try_into().unwrap())).ok_or(String::from("Invalid BMP header"));
+    let read_u16 = |offset:usize| file.get(offset..offset + 2).map(|b| u16::from_le_bytes(b.try_into().unwrap())).ok_or(String::from("Invalid BMP header"));
+    let data_offset = read_u32(10)? as usize;
+    let width = read_u32(18)? as i32;
+    let height = read_u32(22)? as i32;
+    let bits_per_pixel = read_u16(28)?;
+    let compression = read_u32(30)?;
+    if (bits_per_pixel != 24 && bits_per_pixel != 32) || (compression != 0 && compression != 3) || width <= 0 || height == 0{
+        return Err(String::from("Unsupported BMP file, only uncompressed 24/32 bits images are supported"));
+    }
+    let width = width as usize;
+    // Positive height means the rows are stored bottom to top
+    let bottom_up = height > 0;
+    let height = height.unsigned_abs() as usize;
+    let bytes_per_pixel = bits_per_pixel as usize / 8;
+    let row_size = ((width * bytes_per_pixel) + 3) & !3;
+    if file.len() < data_offset + (row_size * height){
+        return Err(String::from("BMP file is truncated"));
+    }
+
+    let mut pixels = Vec::with_capacity(width * height);
+    for y in 0..height{
+        let row = if bottom_up {height - 1 - y} else {y};
+        let row_start = data_offset + (row * row_size);
+        for x in 0..width{
+            let pixel = &file[row_start + (x * bytes_per_pixel)..];
+            // Stored as BGR
+            let gray = ((pixel[2] as u32 * 299) + (pixel[1] as u32 * 587) + (pixel[0] as u32 * 114)) / 1000;
+            pixels.push(gray as u8);
+        }
+    }
+    return Ok(GrayImage{width, height, pixels});
+}
@@ -0,0 +1,353 @@
+use std::{cell::RefCell, path::PathBuf, rc::Rc};
+
+use magenboy_core::{SerialDevice, utils::bit_masks::*};
+
+use crate::image_file_writer::write_image_file;
+
+pub const PRINTER_IMAGE_WIDTH:usize = 160;
+const TILES_PER_LINE:usize = PRINTER_IMAGE_WIDTH / 8;
+const TILE_SIZE:usize = 16;
+// The printer ram holds 9 data packets
+const PRINTER_RAM_SIZE:usize = 0x2000;
+
+const MAGIC:[u8;2] = [0x88, 0x33];
+const ALIVE_VALUE:u8 = 0x81;
+
+const INIT_COMMAND:u8 = 0x1;
+const PRINT_COMMAND:u8 = 0x2;
+const DATA_COMMAND:u8 = 0x4;
+const BREAK_COMMAND:u8 = 0x8;
+const STATUS_COMMAND:u8 = 0xF;
+
+const CHECKSUM_ERROR_MASK:u8 = BIT_0_MASK;
+const PRINTING_MASK:u8 = BIT_1_MASK;
+const IMAGE_DATA_FULL_MASK:u8 = BIT_2_MASK;
+const UNPROCESSED_DATA_MASK:u8 = BIT_3_MASK;
+// The games poll the status while printing, keeping the busy state for a few polls so they show their printing animation
+const PRINTING_STATUS_POLLS:u8 = 4;
+
+const DEFAULT_PALETTE:u8 = 0xE4;
+const SHADES:[[u8;3];4] = [[0xFF, 0xFF, 0xFF], [0xAA, 0xAA, 0xAA], [0x55, 0x55, 0x55], [0, 0, 0]];
+
+#[derive(Clone, Copy, PartialEq)]
+enum PacketState{
+    Magic(usize),
+    Command,
+    Compression,
+    LengthLow,
+    LengthHigh,
+    Data,
+    ChecksumLow,
+    ChecksumHigh,
+    Alive,
+    Status
+}
+
+/// The Game Boy Printer, connected to the serial port and saves the printed images to files.
+///
+/// Consecutive prints with no margin after them are joined to a single image (Pokemon prints the Pokedex entries in several parts)
+pub struct GbPrinter{
+    output_directory:PathBuf,
+    prints_counter:u32,
+
+    state:PacketState,
+    command:u8,
+    compressed:bool,
+    length:u16,
+    data:Vec<u8>,
+    checksum:u16,
+    received_checksum:u16,
+
+    status:u8,
+    printing_polls:u8,
+    ram:Vec<u8>,
+    // The shade index (0-3) of every pixel printed so far
+    image:Vec<u8>
+}
+
+impl GbPrinter{
+    /// The images are saved as `print_[number].png` in the output directory
+    pub fn new(output_directory:PathBuf)->Self{
+        Self{
+            output_directory, prints_counter:0, state:PacketState::Magic(0), command:0, compressed:false, length:0, data:Vec::new(),
+            checksum:0, received_checksum:0, status:0, printing_polls:0, ram:Vec::with_capacity(PRINTER_RAM_SIZE), image:Vec::new()
+        }
+    }
+
+    fn receive_byte(&mut self, value:u8)->u8{
+        let mut response = 0;
+        self.state = match self.state{
+            PacketState::Magic(index) if value == MAGIC[index] => {
+                if index == MAGIC.len() - 1 {PacketState::Command} else {PacketState::Magic(index + 1)}
+            }
+            PacketState::Magic(_) => PacketState::Magic((value == MAGIC[0]) as usize),
+            PacketState::Command => {
+                self.command = value;
+                self.checksum = value as u16;
+                PacketState::Compression
+            }
+            PacketState::Compression => {
+                self.compressed = value & BIT_0_MASK != 0;
+                self.checksum = self.checksum.wrapping_add(value as u16);
+                PacketState::LengthLow
+            }
+            PacketState::LengthLow => {
+                self.length = value as u16;
+                self.checksum = self.checksum.wrapping_add(value as u16);
+                PacketState::LengthHigh
+            }
+            PacketState::LengthHigh => {
+                self.length |= (value as u16) << 8;
+                self.checksum = self.checksum.wrapping_add(value as u16);
+                self.data.clear();
+                if self.length == 0 {PacketState::ChecksumLow} else {PacketState::Data}
+            }
+            PacketState::Data => {
+                self.data.push(value);
+                self.checksum = self.checksum.wrapping_add(value as u16);
+                if self.data.len() == self.length as usize {PacketState::ChecksumLow} else {PacketState::Data}
+            }
+            PacketState::ChecksumLow => {
+                self.received_checksum = value as u16;
+                PacketState::ChecksumHigh
+            }
+            PacketState::ChecksumHigh => {
+                self.received_checksum |= (value as u16) << 8;
+                self.execute_command();
+                PacketState::Alive
+            }
+            PacketState::Alive => {
+                response = ALIVE_VALUE;
+                PacketState::Status
+            }
+            PacketState::Status => {
+                response = self.status;
+                PacketState::Magic(0)
+            }
+        };
+        return response;
+    }
+
+    fn execute_command(&mut self){
+        if self.checksum != self.received_checksum{
+            log::warn!("Printer packet checksum mismatch, expected: {:#X} actual: {:#X}", self.checksum, self.received_checksum);
+            self.status |= CHECKSUM_ERROR_MASK;
+            return;
+        }
+        self.status &= !CHECKSUM_ERROR_MASK;
+        match self.command{
+            INIT_COMMAND => {
+                self.ram.clear();
+                self.status = 0;
+                self.printing_polls = 0;
+            }
+            DATA_COMMAND => {
+                let data = core::mem::take(&mut self.data);
+                if self.compressed{
+                    decompress(&data, &mut self.ram);
+                }
+                else{
+                    self.ram.extend_from_slice(&data);
+                }
+                self.data = data;
+                self.ram.truncate(PRINTER_RAM_SIZE);
+                if !self.ram.is_empty(){
+                    self.status |= UNPROCESSED_DATA_MASK;
+                }
+                if self.ram.len() == PRINTER_RAM_SIZE{
+                    self.status |= IMAGE_DATA_FULL_MASK;
+                }
+            }
+            PRINT_COMMAND if self.data.len() >= 4 => {
+                // The exposure (data[3]) only affects the darkness on real paper and is ignored
+                let margin_after = self.data[1] & 0xF;
+                let palette = if self.data[2] == 0 {DEFAULT_PALETTE} else {self.data[2]};
+                self.print(palette);
+                self.status = (self.status & !(UNPROCESSED_DATA_MASK | IMAGE_DATA_FULL_MASK)) | PRINTING_MASK;
+                self.printing_polls = PRINTING_STATUS_POLLS;
+                if margin_after != 0{
+                    self.save_image();
+                }
+            }
+            STATUS_COMMAND if self.printing_polls != 0 => {
+                self.printing_polls -= 1;
+                if self.printing_polls == 0{
+                    self.status &= !PRINTING_MASK;
+                }
+            }
+            BREAK_COMMAND => self.ram.clear(),
+            STATUS_COMMAND | PRINT_COMMAND => {}
+            _ => log::warn!("Unsupported printer command: {:#X}", self.command)
+        }
+    }
+
+    // Decodes the tiles in the ram to the image
+    fn print(&mut self, palette:u8){
+        let tiles_lines = self.ram.len() / (TILE_SIZE * TILES_PER_LINE);
+        for tiles_line in 0..tiles_lines{
+            for y in 0..8{
+                for x in 0..PRINTER_IMAGE_WIDTH{
+                    let tile_address = ((tiles_line * TILES_PER_LINE) + (x / 8)) * TILE_SIZE;
+                    let low = self.ram[tile_address + (y * 2)];
+                    let high = self.ram[tile_address + (y * 2) + 1];
+                    let bit = 7 - (x % 8);
+                    let color = (((high >> bit) & 1) << 1) | ((low >> bit) & 1);
+                    self.image.push((palette >> (color * 2)) & 0b11);
+                }
+            }
+        }
+        self.ram.clear();
+    }
+
+    fn save_image(&mut self){
+        if self.image.is_empty(){
+            return;
+        }
+        let height = self.image.len() / PRINTER_IMAGE_WIDTH;
+        let pixels:Vec<[u8;3]> = self.image.iter().map(|shade| SHADES[*shade as usize]).collect();
+        let path = loop{
+            self.prints_counter += 1;
+            let path = self.output_directory.join(std::format!("print_{}.png", self.prints_counter));
+            if !path.exists(){
+                break path;
+            }
+        };
+        match write_image_file(&path, PRINTER_IMAGE_WIDTH, height, &pixels){
+            Ok(()) => log::info!("Printed to {}", path.display()),
+            Err(err) => log::error!("Failed to save the printed image to {}: {}", path.display(), err)
+        }
+        self.image.clear();
+    }
+}
+
+impl SerialDevice for GbPrinter{
+    fn transfer_internal(&mut self, data:u8)->u8{
+        return self.receive_byte(data);
+    }
+
+    // The printer never drives the clock
+    fn transfer_external(&mut self, _data:u8)->Option<u8>{None}
+}
+
+/// The gameboy holds the serial device for the rest of the program so this handle is shared with it,
+/// allowing to flush the last print on exit
+#[derive(Clone)]
+pub struct GbPrinterHandle(Rc<RefCell<GbPrinter>>);
+
+impl GbPrinterHandle{
+    pub fn new(printer:GbPrinter)->Self{
+        Self(Rc::new(RefCell::new(printer)))
+    }
+
+    /// Saves a print that had no margin after it
+    pub fn flush(&self){
+        self.0.borrow_mut().save_image();
+    }
+}
+
+impl SerialDevice for GbPrinterHandle{
+    fn transfer_internal(&mut self, data:u8)->u8{
+        self.0.borrow_mut().transfer_internal(data)
+    }
+
+    fn transfer_external(&mut self, data:u8)->Option<u8>{
+        self.0.borrow_mut().transfer_external(data)
+    }
+}
+
+// RLE - a control byte with the msb set is followed by a byte repeated (control & 0x7F) + 2 times,
+// otherwise the control byte is followed by control + 1 literal bytes
+fn decompress(data:&[u8], output:&mut Vec<u8>){
+    let mut index = 0;
+    while index < data.len(){
+        let control = data[index];
+        index += 1;
+        if control & BIT_7_MASK != 0{
+            let Some(value) = data.get(index) else {break};
+            output.extend(core::iter::repeat(*value).take((control & 0x7F) as usize + 2));
+            index += 1;
+        }
+        else{
+            let end = (index + control as usize + 1).min(data.len());
+            output.extend_from_slice(&data[index..end]);
+            index = end;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn send_packet(printer:&mut GbPrinter, command:u8, compressed:bool, data:&[u8])->(u8, u8){
+        let mut packet = vec![MAGIC[0], MAGIC[1], command, compressed as u8];
+        packet.extend_from_slice(&(data.len() as u16).to_le_bytes());
+        packet.extend_from_slice(data);
+        let checksum = packet[2..].iter().fold(0u16, |sum, value| sum.wrapping_add(*value as u16));
+        packet.extend_from_slice(&checksum.to_le_bytes());
+        for value in packet{
+            assert_eq!(printer.transfer_internal(value), 0);
+        }
+        return (printer.transfer_internal(0), printer.transfer_internal(0));
+    }
+
+    #[test]
+    fn decompress_runs_and_literals(){
+        let mut output = Vec::new();
+        decompress(&[0x81, 0xAA, 0x01, 0x12, 0x34], &mut output);
+        assert_eq!(output, vec![0xAA, 0xAA, 0xAA, 0x12, 0x34]);
+    }
+
+    #[test]
+    fn print_a_data_packet(){
+        let output_directory = std::env::temp_dir().join(std::format!("magenboy_printer_test_{}", std::process::id()));
+        std::fs::create_dir_all(&output_directory).unwrap();
+        let mut printer = GbPrinter::new(output_directory.clone());
+
+        assert_eq!(send_packet(&mut printer, INIT_COMMAND, false, &[]), (ALIVE_VALUE, 0));
+        // 2 lines of black tiles, compressed
+        assert_eq!(send_packet(&mut printer, DATA_COMMAND, true, &[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFA, 0xFF]), (ALIVE_VALUE, UNPROCESSED_DATA_MASK));
+        assert_eq!(send_packet(&mut printer, DATA_COMMAND, false, &[]), (ALIVE_VALUE, UNPROCESSED_DATA_MASK));
+        assert_eq!(send_packet(&mut printer, PRINT_COMMAND, false, &[1, 0x13, 0xE4, 0x40]), (ALIVE_VALUE, PRINTING_MASK));
+        for _ in 1..PRINTING_STATUS_POLLS{
+            assert_eq!(send_packet(&mut printer, STATUS_COMMAND, false, &[]), (ALIVE_VALUE, PRINTING_MASK));
+        }
+        assert_eq!(send_packet(&mut printer, STATUS_COMMAND, false, &[]), (ALIVE_VALUE, 0));
+
+        let file = std::fs::read(output_directory.join("print_1.png")).unwrap();
+        assert_eq!(&file[1..4], b"PNG");
+        // 160x16 pixels in the header
+        assert_eq!(&file[16..24], &[0, 0, 0, 160, 0, 0, 0, 16]);
+        std::fs::remove_dir_all(output_directory).unwrap();
+    }
+
+    #[test]
+    fn checksum_error_is_reported(){
+        let mut printer = GbPrinter::new(std::env::temp_dir());
+        for value in [MAGIC[0], MAGIC[1], INIT_COMMAND, 0, 0, 0, 0x12, 0x34]{
+            printer.transfer_internal(value);
+        }
+        assert_eq!(printer.transfer_internal(0), ALIVE_VALUE);
+        assert_eq!(printer.transfer_internal(0), CHECKSUM_ERROR_MASK);
+    }
+
+    #[test]
+    fn flush_saves_a_print_without_margin(){
+        let output_directory = std::env::temp_dir().join(std::format!("magenboy_printer_flush_test_{}", std::process::id()));
+        std::fs::create_dir_all(&output_directory).unwrap();
+        let printer = GbPrinterHandle::new(GbPrinter::new(output_directory.clone()));
+
+        send_packet(&mut printer.0.borrow_mut(), INIT_COMMAND, false, &[]);
+        send_packet(&mut printer.0.borrow_mut(), DATA_COMMAND, true, &[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFA, 0xFF]);
+        // No margin after the print so it waits for the next one
+        send_packet(&mut printer.0.borrow_mut(), PRINT_COMMAND, false, &[1, 0x10, 0xE4, 0x40]);
+        for _ in 0..PRINTING_STATUS_POLLS{
+            send_packet(&mut printer.0.borrow_mut(), STATUS_COMMAND, false, &[]);
+        }
+        assert!(!output_directory.join("print_1.png").exists());
+
+        printer.flush();
+        assert!(output_directory.join("print_1.png").exists());
+        std::fs::remove_dir_all(output_directory).unwrap();
+    }
+}
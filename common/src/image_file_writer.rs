@@ -0,0 +1,126 @@
+use std::{io, path::Path};
+
+/// Writes an RGB image, the format is chosen by the file extension - BMP for `.bmp` and PNG otherwise
+pub fn write_image_file(path:&Path, width:usize, height:usize, pixels:&[[u8;3]])->io::Result<()>{
+    let file = match path.extension().and_then(std::ffi::OsStr::to_str){
+        Some(extension) if extension.eq_ignore_ascii_case("bmp") => encode_bmp(width, height, pixels),
+        _ => encode_png(width, height, pixels)
+    };
+    return std::fs::write(path, file);
+}
+
+// 24 bits uncompressed BMP, the rows are stored bottom up and padded to 4 bytes
+fn encode_bmp(width:usize, height:usize, pixels:&[[u8;3]])->Vec<u8>{
+    const HEADERS_SIZE:u32 = 54;
+    let row_size = (width * 3 + 3) & !3;
+    let image_size = (row_size * height) as u32;
+    let mut file = Vec::with_capacity(HEADERS_SIZE as usize + image_size as usize);
+    file.extend_from_slice(b"BM");
+    file.extend_from_slice(&(HEADERS_SIZE + image_size).to_le_bytes());
+    file.extend_from_slice(&0u32.to_le_bytes());
+    file.extend_from_slice(&HEADERS_SIZE.to_le_bytes());
+    // BITMAPINFOHEADER
+    file.extend_from_slice(&40u32.to_le_bytes());
+    file.extend_from_slice(&(width as i32).to_le_bytes());
+    file.extend_from_slice(&(height as i32).to_le_bytes());
+    file.extend_from_slice(&1u16.to_le_bytes());
+    file.extend_from_slice(&24u16.to_le_bytes());
+    file.extend_from_slice(&0u32.to_le_bytes());
+    file.extend_from_slice(&image_size.to_le_bytes());
+    // 72 DPI
+    file.extend_from_slice(&2835u32.to_le_bytes());
+    file.extend_from_slice(&2835u32.to_le_bytes());
+    file.extend_from_slice(&[0;8]);
+    for row in pixels.chunks_exact(width).rev(){
+        for [r, g, b] in row{
+            file.extend_from_slice(&[*b, *g, *r]);
+        }
+        file.resize(file.len() + row_size - (width * 3), 0);
+    }
+    return file;
+}
+
+// 8 bits RGB PNG, the data is stored with uncompressed deflate blocks to avoid depending on a compression library
+fn encode_png(width:usize, height:usize, pixels:&[[u8;3]])->Vec<u8>{
+    const MAX_STORED_BLOCK_SIZE:usize = 0xFFFF;
+
+    let mut raw_data = Vec::with_capacity((width * 3 + 1) * height);
+    for row in pixels.chunks_exact(width){
+        // No filter
+        raw_data.push(0);
+        raw_data.extend(row.iter().flatten());
+    }
+
+    // zlib header with no compression
+    let mut zlib_data = vec![0x78, 0x01];
+    let blocks_count = raw_data.chunks(MAX_STORED_BLOCK_SIZE).len();
+    for (i, block) in raw_data.chunks(MAX_STORED_BLOCK_SIZE).enumerate(){
+        // The first bit marks the final block
+        zlib_data.push((i == blocks_count - 1) as u8);
+        zlib_data.extend_from_slice(&(block.len() as u16).to_le_bytes());
+        zlib_data.extend_from_slice(&(!(block.len() as u16)).to_le_bytes());
+        zlib_data.extend_from_slice(block);
+    }
+    zlib_data.extend_from_slice(&adler32(&raw_data).to_be_bytes());
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    // Bit depth, color type (RGB), compression, filter and interlace methods
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut file = b"\x89PNG\r\n\x1a\n".to_vec();
+    write_png_chunk(&mut file, b"IHDR", &header);
+    write_png_chunk(&mut file, b"IDAT", &zlib_data);
+    write_png_chunk(&mut file, b"IEND", &[]);
+    return file;
+}
+
+fn write_png_chunk(file:&mut Vec<u8>, chunk_type:&[u8;4], data:&[u8]){
+    file.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let crc_start = file.len();
+    file.extend_from_slice(chunk_type);
+    file.extend_from_slice(data);
+    let crc = crc32(&file[crc_start..]);
+    file.extend_from_slice(&crc.to_be_bytes());
+}
+
+pub(crate) fn crc32(data:&[u8])->u32{
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in data{
+        crc ^= *byte as u32;
+        for _ in 0..8{
+            crc = if crc & 1 != 0 {(crc >> 1) ^ 0xEDB8_8320} else {crc >> 1};
+        }
+    }
+    return !crc;
+}
+
+fn adler32(data:&[u8])->u32{
+    const MOD_ADLER:u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data{
+        a = (a + *byte as u32) % MOD_ADLER;
+        b = (b + a) % MOD_ADLER;
+    }
+    return (b << 16) | a;
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn png_checksums(){
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+
+    #[test]
+    fn bmp_rows_are_padded(){
+        let file = encode_bmp(3, 2, &[[1, 2, 3];6]);
+        // Each row is 9 bytes padded to 12
+        assert_eq!(file.len(), 54 + 24);
+        assert_eq!(&file[54..57], &[3, 2, 1]);
+    }
+}
@@ -1,10 +1,15 @@
 use log::info;
 
-use magenboy_core::{AudioDevice, Bootrom, GameBoy, JoypadProvider, Mode, GBC_BOOT_ROM_SIZE, GB_BOOT_ROM_SIZE};
+use magenboy_core::{AudioDevice, Bootrom, Cheat, GameBoy, JoypadProvider, Mode, RumbleDevice, TiltProvider, GBC_BOOT_ROM_SIZE, GB_BOOT_ROM_SIZE};
 #[cfg(feature = "dbg")]
 use magenboy_core::debugger::DebuggerInterface;
 
-use crate::{mbc_handler::{initialize_mbc, release_mbc}, menu::MagenBoyState, mpmc_gfx_device::MpmcGfxDevice};        
+use magenboy_core::{mmu::carts::Mbc, SerialDevice};
+
+use crate::{mbc_handler::{initialize_mbc, release_mbc, RomLoadError}, menu::MagenBoyState, mpmc_gfx_device::MpmcGfxDevice, rewind::RewindBuffer, camera_image_file::ImageFileSource, tcp_link::TcpLinkDevice, gb_printer::{GbPrinter, GbPrinterHandle}, link_cable::{create_infrared_link, create_link_cable, LinkedGameBoys}};        
+
+const REWIND_SNAPSHOT_INTERVAL:u32 = 1;
+const REWIND_BUFFER_MAX_SIZE:usize = 0x400_0000;
 
 pub fn check_for_terminal_feature_flag(args:&Vec::<String>, flag:&str)->bool{
     args.len() >= 3 && args.contains(&String::from(flag))
@@ -24,8 +29,10 @@ pub fn init_and_run_gameboy(
     spsc_gfx_device: MpmcGfxDevice, 
     joypad_provider: impl JoypadProvider,
     audio_devices: impl AudioDevice,
+    rumble_device: Option<&'static mut dyn RumbleDevice>,
+    tilt_provider: Option<&'static mut dyn TiltProvider>,
     #[cfg(feature = "dbg")] dui: impl DebuggerInterface
-){
+)->Result<(), RomLoadError>{
     let bootrom_path = if check_for_terminal_feature_flag(&args, "--bootrom"){
         Some(get_terminal_feature_flag_value(&args, "--bootrom", "Error! you must specify a value for the --bootrom parameter"))
     }else{
@@ -49,37 +56,205 @@ pub fn init_and_run_gameboy(
         }
     });
 
-    let mbc = initialize_mbc(&program_name);
+    let patch_path = check_for_terminal_feature_flag(&args, "--patch")
+        .then(|| get_terminal_feature_flag_value(&args, "--patch", "Error! you must specify a value for the --patch parameter"));
+    let mbc = initialize_mbc(&program_name, patch_path.as_ref())?;
+    if let Some(device) = rumble_device{
+        mbc.set_rumble_device(device);
+    }
+    if let Some(provider) = tilt_provider{
+        mbc.set_tilt_provider(provider);
+    }
+    if check_for_terminal_feature_flag(&args, "--camera-image"){
+        let path = get_terminal_feature_flag_value(&args, "--camera-image", "Error! you must specify a value for the --camera-image parameter");
+        match ImageFileSource::new(&path){
+            Ok(source) => mbc.set_camera_image_source(Box::leak(Box::new(source))),
+            Err(err) => log::error!("Failed to load the camera image, using the test pattern instead: {}", err)
+        }
+    }
 
     let mut gameboy = match bootrom{
         Some(b) => GameBoy::new_with_bootrom(mbc, joypad_provider, audio_devices, spsc_gfx_device, b, #[cfg(feature = "dbg")] dui),
         None => {
-            let mode = if check_for_terminal_feature_flag(&args, "--mode"){
-                let mode = get_terminal_feature_flag_value(&args, "--mode", "Error: Must specify a mode");
-                let mode = mode.as_str().try_into().expect(format!("Error! mode cannot be: {}", mode).as_str());
-                mode
-            }
-            else{
-                let mode = mbc.detect_preferred_mode();
-                log::info!("Could not find a mode flag, auto detected {}", <Mode as Into<&str>>::into(mode));
-                mode
-            };
+            let mode = get_mode(&args, mbc);
             GameBoy::new_with_mode(mbc, joypad_provider, audio_devices, spsc_gfx_device, mode, #[cfg(feature = "dbg")] dui)
         }
     };
 
+    let printer = check_for_terminal_feature_flag(&args, "--printer").then(||{
+        let output_directory = get_terminal_feature_flag_value(&args, "--printer", "Error! you must specify an output directory for the --printer parameter");
+        GbPrinterHandle::new(GbPrinter::new(output_directory.into()))
+    });
+    if let Some(serial_device) = create_serial_device(&args, printer.clone()){
+        gameboy.set_serial_device(serial_device);
+    }
+
     info!("initialized gameboy successfully!");
 
+    let cheats = read_cheats_file(&args);
+    let mut cheats_enabled = false;
+
+    // Taking a snapshot every frame is not free so rewind is opt in
+    let mut rewind_buffer = check_for_terminal_feature_flag(&args, "--rewind")
+        .then(|| RewindBuffer::new(gameboy.save_state_size(), REWIND_SNAPSHOT_INTERVAL, REWIND_BUFFER_MAX_SIZE));
+
     EMULATOR_STATE.running.store(true, std::sync::atomic::Ordering::Relaxed);
     while EMULATOR_STATE.running.load(std::sync::atomic::Ordering::Relaxed){
         if !EMULATOR_STATE.pause.load(std::sync::atomic::Ordering::SeqCst){
             // Locking the state mutex in order to signal the menu that we are cycling a frame now
             let state = &EMULATOR_STATE;
             let _mutex_ctx = state.state_mutex.lock().unwrap();
-            gameboy.cycle_frame();
+            // The cheats can be toggled from the pause menu
+            if state.cheats_enabled.load(std::sync::atomic::Ordering::Relaxed) != cheats_enabled{
+                cheats_enabled = !cheats_enabled;
+                gameboy.clear_cheats();
+                if cheats_enabled{
+                    for cheat in &cheats{
+                        if !gameboy.add_cheat(*cheat){
+                            log::warn!("Too many cheats, ignoring: {:?}", cheat);
+                        }
+                    }
+                }
+            }
+            match rewind_buffer.as_mut(){
+                Some(rewind_buffer) if state.rewind.load(std::sync::atomic::Ordering::Relaxed) => {
+                    if let Some(snapshot) = rewind_buffer.step_back(){
+                        gameboy.load_state(snapshot).expect("Error! failed to load a rewind snapshot");
+                    }
+                    // Cycling a frame in order to draw the restored state to the screen
+                    gameboy.cycle_frame();
+                }
+                Some(rewind_buffer) => {
+                    gameboy.cycle_frame();
+                    if let Err(err) = rewind_buffer.push_frame(|buffer| gameboy.save_state(buffer)){
+                        log::error!("Failed to take a rewind snapshot: {:?}", err);
+                    }
+                }
+                None => gameboy.cycle_frame()
+            }
         }
     }
     drop(gameboy);
+    // The printer is leaked with the gameboy so the last print is saved explicitly
+    if let Some(printer) = printer{
+        printer.flush();
+    }
     release_mbc(&program_name, mbc);
     log::info!("released the gameboy succefully");
-}
\ No newline at end of file
+    return Ok(());
+}
+
+/// Runs 2 gameboys connected with a link cable, bootroms and the cartridge peripherals are not supported in this mode
+pub fn init_and_run_linked_gameboys<JP:JoypadProvider, AD:AudioDevice>(
+    args: Vec<String>,
+    program_names: [String;2],
+    gfx_devices: [MpmcGfxDevice;2],
+    joypad_providers: [JP;2],
+    audio_devices: [AD;2],
+    #[cfg(feature = "dbg")] duis: (impl DebuggerInterface, impl DebuggerInterface)
+)->Result<(), RomLoadError>{
+    let [first_program_name, second_program_name] = program_names;
+    let [first_gfx_device, second_gfx_device] = gfx_devices;
+    let [first_joypad_provider, second_joypad_provider] = joypad_providers;
+    let [first_audio_device, second_audio_device] = audio_devices;
+
+    let first_mbc = initialize_mbc(&first_program_name, None)?;
+    let second_mbc = initialize_mbc(&second_program_name, None)?;
+    let first_mode = get_mode(&args, first_mbc);
+    let second_mode = get_mode(&args, second_mbc);
+    let mut first_gameboy = GameBoy::new_with_mode(first_mbc, first_joypad_provider, first_audio_device, first_gfx_device, first_mode, #[cfg(feature = "dbg")] duis.0);
+    let mut second_gameboy = GameBoy::new_with_mode(second_mbc, second_joypad_provider, second_audio_device, second_gfx_device, second_mode, #[cfg(feature = "dbg")] duis.1);
+
+    // The ports are leaked since the machines holds them for the rest of the program
+    let (first_port, second_port) = create_link_cable();
+    first_gameboy.set_serial_device(Box::leak(Box::new(first_port)));
+    second_gameboy.set_serial_device(Box::leak(Box::new(second_port)));
+    let (first_infrared_port, second_infrared_port) = create_infrared_link();
+    first_gameboy.set_infrared_device(Box::leak(Box::new(first_infrared_port)));
+    second_gameboy.set_infrared_device(Box::leak(Box::new(second_infrared_port)));
+    let mut linked_gameboys = LinkedGameBoys::new(first_gameboy, second_gameboy);
+
+    info!("initialized the linked gameboys successfully!");
+
+    EMULATOR_STATE.running.store(true, std::sync::atomic::Ordering::Relaxed);
+    while EMULATOR_STATE.running.load(std::sync::atomic::Ordering::Relaxed){
+        if !EMULATOR_STATE.pause.load(std::sync::atomic::Ordering::SeqCst){
+            // Locking the state mutex in order to signal the menu that we are cycling a frame now
+            let _mutex_ctx = EMULATOR_STATE.state_mutex.lock().unwrap();
+            linked_gameboys.cycle_frame();
+        }
+    }
+    drop(linked_gameboys);
+    release_mbc(&first_program_name, first_mbc);
+    // In case both sides run the same game only the first save is kept
+    if second_program_name != first_program_name{
+        release_mbc(&second_program_name, second_mbc);
+    }
+    log::info!("released the gameboys succefully");
+    return Ok(());
+}
+
+// The device is leaked since the gameboy holds it for the rest of the program
+fn create_serial_device(args:&Vec<String>, printer:Option<GbPrinterHandle>)->Option<&'static mut dyn SerialDevice>{
+    let link_requested = check_for_terminal_feature_flag(args, "--link-host") || check_for_terminal_feature_flag(args, "--link-connect");
+    if let Some(printer) = printer{
+        // Both are connected to the serial port, so only one of them can be used
+        if link_requested{
+            std::panic!("Error! the --printer parameter cannot be combined with --link-host or --link-connect");
+        }
+        return Some(Box::leak(Box::new(printer)));
+    }
+    let link_device = if check_for_terminal_feature_flag(args, "--link-host"){
+        let port = get_terminal_feature_flag_value(args, "--link-host", "Error! you must specify a port for the --link-host parameter");
+        let port = port.parse().expect(format!("Error! invalid port: {}", port).as_str());
+        TcpLinkDevice::host(port)
+    }
+    else if check_for_terminal_feature_flag(args, "--link-connect"){
+        let address = get_terminal_feature_flag_value(args, "--link-connect", "Error! you must specify an address for the --link-connect parameter");
+        TcpLinkDevice::connect(address.as_str())
+    }
+    else{
+        return None;
+    };
+    return match link_device{
+        Ok(device) => Some(Box::leak(Box::new(device))),
+        Err(err) => {
+            log::error!("Failed to connect the link cable, running without it: {}", err);
+            None
+        }
+    };
+}
+
+// One code per line, the rest of the line can be used to describe the code and lines starting with # are ignored
+fn read_cheats_file(args:&Vec<String>)->Vec<Cheat>{
+    if !check_for_terminal_feature_flag(args, "--cheats"){
+        return Vec::new();
+    }
+    let path = get_terminal_feature_flag_value(args, "--cheats", "Error! you must specify a value for the --cheats parameter");
+    let file = match std::fs::read_to_string(&path){
+        Ok(file) => file,
+        Err(err) => {
+            log::error!("Failed to read the cheats file, running without cheats: {}", err);
+            return Vec::new();
+        }
+    };
+    let mut cheats = Vec::new();
+    for code in file.lines().filter_map(|line|line.split_whitespace().next()).filter(|code|!code.starts_with('#')){
+        match Cheat::try_from(code){
+            Ok(cheat) => cheats.push(cheat),
+            Err(_) => log::warn!("Invalid cheat code: {}", code)
+        }
+    }
+    log::info!("Loaded {} cheats", cheats.len());
+    return cheats;
+}
+
+pub fn get_mode(args:&Vec<String>, mbc:&dyn Mbc)->Mode{
+    if check_for_terminal_feature_flag(args, "--mode"){
+        let mode = get_terminal_feature_flag_value(args, "--mode", "Error: Must specify a mode");
+        return mode.as_str().try_into().expect(format!("Error! mode cannot be: {}", mode).as_str());
+    }
+    let mode = mbc.detect_preferred_mode();
+    log::info!("Could not find a mode flag, auto detected {}", <Mode as Into<&str>>::into(mode));
+    return mode;
+}
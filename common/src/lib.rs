@@ -2,6 +2,11 @@
 
 cfg_if::cfg_if!{ if #[cfg(feature = "std")] {
     pub mod mbc_handler;
+    pub mod rom_patch;
+    pub mod camera_image_file;
+    pub mod image_file_writer;
+    pub mod gb_printer;
+    pub mod tcp_link;
     pub mod mpmc_gfx_device;
     pub mod logging;
     pub mod initialization;
@@ -10,7 +15,9 @@ cfg_if::cfg_if!{ if #[cfg(feature = "std")] {
 
 cfg_if::cfg_if!{ if #[cfg(feature = "alloc")] {
     extern crate alloc;
-    
+
+    pub mod rewind;
+    pub mod link_cable;
     pub mod audio{
         mod audio_resampler;
         mod manual_audio_resampler;
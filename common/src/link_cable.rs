@@ -0,0 +1,190 @@
+use alloc::rc::Rc;
+use core::cell::RefCell;
+
+use magenboy_core::{AudioDevice, GameBoy, GfxDevice, InfraredDevice, JoypadProvider, SerialDevice};
+#[cfg(feature = "dbg")]
+use magenboy_core::debugger::DebuggerInterface;
+
+// What the serial line reads when the partner is not listening
+const DISCONNECTED_VALUE:u8 = 0xFF;
+
+#[derive(Default)]
+struct LinkPortState{
+    // The byte the port is waiting to exchange with an external clock
+    waiting_byte:Option<u8>,
+    incoming_byte:Option<u8>
+}
+
+/// One end of an in process link cable, created in pairs by `create_link_cable`
+pub struct LinkCablePort{
+    cable:Rc<RefCell<[LinkPortState;2]>>,
+    index:usize
+}
+
+pub fn create_link_cable()->(LinkCablePort, LinkCablePort){
+    let cable = Rc::new(RefCell::new([LinkPortState::default(), LinkPortState::default()]));
+    return (LinkCablePort{cable:cable.clone(), index:0}, LinkCablePort{cable, index:1});
+}
+
+impl SerialDevice for LinkCablePort{
+    fn transfer_internal(&mut self, data:u8)->u8{
+        let mut cable = self.cable.borrow_mut();
+        let partner = &mut cable[1 - self.index];
+        // When the partner is not waiting for a transfer it wont shift the byte in
+        let Some(received) = partner.waiting_byte.take() else {return DISCONNECTED_VALUE};
+        partner.incoming_byte = Some(data);
+        return received;
+    }
+
+    fn transfer_external(&mut self, data:u8)->Option<u8>{
+        let mut cable = self.cable.borrow_mut();
+        let port = &mut cable[self.index];
+        if let Some(received) = port.incoming_byte.take(){
+            port.waiting_byte = None;
+            return Some(received);
+        }
+        port.waiting_byte = Some(data);
+        return None;
+    }
+}
+
+/// One side of an in process infrared link, each side senses the led of the other one
+pub struct InfraredLinkPort{
+    leds:Rc<RefCell<[bool;2]>>,
+    index:usize
+}
+
+pub fn create_infrared_link()->(InfraredLinkPort, InfraredLinkPort){
+    let leds = Rc::new(RefCell::new([false;2]));
+    return (InfraredLinkPort{leds:leds.clone(), index:0}, InfraredLinkPort{leds, index:1});
+}
+
+impl InfraredDevice for InfraredLinkPort{
+    fn set_led(&mut self, enabled:bool){
+        self.leds.borrow_mut()[self.index] = enabled;
+    }
+
+    fn receive(&mut self)->bool{
+        self.leds.borrow()[1 - self.index]
+    }
+}
+
+/// A machine that can be stepped a single instruction at a time
+pub trait LockstepMachine{
+    fn poll_joypad_state(&mut self);
+    /// Returns true when a frame has completed and the time the step took in double speed M-cycles
+    fn cycle_step(&mut self)->(bool, u32);
+}
+
+// A single speed M-cycle is 2 double speed M-cycles
+fn double_speed_m_cycles(m_cycles:u32, double_speed:bool)->u32{
+    if double_speed {m_cycles} else {m_cycles * 2}
+}
+
+cfg_if::cfg_if!{ if #[cfg(feature = "dbg")]{
+    impl<'a, JP:JoypadProvider, AD:AudioDevice, GFX:GfxDevice, DUI:DebuggerInterface> LockstepMachine for GameBoy<'a, JP, AD, GFX, DUI>{
+        fn poll_joypad_state(&mut self){GameBoy::poll_joypad_state(self)}
+        fn cycle_step(&mut self)->(bool, u32){
+            let (frame_done, m_cycles) = GameBoy::cycle_step(self);
+            return (frame_done, double_speed_m_cycles(m_cycles, self.cpu().double_speed));
+        }
+    }
+}else{
+    impl<'a, JP:JoypadProvider, AD:AudioDevice, GFX:GfxDevice> LockstepMachine for GameBoy<'a, JP, AD, GFX>{
+        fn poll_joypad_state(&mut self){GameBoy::poll_joypad_state(self)}
+        fn cycle_step(&mut self)->(bool, u32){
+            let (frame_done, m_cycles) = GameBoy::cycle_step(self);
+            return (frame_done, double_speed_m_cycles(m_cycles, self.cpu().double_speed));
+        }
+    }
+}}
+
+/// Runs 2 machines connected with a link cable on the same thread.
+/// The machine that is behind in time is always the one stepped so both clocks stay within a single instruction of each other,
+/// the frame pace is set by the first machine and the second just follows it.
+pub struct LinkedGameBoys<M1:LockstepMachine, M2:LockstepMachine>{
+    pub first:M1,
+    pub second:M2,
+    // How far the first machine is ahead of the second, in double speed M-cycles
+    first_lead_cycles:i64
+}
+
+impl<M1:LockstepMachine, M2:LockstepMachine> LinkedGameBoys<M1, M2>{
+    /// The machines should already be connected with the ports of a link cable
+    pub fn new(first:M1, second:M2)->Self{
+        Self { first, second, first_lead_cycles: 0 }
+    }
+
+    pub fn cycle_frame(&mut self){
+        self.first.poll_joypad_state();
+        self.second.poll_joypad_state();
+        loop{
+            if self.first_lead_cycles <= 0{
+                let (frame_done, cycles) = self.first.cycle_step();
+                self.first_lead_cycles += cycles as i64;
+                if frame_done{
+                    break;
+                }
+            }
+            else{
+                let (_, cycles) = self.second.cycle_step();
+                self.first_lead_cycles -= cycles as i64;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn bytes_are_exchanged_only_when_the_partner_is_waiting(){
+        let (mut master, mut slave) = create_link_cable();
+        assert_eq!(master.transfer_internal(0x12), DISCONNECTED_VALUE);
+        assert_eq!(slave.transfer_external(0x34), None);
+
+        assert_eq!(master.transfer_internal(0x56), 0x34);
+        assert_eq!(slave.transfer_external(0x34), Some(0x56));
+        assert_eq!(slave.transfer_external(0x78), None);
+    }
+
+    // Takes a fixed amount of cycles every step and completes a frame every `frame_cycles`
+    struct StubMachine{
+        step_cycles:u32,
+        frame_cycles:u32,
+        elapsed_cycles:u32
+    }
+
+    impl LockstepMachine for StubMachine{
+        fn poll_joypad_state(&mut self){}
+        fn cycle_step(&mut self)->(bool, u32){
+            self.elapsed_cycles += self.step_cycles;
+            return (self.elapsed_cycles % self.frame_cycles < self.step_cycles, self.step_cycles);
+        }
+    }
+
+    #[test]
+    fn machines_are_kept_in_time_lockstep(){
+        let first = StubMachine{step_cycles: 2, frame_cycles: 1000, elapsed_cycles: 0};
+        // The first machine advances in short steps while the second runs long instructions
+        let second = StubMachine{step_cycles: 12, frame_cycles: 1000, elapsed_cycles: 0};
+        let mut linked = LinkedGameBoys::new(first, second);
+        for _ in 0..100{
+            linked.cycle_frame();
+            let difference = linked.first.elapsed_cycles as i64 - linked.second.elapsed_cycles as i64;
+            assert!(difference.abs() <= 12, "the machines drifted {} cycles apart", difference);
+        }
+        assert_eq!(linked.first.elapsed_cycles, 100 * 1000);
+    }
+
+    #[test]
+    fn infrared_senses_only_the_partner_led(){
+        let (mut first, mut second) = create_infrared_link();
+        first.set_led(true);
+        assert!(!first.receive());
+        assert!(second.receive());
+        first.set_led(false);
+        assert!(!second.receive());
+    }
+}
@@ -1,14 +1,139 @@
 use magenboy_core::mmu::carts::*;
-use std::fs;
+use std::{fs, io::{Read, Seek}, path::Path, time::{SystemTime, UNIX_EPOCH}};
 use log::info;
 
+use crate::rom_patch::{apply_patch, find_patch_file};
+
 pub const SAVE_SUFFIX:&str = ".sav";
 
-pub fn initialize_mbc(program_name:&String)->&'static mut dyn Mbc{
-    let program = fs::read(program_name).expect(format!("No program found - {}\n", program_name).as_str());
+/// Loading a rom from the disk can fail before the cartridge is even parsed
+#[derive(Debug, PartialEq)]
+pub enum RomLoadError{
+    Io{path:String, error:String},
+    /// A corrupted zip or gzip archive or one without a rom
+    Archive{path:String, error:String},
+    /// Only a patch that was explicitly requested fails the loading
+    Patch{path:String, error:String},
+    Cartridge(CartridgeError)
+}
+
+impl std::fmt::Display for RomLoadError{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self{
+            RomLoadError::Io{path, error} => write!(f, "could not read {}: {}", path, error),
+            RomLoadError::Archive{path, error} => write!(f, "could not extract the rom from {}: {}", path, error),
+            RomLoadError::Patch{path, error} => write!(f, "could not apply the patch {}: {}", path, error),
+            RomLoadError::Cartridge(error) => write!(f, "{}", error)
+        }
+    }
+}
+
+impl From<CartridgeError> for RomLoadError{
+    fn from(error: CartridgeError) -> Self {
+        RomLoadError::Cartridge(error)
+    }
+}
+
+/// When no patch path is passed a patch with the same name as the rom is applied if exists
+pub fn initialize_mbc(program_name:&String, patch_path:Option<&String>)->Result<&'static mut dyn Mbc, RomLoadError>{
+    let program = read_program(program_name)?;
+    let program = patch_program(program, program_name, patch_path)?;
     let save_data = try_get_save_data(program_name);
-    let save_data = if let Some(sd) = &save_data{Some(&sd[..])}else{None};
-    return magenboy_core::machine::mbc_initializer::initialize_mbc(&program, save_data);
+    let (save_data, rtc_footer) = match &save_data{
+        Some(sd)=>{
+            let (ram, footer) = split_rtc_footer(sd);
+            (Some(ram), footer)
+        }
+        None=>(None, None)
+    };
+    let mbc = magenboy_core::machine::mbc_initializer::initialize_mbc(&program, save_data)?;
+    if let Some(footer) = rtc_footer{
+        mbc.load_rtc_footer(footer, get_current_timestamp());
+    }
+    return Ok(mbc);
+}
+
+/// Used to display the game name before loading it, returns None when the file is not a valid rom
+pub fn read_cartridge_header(program_name:&str)->Option<CartridgeHeader>{
+    // Only the header is needed so avoiding reading (and decompressing) the whole rom
+    let program = read_program_start(program_name, HEADER_SIZE as u64).ok()?;
+    return CartridgeHeader::parse(&program).ok();
+}
+
+/// Reads the rom from the file, zip and gzip archives are extracted
+pub fn read_program(program_name:&str)->Result<Vec<u8>, RomLoadError>{
+    return read_program_start(program_name, u64::MAX);
+}
+
+// Reads up to max_size bytes from the start of the rom, archives are decompressed only up to that point
+fn read_program_start(program_name:&str, max_size:u64)->Result<Vec<u8>, RomLoadError>{
+    let io_error = |e:std::io::Error|RomLoadError::Io{path: String::from(program_name), error: e.to_string()};
+    let file = fs::File::open(program_name).map_err(io_error)?;
+    let extension = Path::new(program_name).extension().and_then(std::ffi::OsStr::to_str).map(|e|e.to_ascii_lowercase());
+    let program = match extension.as_deref(){
+        Some("zip") => extract_zip(file, max_size),
+        Some("gz") => extract_gzip(file, max_size),
+        _ => {
+            let mut program = Vec::new();
+            file.take(max_size).read_to_end(&mut program).map_err(io_error)?;
+            return Ok(program);
+        }
+    };
+    return program.map_err(|error|RomLoadError::Archive{path: String::from(program_name), error});
+}
+
+// Extracts the first gb or gbc rom in the archive
+fn extract_zip(file:impl Read + Seek, max_size:u64)->Result<Vec<u8>, String>{
+    let mut archive = zip::ZipArchive::new(file).map_err(|e|e.to_string())?;
+    for i in 0..archive.len(){
+        let entry = archive.by_index(i).map_err(|e|e.to_string())?;
+        let is_rom = Path::new(entry.name()).extension().and_then(std::ffi::OsStr::to_str)
+            .is_some_and(|e| e.eq_ignore_ascii_case("gb") || e.eq_ignore_ascii_case("gbc"));
+        if entry.is_file() && is_rom{
+            info!("loading {} from the zip archive", entry.name());
+            let mut program = Vec::with_capacity(entry.size().min(max_size) as usize);
+            entry.take(max_size).read_to_end(&mut program).map_err(|e|e.to_string())?;
+            return Ok(program);
+        }
+    }
+    return Err(String::from("The zip archive does not contain a gb or gbc rom"));
+}
+
+fn extract_gzip(file:impl Read, max_size:u64)->Result<Vec<u8>, String>{
+    let mut program = Vec::new();
+    flate2::read::GzDecoder::new(file).take(max_size).read_to_end(&mut program).map_err(|e|e.to_string())?;
+    return Ok(program);
+}
+
+fn patch_program(program:Vec<u8>, program_name:&String, patch_path:Option<&String>)->Result<Vec<u8>, RomLoadError>{
+    match patch_path{
+        // A patch that was explicitly requested must be applied
+        Some(path)=>{
+            let patch_error = |error:String| RomLoadError::Patch{path: path.clone(), error};
+            let patch = fs::read(path).map_err(|e|patch_error(e.to_string()))?;
+            let program = apply_patch(&program, &patch).map_err(patch_error)?;
+            info!("applied patch {}", path);
+            return Ok(program);
+        }
+        None=>{
+            let Some(path) = find_patch_file(program_name) else {return Ok(program)};
+            let Ok(patch) = fs::read(&path) else {return Ok(program)};
+            return Ok(match apply_patch(&program, &patch){
+                Ok(patched)=>{
+                    info!("applied patch {}", path);
+                    patched
+                }
+                Err(err)=>{
+                    log::error!("Failed to apply the patch {}, loading the rom unpatched: {}", path, err);
+                    program
+                }
+            });
+        }
+    }
+}
+
+fn get_current_timestamp()->u64{
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d|d.as_secs())
 }
 
 fn try_get_save_data(name:&String)->Option<Vec<u8>>{
@@ -21,10 +146,66 @@ fn try_get_save_data(name:&String)->Option<Vec<u8>>{
 
 pub fn release_mbc<'a>(program_name:&String, mbc: &'a mut dyn Mbc){
     if mbc.has_battery(){
-        while fs::write(format!("{}{}", program_name, ".sav"), mbc.get_ram()).is_err() {}       
+        let mut save_data = mbc.get_ram().to_vec();
+        if let Some(footer) = mbc.get_rtc_footer(get_current_timestamp()){
+            save_data.extend_from_slice(&footer);
+        }
+        while fs::write(format!("{}{}", program_name, ".sav"), &save_data).is_err() {}       
         info!("saved succesfully");
     }
     else{
         info!("No battery detected, no save data created");
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests{
+    use std::io::Write;
+    use super::*;
+
+    #[test]
+    fn extract_the_first_rom_from_a_zip(){
+        let mut archive = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        archive.start_file("readme.txt", options).unwrap();
+        archive.write_all(b"not a rom").unwrap();
+        archive.start_file("game.GBC", options).unwrap();
+        archive.write_all(&[1, 2, 3, 4]).unwrap();
+        let file = archive.finish().unwrap().into_inner();
+
+        assert_eq!(extract_zip(std::io::Cursor::new(&file), u64::MAX).unwrap(), vec![1, 2, 3, 4]);
+        assert_eq!(extract_zip(std::io::Cursor::new(&file), 2).unwrap(), vec![1, 2]);
+    }
+
+    #[test]
+    fn extract_a_gzip(){
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&[5; 100]).unwrap();
+        let file = encoder.finish().unwrap();
+
+        assert_eq!(extract_gzip(file.as_slice(), u64::MAX).unwrap(), vec![5; 100]);
+        assert_eq!(extract_gzip(file.as_slice(), 10).unwrap(), vec![5; 10]);
+    }
+
+    #[test]
+    fn read_only_the_start_of_a_rom(){
+        let path = std::env::temp_dir().join("magenboy_large_rom.gb");
+        fs::write(&path, vec![7; 0x8000]).unwrap();
+        assert_eq!(read_program_start(&path.to_string_lossy(), HEADER_SIZE as u64).unwrap(), vec![7; HEADER_SIZE]);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn loading_failures_are_errors(){
+        let directory = std::env::temp_dir();
+        let missing_path = directory.join("magenboy_missing_rom.gb").to_string_lossy().into_owned();
+        assert!(matches!(read_program(&missing_path), Err(RomLoadError::Io{..})));
+
+        let corrupted_path = directory.join("magenboy_corrupted_rom.zip");
+        fs::write(&corrupted_path, b"not a zip").unwrap();
+        assert!(matches!(read_program(&corrupted_path.to_string_lossy()), Err(RomLoadError::Archive{..})));
+        fs::remove_file(corrupted_path).unwrap();
+
+        let missing_patch_path = directory.join("magenboy_missing_patch.ips").to_string_lossy().into_owned();
+        assert!(matches!(patch_program(vec![0;4], &missing_path, Some(&missing_patch_path)), Err(RomLoadError::Patch{..})));
+    }
+}
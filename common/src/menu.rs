@@ -9,7 +9,8 @@ pub struct MenuOption<T, S:AsRef<str>>{
 pub enum EmulatorMenuOption{
     Resume,
     Restart,
-    Shutdown
+    Shutdown,
+    ToggleCheats
 }
 
 pub const GAME_MENU_OPTIONS:[MenuOption<EmulatorMenuOption, &str>;3] = [
@@ -18,6 +19,14 @@ pub const GAME_MENU_OPTIONS:[MenuOption<EmulatorMenuOption, &str>;3] = [
     MenuOption{prompt:"Shutdown", value:EmulatorMenuOption::Shutdown}
 ];
 
+// The std frontends can also toggle the cheats loaded with the --cheats flag
+pub const EMULATION_MENU_OPTIONS:[MenuOption<EmulatorMenuOption, &str>;4] = [
+    MenuOption{prompt:"Resume", value:EmulatorMenuOption::Resume},
+    MenuOption{prompt:"Toggle cheats", value:EmulatorMenuOption::ToggleCheats},
+    MenuOption{prompt:"Restart", value:EmulatorMenuOption::Restart}, 
+    MenuOption{prompt:"Shutdown", value:EmulatorMenuOption::Shutdown}
+];
+
 cfg_if::cfg_if!{ if #[cfg(feature = "std")]{
     use std::{sync::{atomic::AtomicBool, Mutex}, path::PathBuf};
     use magenboy_core::{ppu::gfx_device::GfxDevice, keypad::joypad_provider::JoypadProvider};
@@ -28,12 +37,14 @@ cfg_if::cfg_if!{ if #[cfg(feature = "std")]{
         pub running:AtomicBool,
         pub pause:AtomicBool,
         pub exit:AtomicBool,
+        pub rewind:AtomicBool,
+        pub cheats_enabled:AtomicBool,
         pub state_mutex:Mutex<()>
     }
 
     impl MagenBoyState{
         pub const fn new() -> Self {
-            Self { running: AtomicBool::new(true), pause: AtomicBool::new(false), exit: AtomicBool::new(false), state_mutex: Mutex::new(()) }
+            Self { running: AtomicBool::new(true), pause: AtomicBool::new(false), exit: AtomicBool::new(false), rewind: AtomicBool::new(false), cheats_enabled: AtomicBool::new(true), state_mutex: Mutex::new(()) }
         }
     }
 
@@ -55,13 +66,18 @@ cfg_if::cfg_if!{ if #[cfg(feature = "std")]{
                     state.running.store(false, std::sync::atomic::Ordering::Relaxed);
                     state.exit.store(true, std::sync::atomic::Ordering::Relaxed);
                 },
+                EmulatorMenuOption::ToggleCheats => {
+                    let enabled = !state.cheats_enabled.load(std::sync::atomic::Ordering::Relaxed);
+                    state.cheats_enabled.store(enabled, std::sync::atomic::Ordering::Relaxed);
+                    log::info!("Cheats {}", if enabled {"enabled"} else {"disabled"});
+                }
             }
         }
 
         fn get_game_menu_selection<GFX:GfxDevice>(&mut self, state:&MagenBoyState,gfx_device:&mut GFX, emulation_framebuffer_channel:crossbeam_channel::Receiver<usize>)->&EmulatorMenuOption{
             let menu_renderer = joypad_gfx_menu::GfxDeviceMenuRenderer::new(gfx_device);
         
-            let mut menu = JoypadMenu::new(&GAME_MENU_OPTIONS, &self.header, menu_renderer);  
+            let mut menu = JoypadMenu::new(&EMULATION_MENU_OPTIONS, &self.header, menu_renderer);  
         
             // lock the mutex here to sync the 2 threads
             state.pause.store(true, std::sync::atomic::Ordering::SeqCst);
@@ -88,9 +104,14 @@ cfg_if::cfg_if!{ if #[cfg(feature = "std")]{
             let path = entry.path();
             if let Some(extension) = path.as_path().extension().and_then(std::ffi::OsStr::to_str){
                 match extension {
-                    "gb" | "gbc"=>{
+                    "gb" | "gbc" | "zip" | "gz"=>{
                         let filename = String::from(path.file_name().expect("Error should be a file").to_str().unwrap());
-                        let option = MenuOption{value: path, prompt: filename};
+                        // Prefer the game name from the header, homebrews usually has an empty title
+                        let prompt = crate::mbc_handler::read_cartridge_header(path.to_str().unwrap())
+                            .map(|header| String::from(header.title()))
+                            .filter(|title| !title.is_empty())
+                            .unwrap_or(filename);
+                        let option = MenuOption{value: path, prompt};
                         menu_options.push(option);
                     },
                     _=>{}
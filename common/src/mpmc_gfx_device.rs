@@ -1,19 +1,50 @@
-use magenboy_core::ppu::{gb_ppu::{SCREEN_HEIGHT, SCREEN_WIDTH}, gfx_device::{GfxDevice, Pixel}};
+use magenboy_core::{ppu::{gb_ppu::{BUFFERS_NUMBER, SCREEN_HEIGHT, SCREEN_WIDTH}, gfx_device::{GfxDevice, Pixel}}, SGB_SCREEN_HEIGHT, SGB_SCREEN_WIDTH};
 
 pub struct MpmcGfxDevice{
-    sender: crossbeam_channel::Sender<usize>
+    sender: crossbeam_channel::Sender<usize>,
+    // When set all the frames are sent in the SGB border size, frames without a border are padded
+    border_buffers: Option<Vec<[Pixel; SGB_SCREEN_HEIGHT * SGB_SCREEN_WIDTH]>>,
+    border_buffer_index: usize
 }
 
 impl MpmcGfxDevice{
     pub fn new(sender:crossbeam_channel::Sender<usize>)->Self{
-        Self{sender}
+        Self{sender, border_buffers: None, border_buffer_index: 0}
+    }
+
+    pub fn with_sgb_border(sender:crossbeam_channel::Sender<usize>)->Self{
+        Self{sender, border_buffers: Some(vec![[0; SGB_SCREEN_HEIGHT * SGB_SCREEN_WIDTH]; BUFFERS_NUMBER]), border_buffer_index: 0}
+    }
+
+    fn send(&self, buffer_address:usize){
+        if self.sender.send(buffer_address).is_err(){
+            log::debug!("The receiver endpoint has been closed");
+        }
     }
 }
 
 impl GfxDevice for MpmcGfxDevice{
     fn swap_buffer(&mut self, buffer:&[Pixel; SCREEN_HEIGHT * SCREEN_WIDTH]) {
-        if self.sender.send(buffer.as_ptr() as usize).is_err(){
-            log::debug!("The receiver endpoint has been closed");
+        let Some(border_buffers) = self.border_buffers.as_mut() else {
+            self.send(buffer.as_ptr() as usize);
+            return;
+        };
+        self.border_buffer_index = (self.border_buffer_index + 1) % BUFFERS_NUMBER;
+        let border_buffer = &mut border_buffers[self.border_buffer_index];
+        let (x_offset, y_offset) = ((SGB_SCREEN_WIDTH - SCREEN_WIDTH) / 2, (SGB_SCREEN_HEIGHT - SCREEN_HEIGHT) / 2);
+        for (y, line) in buffer.chunks_exact(SCREEN_WIDTH).enumerate(){
+            let start = ((y + y_offset) * SGB_SCREEN_WIDTH) + x_offset;
+            border_buffer[start..start + SCREEN_WIDTH].copy_from_slice(line);
         }
+        let buffer_address = border_buffer.as_ptr() as usize;
+        self.send(buffer_address);
+    }
+
+    fn sgb_border_supported(&self)->bool {
+        self.border_buffers.is_some()
     }
-}
\ No newline at end of file
+
+    fn swap_sgb_buffer(&mut self, buffer:&[Pixel; SGB_SCREEN_HEIGHT * SGB_SCREEN_WIDTH]) {
+        self.send(buffer.as_ptr() as usize);
+    }
+}
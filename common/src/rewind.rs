@@ -0,0 +1,179 @@
+use alloc::{collections::VecDeque, vec, vec::Vec};
+
+use magenboy_core::SaveStateError;
+
+/// Records the machine state every few frames and allows stepping back through them.
+/// Only the latest snapshot is stored in full, older ones are stored as the XOR against the next snapshot,
+/// most of the state barely changes between frames so the XOR is mostly zeros and is RLE compressed.
+pub struct RewindBuffer{
+    snapshot_interval:u32,
+    frames_counter:u32,
+    max_deltas_size:usize,
+    deltas_size:usize,
+    deltas:VecDeque<Vec<u8>>,
+    current_snapshot:Vec<u8>,
+    next_snapshot:Vec<u8>,
+    has_snapshot:bool
+}
+
+impl RewindBuffer{
+    pub fn new(state_size:usize, snapshot_interval:u32, max_deltas_size:usize)->Self{
+        Self{
+            snapshot_interval, max_deltas_size,
+            frames_counter:0, deltas_size:0, deltas:VecDeque::new(),
+            current_snapshot:vec![0;state_size], next_snapshot:vec![0;state_size],
+            has_snapshot:false
+        }
+    }
+
+    /// Should be called once every frame, takes a snapshot every snapshot_interval frames
+    pub fn push_frame<F:FnOnce(&mut [u8])->Result<usize, SaveStateError>>(&mut self, save_state:F)->Result<(), SaveStateError>{
+        self.frames_counter += 1;
+        if self.frames_counter < self.snapshot_interval{
+            return Ok(());
+        }
+        self.frames_counter = 0;
+
+        save_state(&mut self.next_snapshot)?;
+        if self.has_snapshot{
+            let delta = encode_delta(&self.current_snapshot, &self.next_snapshot);
+            self.deltas_size += delta.len();
+            self.deltas.push_back(delta);
+            while self.deltas_size > self.max_deltas_size{
+                let Some(oldest) = self.deltas.pop_front() else {break};
+                self.deltas_size -= oldest.len();
+            }
+        }
+        core::mem::swap(&mut self.current_snapshot, &mut self.next_snapshot);
+        self.has_snapshot = true;
+        return Ok(());
+    }
+
+    /// Steps back one snapshot and returns it, once the buffer is exhausted keeps returning the oldest snapshot
+    pub fn step_back(&mut self)->Option<&[u8]>{
+        if !self.has_snapshot{
+            return None;
+        }
+        if let Some(delta) = self.deltas.pop_back(){
+            self.deltas_size -= delta.len();
+            apply_delta(&mut self.current_snapshot, &delta);
+        }
+        self.frames_counter = 0;
+        return Some(&self.current_snapshot);
+    }
+
+    pub fn clear(&mut self){
+        self.deltas.clear();
+        self.deltas_size = 0;
+        self.frames_counter = 0;
+        self.has_snapshot = false;
+    }
+}
+
+// The delta is a sequence of (zeros run length, literals length, literals) where the literals are the non zero XORed bytes
+fn encode_delta(old:&[u8], new:&[u8])->Vec<u8>{
+    let mut output = Vec::new();
+    let mut i = 0;
+    while i < new.len(){
+        let zeros_start = i;
+        while i < new.len() && old[i] == new[i]{
+            i += 1;
+        }
+        let literals_start = i;
+        while i < new.len() && old[i] != new[i]{
+            i += 1;
+        }
+        write_varint(&mut output, literals_start - zeros_start);
+        write_varint(&mut output, i - literals_start);
+        output.extend(old[literals_start..i].iter().zip(&new[literals_start..i]).map(|(o, n)| o ^ n));
+    }
+    return output;
+}
+
+fn apply_delta(snapshot:&mut [u8], delta:&[u8]){
+    let mut snapshot_index = 0;
+    let mut delta_index = 0;
+    while delta_index < delta.len(){
+        snapshot_index += read_varint(delta, &mut delta_index);
+        let literals_length = read_varint(delta, &mut delta_index);
+        for i in 0..literals_length{
+            snapshot[snapshot_index + i] ^= delta[delta_index + i];
+        }
+        snapshot_index += literals_length;
+        delta_index += literals_length;
+    }
+}
+
+// LEB128, 7 bits per byte with the msb marking that more bytes follow
+fn write_varint(output:&mut Vec<u8>, mut value:usize){
+    while value >= 0x80{
+        output.push((value as u8 & 0x7F) | 0x80);
+        value >>= 7;
+    }
+    output.push(value as u8);
+}
+
+fn read_varint(input:&[u8], index:&mut usize)->usize{
+    let mut value = 0;
+    let mut shift = 0;
+    loop{
+        let byte = input[*index];
+        *index += 1;
+        value |= ((byte & 0x7F) as usize) << shift;
+        if byte & 0x80 == 0{
+            return value;
+        }
+        shift += 7;
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn save_byte(value:u8)->impl FnOnce(&mut [u8])->Result<usize, SaveStateError>{
+        move |buffer:&mut [u8]|{
+            buffer.fill(0);
+            buffer[300] = value;
+            buffer[301] = value;
+            return Ok(buffer.len());
+        }
+    }
+
+    #[test]
+    fn delta_round_trip(){
+        let old:Vec<u8> = (0..1000).map(|i| (i / 7) as u8).collect();
+        let mut new = old.clone();
+        new[0] = 0xFF;
+        new[500..700].fill(3);
+        new[999] = 0;
+        let delta = encode_delta(&old, &new);
+        assert!(delta.len() < 250);
+        let mut restored = new.clone();
+        apply_delta(&mut restored, &delta);
+        assert_eq!(restored, old);
+    }
+
+    #[test]
+    fn step_back_through_snapshots(){
+        let mut rewind = RewindBuffer::new(1000, 2, usize::MAX);
+        for value in 1..=6{
+            rewind.push_frame(save_byte(value)).unwrap();
+        }
+        assert_eq!(rewind.step_back().unwrap()[300], 4);
+        assert_eq!(rewind.step_back().unwrap()[301], 2);
+        assert_eq!(rewind.step_back().unwrap()[300], 2);
+    }
+
+    #[test]
+    fn oldest_snapshots_are_dropped(){
+        // Every delta here is 8 bytes long so only 2 of them fit
+        let mut rewind = RewindBuffer::new(1000, 1, 20);
+        for value in 1..=10{
+            rewind.push_frame(save_byte(value)).unwrap();
+        }
+        assert_eq!(rewind.step_back().unwrap()[300], 9);
+        assert_eq!(rewind.step_back().unwrap()[300], 8);
+        assert_eq!(rewind.step_back().unwrap()[300], 8);
+    }
+}
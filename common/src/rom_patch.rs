@@ -0,0 +1,295 @@
+use std::path::Path;
+
+use crate::image_file_writer::crc32;
+
+pub const PATCH_EXTENSIONS:[&str;3] = ["ips", "ups", "bps"];
+
+const IPS_MAGIC:&[u8] = b"PATCH";
+const IPS_EOF:&[u8] = b"EOF";
+const UPS_MAGIC:&[u8] = b"UPS1";
+const BPS_MAGIC:&[u8] = b"BPS1";
+// UPS and BPS end with the source, target and patch CRC32s
+const FOOTER_SIZE:usize = 12;
+
+/// Looks for a patch with the same name as the rom (game.gb -> game.ips/ups/bps)
+pub fn find_patch_file(rom_path:&str)->Option<String>{
+    return PATCH_EXTENSIONS.iter()
+        .map(|extension| Path::new(rom_path).with_extension(extension))
+        .find(|path| path.is_file())
+        .map(|path| path.to_string_lossy().into_owned());
+}
+
+/// Applies an IPS, UPS or BPS patch (detected by the patch header) and returns the patched rom
+pub fn apply_patch(rom:&[u8], patch:&[u8])->Result<Vec<u8>, String>{
+    if patch.starts_with(IPS_MAGIC){
+        return apply_ips(rom, patch);
+    }
+    if patch.starts_with(UPS_MAGIC){
+        return apply_ups(rom, patch);
+    }
+    if patch.starts_with(BPS_MAGIC){
+        return apply_bps(rom, patch);
+    }
+    return Err(String::from("Unsupported patch format, only IPS, UPS and BPS are supported"));
+}
+
+struct PatchReader<'a>{
+    data:&'a [u8],
+    position:usize
+}
+
+impl<'a> PatchReader<'a>{
+    fn new(data:&'a [u8], position:usize)->Self{
+        Self { data, position }
+    }
+
+    fn read_bytes(&mut self, size:usize)->Result<&'a [u8], String>{
+        let bytes = self.data.get(self.position..self.position + size).ok_or(String::from("Unexpected end of patch"))?;
+        self.position += size;
+        return Ok(bytes);
+    }
+
+    fn read_u8(&mut self)->Result<u8, String>{
+        return Ok(self.read_bytes(1)?[0]);
+    }
+
+    fn read_be(&mut self, size:usize)->Result<usize, String>{
+        return Ok(self.read_bytes(size)?.iter().fold(0, |value, byte| (value << 8) | *byte as usize));
+    }
+
+    // The variable length encoding of UPS and BPS, each continuation adds an implicit 1 so every number has a single encoding
+    fn read_number(&mut self)->Result<usize, String>{
+        let mut value:usize = 0;
+        let mut shift:usize = 1;
+        loop{
+            let byte = self.read_u8()?;
+            value = (byte as usize & 0x7F).checked_mul(shift).and_then(|v|v.checked_add(value)).ok_or(String::from("Number overflow"))?;
+            if byte & 0x80 != 0{
+                return Ok(value);
+            }
+            shift = shift.checked_shl(7).ok_or(String::from("Number overflow"))?;
+            value = value.checked_add(shift).ok_or(String::from("Number overflow"))?;
+        }
+    }
+}
+
+fn apply_ips(rom:&[u8], patch:&[u8])->Result<Vec<u8>, String>{
+    let mut output = rom.to_vec();
+    let mut reader = PatchReader::new(patch, IPS_MAGIC.len());
+    loop{
+        if reader.read_bytes(IPS_EOF.len())? == IPS_EOF{
+            break;
+        }
+        reader.position -= IPS_EOF.len();
+        let offset = reader.read_be(3)?;
+        let size = reader.read_be(2)?;
+        // A size of 0 marks a run length encoded record
+        let (size, data) = match size{
+            0 => {
+                let size = reader.read_be(2)?;
+                (size, vec![reader.read_u8()?; size])
+            }
+            _ => (size, reader.read_bytes(size)?.to_vec())
+        };
+        if output.len() < offset + size{
+            output.resize(offset + size, 0);
+        }
+        output[offset..offset + size].copy_from_slice(&data);
+    }
+    // Some IPS files has a truncation extension after the EOF marker
+    if let Ok(size) = reader.read_be(3){
+        output.truncate(size);
+    }
+    return Ok(output);
+}
+
+fn check_footer(rom:&[u8], patch:&[u8])->Result<(u32, u32), String>{
+    if patch.len() < FOOTER_SIZE{
+        return Err(String::from("Unexpected end of patch"));
+    }
+    let footer = &patch[patch.len() - FOOTER_SIZE..];
+    let read_crc = |index:usize| u32::from_le_bytes(footer[index * 4..(index + 1) * 4].try_into().unwrap());
+    if crc32(&patch[..patch.len() - 4]) != read_crc(2){
+        return Err(String::from("The patch is corrupted, checksum mismatch"));
+    }
+    let source_crc = read_crc(0);
+    if crc32(rom) != source_crc{
+        return Err(std::format!("The patch does not match this rom, expected rom checksum: {:#010X}", source_crc));
+    }
+    return Ok((source_crc, read_crc(1)));
+}
+
+fn check_output(output:&[u8], target_crc:u32)->Result<(), String>{
+    if crc32(output) != target_crc{
+        return Err(String::from("The patched rom checksum does not match the patch"));
+    }
+    return Ok(());
+}
+
+fn apply_ups(rom:&[u8], patch:&[u8])->Result<Vec<u8>, String>{
+    let (_, target_crc) = check_footer(rom, patch)?;
+    let mut reader = PatchReader::new(&patch[..patch.len() - FOOTER_SIZE], UPS_MAGIC.len());
+    let source_size = reader.read_number()?;
+    let target_size = reader.read_number()?;
+    if source_size != rom.len(){
+        return Err(std::format!("The patch does not match this rom, expected rom size: {:#X}", source_size));
+    }
+
+    let mut output = rom.to_vec();
+    output.resize(target_size, 0);
+    let mut offset = 0;
+    while reader.position < reader.data.len(){
+        offset += reader.read_number()?;
+        // XORed bytes until a zero byte
+        loop{
+            let value = reader.read_u8()?;
+            if offset < target_size{
+                output[offset] = value ^ rom.get(offset).copied().unwrap_or(0);
+            }
+            offset += 1;
+            if value == 0{
+                break;
+            }
+        }
+    }
+    check_output(&output, target_crc)?;
+    return Ok(output);
+}
+
+fn apply_bps(rom:&[u8], patch:&[u8])->Result<Vec<u8>, String>{
+    const SOURCE_READ:usize = 0;
+    const TARGET_READ:usize = 1;
+    const SOURCE_COPY:usize = 2;
+    const TARGET_COPY:usize = 3;
+
+    let (_, target_crc) = check_footer(rom, patch)?;
+    let mut reader = PatchReader::new(&patch[..patch.len() - FOOTER_SIZE], BPS_MAGIC.len());
+    let source_size = reader.read_number()?;
+    let target_size = reader.read_number()?;
+    let metadata_size = reader.read_number()?;
+    reader.read_bytes(metadata_size)?;
+    if source_size != rom.len(){
+        return Err(std::format!("The patch does not match this rom, expected rom size: {:#X}", source_size));
+    }
+
+    let mut output:Vec<u8> = Vec::with_capacity(target_size);
+    let mut source_offset:usize = 0;
+    let mut target_offset:usize = 0;
+    let out_of_bounds = || String::from("The patch points outside of the rom");
+    while reader.position < reader.data.len(){
+        let action = reader.read_number()?;
+        let length = (action >> 2) + 1;
+        match action & 3{
+            SOURCE_READ => {
+                let start = output.len();
+                output.extend_from_slice(rom.get(start..start + length).ok_or_else(out_of_bounds)?);
+            }
+            TARGET_READ => output.extend_from_slice(reader.read_bytes(length)?),
+            SOURCE_COPY | TARGET_COPY => {
+                // The relative offset is a sign and magnitude number
+                let relative = reader.read_number()?;
+                let offset = if action & 3 == SOURCE_COPY {&mut source_offset} else {&mut target_offset};
+                *offset = match relative & 1{
+                    0 => offset.checked_add(relative >> 1),
+                    _ => offset.checked_sub(relative >> 1)
+                }.ok_or_else(out_of_bounds)?;
+                if action & 3 == SOURCE_COPY{
+                    output.extend_from_slice(rom.get(*offset..*offset + length).ok_or_else(out_of_bounds)?);
+                    *offset += length;
+                }
+                else{
+                    // The copy can overlap the bytes it writes so it is done byte by byte
+                    for _ in 0..length{
+                        let value = *output.get(*offset).ok_or_else(out_of_bounds)?;
+                        output.push(value);
+                        *offset += 1;
+                    }
+                }
+            }
+            _ => unreachable!()
+        }
+        if output.len() > target_size{
+            return Err(String::from("The patch writes past the patched rom size"));
+        }
+    }
+    if output.len() != target_size{
+        return Err(String::from("The patched rom size does not match the patch"));
+    }
+    check_output(&output, target_crc)?;
+    return Ok(output);
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn encode_number(mut value:usize, output:&mut Vec<u8>){
+        loop{
+            let byte = (value & 0x7F) as u8;
+            value >>= 7;
+            if value == 0{
+                output.push(byte | 0x80);
+                return;
+            }
+            output.push(byte);
+            value -= 1;
+        }
+    }
+
+    fn append_footer(patch:&mut Vec<u8>, rom:&[u8], output:&[u8]){
+        patch.extend_from_slice(&crc32(rom).to_le_bytes());
+        patch.extend_from_slice(&crc32(output).to_le_bytes());
+        let patch_crc = crc32(patch);
+        patch.extend_from_slice(&patch_crc.to_le_bytes());
+    }
+
+    #[test]
+    fn ips_records_and_rle(){
+        let rom = [0u8;8];
+        let mut patch = IPS_MAGIC.to_vec();
+        patch.extend_from_slice(&[0, 0, 1, 0, 2, 0xAA, 0xBB]);
+        // RLE record that grows the rom
+        patch.extend_from_slice(&[0, 0, 7, 0, 0, 0, 3, 0xCC]);
+        patch.extend_from_slice(IPS_EOF);
+        assert_eq!(apply_patch(&rom, &patch).unwrap(), vec![0, 0xAA, 0xBB, 0, 0, 0, 0, 0xCC, 0xCC, 0xCC]);
+    }
+
+    #[test]
+    fn ups_xors_the_rom(){
+        let rom = [1u8, 2, 3, 4];
+        let expected = [1u8, 7, 3, 4, 9];
+        let mut patch = UPS_MAGIC.to_vec();
+        encode_number(rom.len(), &mut patch);
+        encode_number(expected.len(), &mut patch);
+        encode_number(1, &mut patch);
+        patch.extend_from_slice(&[2 ^ 7, 0]);
+        encode_number(1, &mut patch);
+        patch.extend_from_slice(&[9, 0]);
+        append_footer(&mut patch, &rom, &expected);
+        assert_eq!(apply_patch(&rom, &patch).unwrap(), expected.to_vec());
+
+        // A different rom fails the checksum
+        assert!(apply_patch(&[1, 2, 3, 5], &patch).is_err());
+    }
+
+    #[test]
+    fn bps_actions(){
+        let rom = [1u8, 2, 3, 4];
+        let expected = [1u8, 2, 9, 9, 9, 3, 4];
+        let mut patch = BPS_MAGIC.to_vec();
+        encode_number(rom.len(), &mut patch);
+        encode_number(expected.len(), &mut patch);
+        encode_number(0, &mut patch);
+        // Source read 2, target read 1, target copy 2 (overlapping), source copy 2 from offset 2
+        // The action is (length - 1) << 2 | command
+        encode_number(0b100, &mut patch);
+        encode_number(0b001, &mut patch);
+        patch.push(9);
+        encode_number(0b111, &mut patch);
+        encode_number(2 << 1, &mut patch);
+        encode_number(0b110, &mut patch);
+        encode_number(2 << 1, &mut patch);
+        append_footer(&mut patch, &rom, &expected);
+        assert_eq!(apply_patch(&rom, &patch).unwrap(), expected.to_vec());
+    }
+}
@@ -0,0 +1,316 @@
+use std::{io::{self, Read, Write}, net::{TcpListener, TcpStream, ToSocketAddrs}, sync::{Arc, Condvar, Mutex}, time::{Duration, Instant}};
+
+use magenboy_core::SerialDevice;
+
+const HANDSHAKE_MAGIC:[u8;4] = *b"MGBL";
+const PROTOCOL_VERSION:u8 = 2;
+// Generous in order to tolerate slow networks, once passed the partner is considered disconnected
+const REPLY_TIMEOUT:Duration = Duration::from_secs(5);
+// How long a partner that is not waiting for a transfer holds it before answering with 0xFF like a disconnected cable,
+// measured by the partner so the answer is delayed only by the network latency. A transfer takes about 1ms on hardware
+const PARTNER_READY_WINDOW:Duration = Duration::from_millis(5);
+const DISCONNECTED_VALUE:u8 = 0xFF;
+
+const TRANSFER_MESSAGE:u8 = 0;
+const REPLY_MESSAGE:u8 = 1;
+// Sent when this side gave up on its transfer so the partner will not complete it
+const CANCEL_MESSAGE:u8 = 2;
+// type, sequence number (u32) and data
+const MESSAGE_SIZE:usize = 6;
+
+#[derive(Default)]
+struct LinkState{
+    // The byte this side is ready to exchange, either waiting for an external clock or in the middle of its own transfer
+    waiting_byte:Option<u8>,
+    incoming_byte:Option<u8>,
+    // A transfer the partner started before this side was ready (sequence number, data and arrival time),
+    // answered once this side waits for an external clock or with 0xFF when the ready window passes
+    pending_transfer:Option<(u32, u8, Instant)>,
+    ready_window:Duration,
+    sequence_number:u32,
+    // Set while this side transfer is waiting for the partner reply
+    transfer_start:Option<Instant>,
+    reply:Option<u8>,
+    connected:bool
+}
+
+/// A link cable to another MagenBoy process over TCP.
+///
+/// Each side becomes the clock master when it starts a transfer with the internal clock, it sends its byte and the transfer stays pending
+/// (without stalling the emulation) until the partner replies.
+/// The replies are sent from a background thread, a partner that is not waiting for a transfer yet replies once it does
+/// or with 0xFF after a short window, in case both sides start a transfer at the same time both transfers complete with the exchanged bytes,
+/// just like on hardware. A partner that does not reply in time (a lost connection) is answered with 0xFF and the transfer is canceled.
+pub struct TcpLinkDevice{
+    // Both this side transfers and the replies from the reader thread are written through it
+    writer:Arc<Mutex<TcpStream>>,
+    state:Arc<(Mutex<LinkState>, Condvar)>
+}
+
+impl TcpLinkDevice{
+    /// Blocks until the partner connects
+    pub fn host(port:u16)->io::Result<Self>{
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        log::info!("Waiting for the link partner on port {}", port);
+        let (stream, address) = listener.accept()?;
+        log::info!("Link partner connected from {}", address);
+        return Self::from_stream(stream);
+    }
+
+    pub fn connect(address:impl ToSocketAddrs)->io::Result<Self>{
+        let stream = TcpStream::connect(address)?;
+        log::info!("Connected to the link partner at {}", stream.peer_addr()?);
+        return Self::from_stream(stream);
+    }
+
+    pub fn from_stream(mut stream:TcpStream)->io::Result<Self>{
+        stream.set_nodelay(true)?;
+        stream.write_all(&HANDSHAKE_MAGIC)?;
+        stream.write_all(&[PROTOCOL_VERSION])?;
+        let mut handshake = [0;HANDSHAKE_MAGIC.len() + 1];
+        stream.read_exact(&mut handshake)?;
+        if handshake[..HANDSHAKE_MAGIC.len()] != HANDSHAKE_MAGIC{
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "The link partner is not a MagenBoy instance"));
+        }
+        if handshake[HANDSHAKE_MAGIC.len()] != PROTOCOL_VERSION{
+            return Err(io::Error::new(io::ErrorKind::InvalidData, std::format!("Unsupported link protocol version: {}", handshake[HANDSHAKE_MAGIC.len()])));
+        }
+
+        let state = Arc::new((Mutex::new(LinkState{connected:true, ready_window:PARTNER_READY_WINDOW, ..Default::default()}), Condvar::new()));
+        let reader_stream = stream.try_clone()?;
+        // Waking up the reader in order to expire pending transfers
+        reader_stream.set_read_timeout(Some(PARTNER_READY_WINDOW))?;
+        let writer = Arc::new(Mutex::new(stream));
+        let reader_writer = writer.clone();
+        let reader_state = state.clone();
+        std::thread::Builder::new()
+            .name("Link cable reader".to_string())
+            .spawn(move || Self::reader_loop(reader_stream, reader_writer, reader_state))?;
+
+        return Ok(Self{writer, state});
+    }
+
+    fn reader_loop(mut stream:TcpStream, writer:Arc<Mutex<TcpStream>>, state:Arc<(Mutex<LinkState>, Condvar)>){
+        let (state_lock, condvar) = &*state;
+        let mut message = [0;MESSAGE_SIZE];
+        // read_exact cant be used with a timeout since it might drop a partially read message
+        let mut message_length = 0;
+        loop{
+            match stream.read(&mut message[message_length..]){
+                Ok(0) => break,
+                Ok(length) => message_length += length,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut => {}
+                Err(_) => break
+            }
+            let mut state = state_lock.lock().unwrap();
+            let ready_window = state.ready_window;
+            if state.pending_transfer.is_some_and(|(_, _, arrival)|arrival.elapsed() >= ready_window){
+                let (sequence_number, _, _) = state.pending_transfer.take().unwrap();
+                if write_message(&mut writer.lock().unwrap(), REPLY_MESSAGE, sequence_number, DISCONNECTED_VALUE).is_err(){
+                    break;
+                }
+            }
+            if message_length < MESSAGE_SIZE{
+                continue;
+            }
+            message_length = 0;
+            let sequence_number = u32::from_le_bytes(message[1..5].try_into().unwrap());
+            match message[0]{
+                TRANSFER_MESSAGE => match state.waiting_byte.take(){
+                    Some(byte) => {
+                        state.incoming_byte = Some(message[5]);
+                        if write_message(&mut writer.lock().unwrap(), REPLY_MESSAGE, sequence_number, byte).is_err(){
+                            break;
+                        }
+                    }
+                    None => state.pending_transfer = Some((sequence_number, message[5], Instant::now()))
+                },
+                // Replies to transfers that already timed out are ignored
+                REPLY_MESSAGE if sequence_number == state.sequence_number => {
+                    state.reply = Some(message[5]);
+                    condvar.notify_all();
+                }
+                REPLY_MESSAGE => {}
+                CANCEL_MESSAGE => {
+                    if state.pending_transfer.is_some_and(|(pending_sequence_number, _, _)|pending_sequence_number == sequence_number){
+                        state.pending_transfer = None;
+                    }
+                }
+                _ => {
+                    log::error!("Received an invalid link message: {:?}", message);
+                    break;
+                }
+            }
+        }
+        log::warn!("The link partner disconnected");
+        state_lock.lock().unwrap().connected = false;
+        condvar.notify_all();
+    }
+}
+
+impl SerialDevice for TcpLinkDevice{
+    fn transfer_internal(&mut self, data:u8)->u8{
+        loop{
+            if let Some(received) = self.poll_transfer_internal(data){
+                return received;
+            }
+            let (state_lock, condvar) = &*self.state;
+            let state = state_lock.lock().unwrap();
+            let _ = condvar.wait_timeout_while(state, REPLY_TIMEOUT, |s|s.reply.is_none() && s.connected).unwrap();
+        }
+    }
+
+    fn poll_transfer_internal(&mut self, data:u8)->Option<u8>{
+        let mut state = self.state.0.lock().unwrap();
+        if !state.connected{
+            state.transfer_start = None;
+            state.waiting_byte = None;
+            return Some(DISCONNECTED_VALUE);
+        }
+        if state.transfer_start.is_none(){
+            state.sequence_number = state.sequence_number.wrapping_add(1);
+            state.reply = None;
+            state.waiting_byte = Some(data);
+            if write_message(&mut self.writer.lock().unwrap(), TRANSFER_MESSAGE, state.sequence_number, data).is_err(){
+                state.connected = false;
+                state.waiting_byte = None;
+                return Some(DISCONNECTED_VALUE);
+            }
+            state.transfer_start = Some(Instant::now());
+            return None;
+        }
+        if state.reply.is_none(){
+            if state.transfer_start.is_some_and(|start|start.elapsed() < REPLY_TIMEOUT){
+                return None;
+            }
+            log::warn!("The link partner did not reply in time");
+            // A reply that is already on its way is ignored by the sequence number check
+            if write_message(&mut self.writer.lock().unwrap(), CANCEL_MESSAGE, state.sequence_number, 0).is_err(){
+                state.connected = false;
+            }
+        }
+        state.transfer_start = None;
+        // In case the partner started a transfer at the same time its byte was already received
+        state.waiting_byte = None;
+        let received = state.incoming_byte.take();
+        return Some(state.reply.take().map_or(DISCONNECTED_VALUE, |reply| received.unwrap_or(reply)));
+    }
+
+    fn transfer_external(&mut self, data:u8)->Option<u8>{
+        let mut state = self.state.0.lock().unwrap();
+        if let Some(received) = state.incoming_byte.take(){
+            state.waiting_byte = None;
+            return Some(received);
+        }
+        if let Some((sequence_number, received, _)) = state.pending_transfer.take(){
+            if write_message(&mut self.writer.lock().unwrap(), REPLY_MESSAGE, sequence_number, data).is_err(){
+                state.connected = false;
+                return None;
+            }
+            return Some(received);
+        }
+        state.waiting_byte = Some(data);
+        return None;
+    }
+}
+
+fn write_message(stream:&mut TcpStream, message_type:u8, sequence_number:u32, data:u8)->io::Result<()>{
+    let mut message = [0;MESSAGE_SIZE];
+    message[0] = message_type;
+    message[1..5].copy_from_slice(&sequence_number.to_le_bytes());
+    message[5] = data;
+    return stream.write_all(&message);
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn create_linked_devices()->(TcpLinkDevice, TcpLinkDevice){
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let client = std::thread::spawn(move || TcpLinkDevice::connect(address).unwrap());
+        let (stream, _) = listener.accept().unwrap();
+        let host = TcpLinkDevice::from_stream(stream).unwrap();
+        return (host, client.join().unwrap());
+    }
+
+    #[test]
+    fn transfer_over_localhost(){
+        let (mut master, mut slave) = create_linked_devices();
+        assert_eq!(slave.transfer_external(0x34), None);
+        assert_eq!(master.transfer_internal(0x56), 0x34);
+        assert_eq!(slave.transfer_external(0x34), Some(0x56));
+        assert_eq!(slave.transfer_external(0x78), None);
+    }
+
+    #[test]
+    fn transfer_stays_pending_until_the_partner_is_ready(){
+        let (mut master, mut slave) = create_linked_devices();
+        // Making sure the slave gets ready before the window passes
+        slave.state.0.lock().unwrap().ready_window = REPLY_TIMEOUT;
+        assert_eq!(master.poll_transfer_internal(0x12), None);
+        // Waiting for the transfer message to arrive before the slave gets ready
+        while slave.state.0.lock().unwrap().pending_transfer.is_none(){
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(master.poll_transfer_internal(0x12), None);
+
+        assert_eq!(slave.transfer_external(0x34), Some(0x12));
+        let received = loop{
+            if let Some(received) = master.poll_transfer_internal(0x12){
+                break received;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        };
+        assert_eq!(received, 0x34);
+    }
+
+    #[test]
+    fn transfer_is_not_completed_after_the_ready_window(){
+        let (mut master, mut slave) = create_linked_devices();
+        let received = loop{
+            if let Some(received) = master.poll_transfer_internal(0x12){
+                break received;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        };
+        assert_eq!(received, DISCONNECTED_VALUE);
+
+        // The slave gets ready only after the master gave up, the abandoned transfer must not complete
+        assert_eq!(slave.transfer_external(0x34), None);
+        assert!(slave.state.0.lock().unwrap().pending_transfer.is_none());
+    }
+
+    #[test]
+    fn canceled_transfer_is_dropped(){
+        let (mut master, slave) = create_linked_devices();
+        slave.state.0.lock().unwrap().ready_window = REPLY_TIMEOUT;
+        assert_eq!(master.poll_transfer_internal(0x12), None);
+        while slave.state.0.lock().unwrap().pending_transfer.is_none(){
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        // Simulating the master timing out
+        master.state.0.lock().unwrap().transfer_start = Some(Instant::now() - REPLY_TIMEOUT);
+        assert_eq!(master.poll_transfer_internal(0x12), Some(DISCONNECTED_VALUE));
+        while slave.state.0.lock().unwrap().pending_transfer.is_some(){
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn handshake_rejects_other_protocols(){
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let client = std::thread::spawn(move ||{
+            let mut stream = TcpStream::connect(address).unwrap();
+            stream.write_all(b"HTTP/").unwrap();
+            // Closing with the host handshake unread resets the connection before the host reads this one
+            let mut handshake = [0;HANDSHAKE_MAGIC.len() + 1];
+            stream.read_exact(&mut handshake).unwrap();
+        });
+        let (stream, _) = listener.accept().unwrap();
+        assert_eq!(TcpLinkDevice::from_stream(stream).err().unwrap().kind(), io::ErrorKind::InvalidData);
+        client.join().unwrap();
+    }
+}
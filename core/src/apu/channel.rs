@@ -1,3 +1,4 @@
+use crate::utils::save_state::*;
 use super::{audio_device::{Sample, DEFAULT_SAPMPLE, SAMPLE_MAX}, sample_producer::*, timer::Timer};
 
 pub struct Channel<Procuder: SampleProducer>{
@@ -69,3 +70,26 @@ impl<Procuder: SampleProducer> Channel<Procuder>{
         return (digital_sample as Sample - (MAX_DIGITAL_SAMPLE as Sample / 2)) * RATIO;
     }
 }
+
+impl<Procuder: SampleProducer + SaveState> SaveState for Channel<Procuder>{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_bool(self.enabled);
+        writer.write_u16(self.frequency);
+        writer.write_u16(self.sound_length);
+        writer.write_bool(self.length_enable);
+        self.sample_producer.save_state(writer);
+        self.timer.save_state(writer);
+        writer.write_i16(self.last_sample);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.enabled = reader.read_bool()?;
+        self.frequency = reader.read_u16()?;
+        self.sound_length = reader.read_u16()?;
+        self.length_enable = reader.read_bool()?;
+        self.sample_producer.load_state(reader)?;
+        self.timer.load_state(reader)?;
+        self.last_sample = reader.read_i16()?;
+        return Ok(());
+    }
+}
\ No newline at end of file
@@ -1,3 +1,4 @@
+use crate::utils::save_state::*;
 use super::timer::Timer;
 
 pub struct TickType{
@@ -59,4 +60,17 @@ impl FrameSequencer{
         self.timer.update_cycles_to_tick(8192);
         self.counter = 0;
     }
+}
+
+impl SaveState for FrameSequencer{
+    fn save_state(&self, writer:&mut StateWriter) {
+        self.timer.save_state(writer);
+        writer.write_u8(self.counter);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.timer.load_state(reader)?;
+        self.counter = reader.read_u8()?;
+        return Ok(());
+    }
 }
\ No newline at end of file
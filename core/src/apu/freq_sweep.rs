@@ -1,3 +1,5 @@
+use crate::utils::save_state::*;
+
 pub struct FreqSweep{
     pub enabled:bool,
     pub sweep_counter:u8,
@@ -51,4 +53,27 @@ impl FreqSweep{
     pub fn check_overflow(freq:u16)->bool{
         freq > 2047
     }
+}
+
+impl SaveState for FreqSweep{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_bool(self.enabled);
+        writer.write_u8(self.sweep_counter);
+        writer.write_u8(self.sweep_period);
+        writer.write_bool(self.sweep_decrease);
+        writer.write_u8(self.sweep_shift);
+        writer.write_u16(self.shadow_frequency);
+        writer.write_u8(self.nr10_register);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.enabled = reader.read_bool()?;
+        self.sweep_counter = reader.read_u8()?;
+        self.sweep_period = reader.read_u8()?;
+        self.sweep_decrease = reader.read_bool()?;
+        self.sweep_shift = reader.read_u8()?;
+        self.shadow_frequency = reader.read_u16()?;
+        self.nr10_register = reader.read_u8()?;
+        return Ok(());
+    }
 }
\ No newline at end of file
@@ -1,3 +1,4 @@
+use crate::utils::save_state::*;
 use super::{
     audio_device::*, 
     channel::Channel, 
@@ -157,3 +158,39 @@ impl<Device: AudioDevice> GbApu<Device>{
         }
     }
 }
+
+// The samples in the audio buffer are not part of the state since they are already produced,
+// only the position in the buffer is saved in order to keep the buffer push timing deterministic
+impl<Device: AudioDevice> SaveState for GbApu<Device>{
+    fn save_state(&self, writer:&mut StateWriter) {
+        self.wave_channel.save_state(writer);
+        self.sweep_tone_channel.save_state(writer);
+        self.tone_channel.save_state(writer);
+        self.noise_channel.save_state(writer);
+        self.frame_sequencer.save_state(writer);
+        self.right_terminal.save_state(writer);
+        self.left_terminal.save_state(writer);
+        writer.write_bool(self.enabled);
+        writer.write_u8(self.nr50_register);
+        writer.write_u8(self.nr51_register);
+        writer.write_u32(self.current_m_cycle);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.wave_channel.load_state(reader)?;
+        self.sweep_tone_channel.load_state(reader)?;
+        self.tone_channel.load_state(reader)?;
+        self.noise_channel.load_state(reader)?;
+        self.frame_sequencer.load_state(reader)?;
+        self.right_terminal.load_state(reader)?;
+        self.left_terminal.load_state(reader)?;
+        self.enabled = reader.read_bool()?;
+        self.nr50_register = reader.read_u8()?;
+        self.nr51_register = reader.read_u8()?;
+        self.current_m_cycle = reader.read_u32()?;
+        if self.current_m_cycle as usize >= BUFFER_SIZE{
+            return Err(SaveStateError::Corrupted);
+        }
+        return Ok(());
+    }
+}
\ No newline at end of file
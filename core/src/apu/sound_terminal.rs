@@ -1,3 +1,4 @@
+use crate::utils::save_state::*;
 use super::{audio_device::{DEFAULT_SAPMPLE, Sample}, NUMBER_OF_CHANNELS};
 
 type ChannelMask = u16;
@@ -45,4 +46,16 @@ impl SoundTerminal{
         // Adding +1 cause thats how to GB calculates the sound (0 still has volume)
         return mixed_sample * ((self.volume + 1) as Sample);
     }
+}
+
+impl SaveState for SoundTerminal{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_u8(self.volume);
+        writer.write_u16_slice(&self.channel_masks);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.volume = reader.read_u8()?;
+        return reader.read_u16_slice(&mut self.channel_masks);
+    }
 }
\ No newline at end of file
@@ -1,3 +1,4 @@
+use crate::utils::save_state::*;
 use super::{freq_sweep::FreqSweep, sample_producer::SampleProducer, volume_envelop::VolumeEnvlope};
 
 const DUTY_TABLE:[[u8; 8]; 4] = [
@@ -68,3 +69,27 @@ impl SampleProducer for SquareSampleProducer{
     }
 }
 
+impl SaveState for SquareSampleProducer{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_u8(self.wave_duty);
+        if let Some(sweep) = &self.sweep{
+            sweep.save_state(writer);
+        }
+        self.envelop.save_state(writer);
+        writer.write_u8(self.duty_sample_pointer);
+    }
+
+    // The sweep existence is determined by the channel and not by the state
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.wave_duty = reader.read_u8()?;
+        if let Some(sweep) = self.sweep.as_mut(){
+            sweep.load_state(reader)?;
+        }
+        self.envelop.load_state(reader)?;
+        self.duty_sample_pointer = reader.read_u8()?;
+        if self.wave_duty > 3 || self.duty_sample_pointer > 7{
+            return Err(SaveStateError::Corrupted);
+        }
+        return Ok(());
+    }
+}
\ No newline at end of file
@@ -1,3 +1,5 @@
+use crate::utils::save_state::*;
+
 pub struct Timer{
     cycles_to_tick:u16,
     cycle_counter:u16
@@ -31,4 +33,17 @@ impl Timer{
         self.cycles_to_tick = cycles_to_tick >> 2;
         self.cycle_counter = 0;
     }
+}
+
+impl SaveState for Timer{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_u16(self.cycles_to_tick);
+        writer.write_u16(self.cycle_counter);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.cycles_to_tick = reader.read_u16()?;
+        self.cycle_counter = reader.read_u16()?;
+        return Ok(());
+    }
 }
\ No newline at end of file
@@ -1,3 +1,5 @@
+use crate::utils::save_state::*;
+
 pub struct VolumeEnvlope{
     pub volume:u8,
     pub current_volume:u8,
@@ -49,4 +51,25 @@ impl Default for VolumeEnvlope{
             nrx2_register:0
         }
     }
+}
+
+impl SaveState for VolumeEnvlope{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_u8(self.volume);
+        writer.write_u8(self.current_volume);
+        writer.write_bool(self.increase_envelope);
+        writer.write_u8(self.number_of_envelope_sweep);
+        writer.write_u8(self.envelop_duration_counter);
+        writer.write_u8(self.nrx2_register);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.volume = reader.read_u8()?;
+        self.current_volume = reader.read_u8()?;
+        self.increase_envelope = reader.read_bool()?;
+        self.number_of_envelope_sweep = reader.read_u8()?;
+        self.envelop_duration_counter = reader.read_u8()?;
+        self.nrx2_register = reader.read_u8()?;
+        return Ok(());
+    }
 }
\ No newline at end of file
@@ -1,3 +1,4 @@
+use crate::utils::save_state::*;
 use super::sample_producer::SampleProducer;
 
 pub struct WaveSampleProducer{
@@ -63,4 +64,24 @@ impl WaveSampleProducer{
             _=>core::panic!("wave channel volume value is invalid {}", self.volume)
         }
     }
+}
+
+impl SaveState for WaveSampleProducer{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_bytes(&self.wave_samples);
+        writer.write_u8(self.volume);
+        writer.write_bool(self.nr30_dac_state);
+        writer.write_u8(self.sample_counter);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        reader.read_bytes(&mut self.wave_samples)?;
+        self.volume = reader.read_u8()?;
+        self.nr30_dac_state = reader.read_bool()?;
+        self.sample_counter = reader.read_u8()?;
+        if self.volume > 3 || self.sample_counter >= 32{
+            return Err(SaveStateError::Corrupted);
+        }
+        return Ok(());
+    }
 }
\ No newline at end of file
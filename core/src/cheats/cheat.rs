@@ -0,0 +1,62 @@
+use core::convert::TryFrom;
+
+/// Writes a value to memory once every frame, in the format of `BBVVAAAA` (bank, value and a little endian address)
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct GameSharkCode{
+    pub bank:u8,
+    pub value:u8,
+    pub address:u16
+}
+
+/// Patches a ROM read, in the format of `VVA-AAA-CCC` where the compare part is optional
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct GameGenieCode{
+    pub address:u16,
+    pub value:u8,
+    // The patch applies only when the original value matches
+    pub compare:Option<u8>
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Cheat{
+    GameShark(GameSharkCode),
+    GameGenie(GameGenieCode)
+}
+
+impl TryFrom<&str> for Cheat{
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let mut digits = [0u8;9];
+        let mut length = 0;
+        for c in value.trim().chars().filter(|c|*c != '-'){
+            if length == digits.len(){
+                return Err(());
+            }
+            digits[length] = c.to_digit(16).ok_or(())? as u8;
+            length += 1;
+        }
+        let byte = |index:usize| (digits[index] << 4) | digits[index + 1];
+
+        return match length{
+            8 => {
+                let address = u16::from_le_bytes([byte(4), byte(6)]);
+                // Writing to the ROM area would switch banks
+                if address < 0x8000{
+                    return Err(());
+                }
+                Ok(Cheat::GameShark(GameSharkCode{bank: byte(0), value: byte(2), address}))
+            }
+            6 | 9 => {
+                let address = (((digits[5] ^ 0xF) as u16) << 12) | ((digits[2] as u16) << 8) | ((digits[3] as u16) << 4) | digits[4] as u16;
+                if address >= 0x8000{
+                    return Err(());
+                }
+                // The compare value is scrambled across the first and last digits, the middle one is ignored
+                let compare = (length == 9).then(|| ((digits[6] << 4) | digits[8]).rotate_right(2) ^ 0xBA);
+                Ok(Cheat::GameGenie(GameGenieCode{address, value: byte(0), compare}))
+            }
+            _ => Err(())
+        };
+    }
+}
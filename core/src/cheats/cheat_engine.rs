@@ -0,0 +1,55 @@
+use super::cheat::*;
+
+pub const MAX_CHEATS:usize = 64;
+
+pub struct CheatEngine{
+    game_shark_codes:[GameSharkCode;MAX_CHEATS],
+    game_shark_count:usize,
+    game_genie_codes:[GameGenieCode;MAX_CHEATS],
+    game_genie_count:usize
+}
+
+impl CheatEngine{
+    pub fn new()->Self{
+        Self { game_shark_codes: [GameSharkCode::default();MAX_CHEATS], game_shark_count: 0, game_genie_codes: [GameGenieCode::default();MAX_CHEATS], game_genie_count: 0 }
+    }
+
+    /// Returns false when there is no room for another cheat of this type
+    pub fn add(&mut self, cheat:Cheat)->bool{
+        match cheat{
+            Cheat::GameShark(code) => {
+                if self.game_shark_count == MAX_CHEATS{
+                    return false;
+                }
+                self.game_shark_codes[self.game_shark_count] = code;
+                self.game_shark_count += 1;
+            }
+            Cheat::GameGenie(code) => {
+                if self.game_genie_count == MAX_CHEATS{
+                    return false;
+                }
+                self.game_genie_codes[self.game_genie_count] = code;
+                self.game_genie_count += 1;
+            }
+        }
+        return true;
+    }
+
+    pub fn clear(&mut self){
+        self.game_shark_count = 0;
+        self.game_genie_count = 0;
+    }
+
+    pub fn game_shark_codes(&self)->&[GameSharkCode]{
+        &self.game_shark_codes[..self.game_shark_count]
+    }
+
+    pub fn patch_rom_read(&self, address:u16, value:u8)->u8{
+        for code in &self.game_genie_codes[..self.game_genie_count]{
+            if code.address == address && code.compare.map_or(true, |compare|compare == value){
+                return code.value;
+            }
+        }
+        return value;
+    }
+}
@@ -0,0 +1,2 @@
+pub mod cheat;
+pub mod cheat_engine;
@@ -1,4 +1,4 @@
-use crate::mmu::{interrupts_handler::InterruptRequest, Memory};
+use crate::{mmu::{interrupts_handler::InterruptRequest, Memory}, utils::save_state::*};
 
 use super::register::Reg;
 use super::flag::Flag;
@@ -12,9 +12,15 @@ pub struct GbCpu {
     pub program_counter: u16,
     pub mie: bool,
     pub halt:bool,
+    /// Set by HALT when IME is off and an interrupt is already pending, the next opcode fetch does not increment the PC
+    pub halt_bug:bool,
     pub stop:bool,
+    /// Set by an illegal opcode, the CPU stops fetching opcodes and ignores interrupts until reset
+    pub locked:bool,
     pub cgb_mode:bool,
-    pub double_speed:bool
+    pub double_speed:bool,
+    /// M-cycles left for the CPU to pause after a CGB speed switch
+    pub speed_switch_pause_cycles:u16
 }
 
 impl Default for GbCpu {
@@ -28,9 +34,12 @@ impl Default for GbCpu {
             program_counter: 0,
             mie: false,
             halt:false,
+            halt_bug:false,
             stop:false,
+            locked:false,
             cgb_mode:false,
-            double_speed:false
+            double_speed:false,
+            speed_switch_pause_cycles:0
         }
     }
 }
@@ -93,3 +102,40 @@ impl GbCpu {
         *self.hl.value_mut() = self.hl.value().wrapping_sub(1);
     }
 }
+
+impl SaveState for GbCpu{
+    fn save_state(&self, writer:&mut StateWriter) {
+        self.af.save_state(writer);
+        self.bc.save_state(writer);
+        self.de.save_state(writer);
+        self.hl.save_state(writer);
+        writer.write_u16(self.stack_pointer);
+        writer.write_u16(self.program_counter);
+        writer.write_bool(self.mie);
+        writer.write_bool(self.halt);
+        writer.write_bool(self.halt_bug);
+        writer.write_bool(self.stop);
+        writer.write_bool(self.locked);
+        writer.write_bool(self.cgb_mode);
+        writer.write_bool(self.double_speed);
+        writer.write_u16(self.speed_switch_pause_cycles);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.af.load_state(reader)?;
+        self.bc.load_state(reader)?;
+        self.de.load_state(reader)?;
+        self.hl.load_state(reader)?;
+        self.stack_pointer = reader.read_u16()?;
+        self.program_counter = reader.read_u16()?;
+        self.mie = reader.read_bool()?;
+        self.halt = reader.read_bool()?;
+        self.halt_bug = reader.read_bool()?;
+        self.stop = reader.read_bool()?;
+        self.locked = reader.read_bool()?;
+        self.cgb_mode = reader.read_bool()?;
+        self.double_speed = reader.read_bool()?;
+        self.speed_switch_pause_cycles = reader.read_u16()?;
+        return Ok(());
+    }
+}
\ No newline at end of file
@@ -20,19 +20,24 @@ type U32MemoryOpcodeFunc<T> = fn(&mut GbCpu,&mut T,u32)->u8;
 impl GbCpu{
     pub fn run_opcode(&mut self, memory:&mut impl Memory)->u8{
         let opcode = self.fetch_next_byte(memory);
+        if self.halt_bug{
+            // The PC fails to increment so the byte after the HALT is read twice
+            self.halt_bug = false;
+            self.program_counter = self.program_counter.wrapping_sub(1);
+        }
     
         match opcode{
             //Stop
             0x10=>{
-                // TODO: verify if stop is 1 byte or 2 bytes
+                // Stop is 2 bytes long but the hardware ignores the second byte
                 let next_byte = self.fetch_next_byte(memory);
-                if next_byte == 0{
-                    stop(self, memory)
-                }
-                else{
-                    core::panic!("Invalid stop opcode, second byte: {:#X}", next_byte);
+                if next_byte != 0{
+                    log::warn!("Stop opcode at {:#X} with a non zero second byte: {:#X}", self.program_counter.wrapping_sub(2), next_byte);
                 }
+                stop(self, memory)
             }
+
+            0xD3 | 0xDB | 0xDD | 0xE3 | 0xE4 | 0xEB | 0xEC | 0xED | 0xF4 | 0xFC | 0xFD => illegal_opcode(self, opcode),
     
             //just cpu
             0x00=>0,    // 1 cycles - 1 reading opcode
@@ -166,7 +171,6 @@ impl GbCpu{
                 }
             },
     
-            _=>core::panic!("Unsupported opcode:{:#X}", opcode)
         }
     }
 
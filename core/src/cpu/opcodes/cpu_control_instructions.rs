@@ -1,4 +1,4 @@
-use crate::{mmu::Memory, cpu::{gb_cpu::GbCpu, flag::Flag}, utils::memory_registers::{IE_REGISTER_ADDRESS, JOYP_REGISTER_ADDRESS, KEY1_REGISTER_ADDRESS}};
+use crate::{mmu::Memory, cpu::{gb_cpu::GbCpu, flag::Flag}, utils::memory_registers::{DIV_REGISTER_ADDRESS, IE_REGISTER_ADDRESS, IF_REGISTER_ADDRESS, JOYP_REGISTER_ADDRESS, KEY1_REGISTER_ADDRESS}};
 
 pub fn ccf(cpu:&mut GbCpu)->u8{
     let carry:bool = cpu.get_flag(Flag::Carry);
@@ -20,6 +20,12 @@ pub fn scf(cpu:&mut GbCpu)->u8{
 }
 
 pub fn halt(cpu:&mut GbCpu, memory: &mut impl Memory)->u8{
+    let interrupt_pending = memory.read(IE_REGISTER_ADDRESS, 0) & memory.read(IF_REGISTER_ADDRESS, 0) & 0b11111 != 0;
+    if !cpu.mie && interrupt_pending{
+        // The halt bug, the CPU does not halt and fails to increment the PC on the next opcode fetch
+        cpu.halt_bug = true;
+        return 0;
+    }
     cpu.halt = true;
     memory.set_halt(true);
 
@@ -28,6 +34,9 @@ pub fn halt(cpu:&mut GbCpu, memory: &mut impl Memory)->u8{
 }
 
 
+// The CPU is paused for ~2050 M-cycles (8200 T-cycles) while the clock stabilizes
+const SPEED_SWITCH_PAUSE_M_CYCLES:u16 = 2050;
+
 // For some reason inlining boost perf on gbc
 #[inline]
 pub fn stop(cpu:&mut GbCpu, memory: &mut impl Memory)->u8{
@@ -38,7 +47,20 @@ pub fn stop(cpu:&mut GbCpu, memory: &mut impl Memory)->u8{
         cpu.double_speed = !cpu.double_speed;
         memory.set_double_speed_mode(cpu.double_speed);
         memory.write(KEY1_REGISTER_ADDRESS, 0, 0);
+        cpu.speed_switch_pause_cycles = SPEED_SWITCH_PAUSE_M_CYCLES;
     }
+    // Stop resets the divider
+    memory.write(DIV_REGISTER_ADDRESS, 0, 0);
+
+    // 1 cycles - 1 reading opcode
+    return 0;
+}
+
+pub fn illegal_opcode(cpu:&mut GbCpu, opcode:u8)->u8{
+    // Leaving the PC on the illegal opcode so it could be inspected
+    cpu.program_counter = cpu.program_counter.wrapping_sub(1);
+    cpu.locked = true;
+    log::error!("Illegal opcode {:#X} at {:#X}, the CPU is locked up", opcode, cpu.program_counter);
 
     // 1 cycles - 1 reading opcode
     return 0;
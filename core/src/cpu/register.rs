@@ -1,3 +1,4 @@
+use crate::utils::save_state::*;
 
 const LOW_POSITION:isize = 0;
 const HIGH_POSITION:isize = 1;
@@ -53,4 +54,15 @@ impl Reg{
     fn get_masked_value(&self)->u16{
         self.value & self.read_only_mask
     }
-} 
\ No newline at end of file
+} 
+
+impl SaveState for Reg{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_u16(self.get_masked_value());
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.value = reader.read_u16()? & self.read_only_mask;
+        return Ok(());
+    }
+}
\ No newline at end of file
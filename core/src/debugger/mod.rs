@@ -1,10 +1,13 @@
 mod disassembler;
+mod ram_search;
 
 use std::fmt::{Formatter, Display, Result};
 use std::collections::{HashSet, HashMap};
 
 use crate::{*, machine::gameboy::*, cpu::gb_cpu::GbCpu, utils::vec2::Vec2, ppu::{ppu_state::PpuState, gb_ppu::GbPpu}};
 use self::disassembler::{OpcodeEntry, disassemble};
+use self::ram_search::RamSearch;
+pub use self::ram_search::{RamSearchCandidate, RamSearchFilter};
 
 #[derive(Clone, Copy)]
 pub enum PpuLayer{
@@ -59,7 +62,9 @@ pub enum DebuggerCommand{
     Watch(Address, WatchMode, Option<u8>),
     RemoveWatch(Address),
     PpuInfo,
-    GetPpuLayer(PpuLayer)
+    GetPpuLayer(PpuLayer),
+    StartRamSearch,
+    FilterRamSearch(RamSearchFilter)
 }
 
 pub const PPU_BUFFER_WIDTH:usize = 0x100;
@@ -80,10 +85,13 @@ pub enum DebuggerResult{
     Disassembly(u16, u16, Vec<OpcodeEntry>),
     AddedWatch(Address),
     HitWatch(Address, Address, u8),
+    /// The CPU executed an illegal opcode (at the address) and locked up
+    HitLockup(Address, u8),
     RemovedWatch(Address),
     WatchDoNotExist(Address),
     PpuInfo(PpuInfo),
-    PpuLayer(PpuLayer, Box<[Pixel;PPU_BUFFER_SIZE]>)
+    PpuLayer(PpuLayer, Box<[Pixel;PPU_BUFFER_SIZE]>),
+    RamSearch(Vec<RamSearchCandidate>)
 }
 
 #[derive(Clone, Copy)]
@@ -132,19 +140,21 @@ pub trait DebuggerInterface{
 pub struct Debugger<UI:DebuggerInterface>{
     ui:UI,
     breakpoints:HashSet<Address>,
-    skip_halt: bool
+    skip_halt: bool,
+    ram_search: RamSearch,
+    lockup_reported: bool
 }
 
 impl<UI:DebuggerInterface> Debugger<UI>{
     pub fn new(ui:UI)->Self{
-        Self { ui, breakpoints: HashSet::new(), skip_halt: false }
+        Self { ui, breakpoints: HashSet::new(), skip_halt: false, ram_search: RamSearch::new(), lockup_reported: false }
     }
 
     fn recv(&self)->DebuggerCommand{self.ui.recv_command()}
     fn send(&self, result: DebuggerResult){self.ui.send_result(result)}
 
-    fn should_halt(&self, cpu:&GbCpu, bank:u16, hit_watch:bool)->bool{
-        (self.check_for_break(cpu.program_counter, bank) || self.ui.should_stop() || hit_watch) && !(cpu.halt && self.skip_halt)
+    fn should_halt(&self, cpu:&GbCpu, bank:u16, hit_event:bool)->bool{
+        (self.check_for_break(cpu.program_counter, bank) || self.ui.should_stop() || hit_event) && !(cpu.halt && self.skip_halt)
     }
 
     fn check_for_break(&self, pc:u16, bank:u16)->bool{self.breakpoints.contains(&Address::new(pc, bank))}
@@ -156,7 +166,11 @@ impl<UI:DebuggerInterface> Debugger<UI>{
 
 impl_gameboy!{{
     pub fn run_debugger(&mut self){
-        while self.debugger.should_halt(&self.cpu, self.mmu.get_current_bank(self.cpu.program_counter), self.mmu.mem_watch.hit_addr.is_some()) {
+        // The lockup is reported once, loading a state might release it
+        if !self.cpu.locked{
+            self.debugger.lockup_reported = false;
+        }
+        while self.debugger.should_halt(&self.cpu, self.mmu.get_current_bank(self.cpu.program_counter), self.mmu.mem_watch.hit_addr.is_some() || (self.cpu.locked && !self.debugger.lockup_reported)) {
             if !self.cpu.halt && self.debugger.skip_halt{
                 self.debugger.send(DebuggerResult::HaltWakeup);
                 self.debugger.skip_halt = false;
@@ -168,6 +182,11 @@ impl_gameboy!{{
                 self.debugger.send(DebuggerResult::HitWatch(hit_address, Address { mem_addr: self.cpu.program_counter, bank: self.mmu.get_current_bank(self.cpu.program_counter) }, val));
                 self.mmu.mem_watch.hit_addr = None;
             }
+            if self.cpu.locked && !self.debugger.lockup_reported{
+                let opcode = self.mmu.dbg_read(self.cpu.program_counter);
+                self.debugger.send(DebuggerResult::HitLockup(Address { mem_addr: self.cpu.program_counter, bank: self.mmu.get_current_bank(self.cpu.program_counter) }, opcode));
+                self.debugger.lockup_reported = true;
+            }
             match self.debugger.recv(){
                 DebuggerCommand::Stop=>self.debugger.send(DebuggerResult::Stopped(Address{ mem_addr: self.cpu.program_counter, bank: self.mmu.get_current_bank(self.cpu.program_counter) })),
                 DebuggerCommand::Step=>{
@@ -218,6 +237,16 @@ impl_gameboy!{{
                     let buffer = self.mmu.get_ppu().get_layer(layer);
                     self.debugger.send(DebuggerResult::PpuLayer(layer, buffer));
                 }
+                DebuggerCommand::StartRamSearch=>{
+                    let sram_size = self.mmu.sram_size();
+                    let wram_banks = self.mmu.wram_banks_count();
+                    self.debugger.ram_search.start(sram_size, wram_banks, |address|self.mmu.dbg_read_bank(address.mem_addr, address.bank));
+                    self.debugger.send(DebuggerResult::RamSearch(self.debugger.ram_search.candidates().to_vec()));
+                }
+                DebuggerCommand::FilterRamSearch(filter)=>{
+                    self.debugger.ram_search.filter(filter, |address|self.mmu.dbg_read_bank(address.mem_addr, address.bank));
+                    self.debugger.send(DebuggerResult::RamSearch(self.debugger.ram_search.candidates().to_vec()));
+                }
             }
         }
     }
@@ -0,0 +1,146 @@
+use core::ops::RangeInclusive;
+
+use super::Address;
+
+const SRAM_START_ADDRESS:u16 = 0xA000;
+const SRAM_BANK_SIZE:usize = 0x2000;
+const WRAM_BANK0_RANGE:RangeInclusive<u16> = 0xC000..=0xCFFF;
+const WRAM_SWITCHABLE_RANGE:RangeInclusive<u16> = 0xD000..=0xDFFF;
+const HRAM_RANGE:RangeInclusive<u16> = 0xFF80..=0xFFFE;
+
+/// Compares the current value of a candidate against its value in the last search step
+#[derive(Clone, Copy)]
+pub enum RamSearchFilter{
+    Equal,
+    Changed,
+    Increased,
+    Decreased,
+    Value(u8)
+}
+
+impl RamSearchFilter{
+    fn matches(&self, last_value:u8, value:u8)->bool{
+        match self{
+            RamSearchFilter::Equal => value == last_value,
+            RamSearchFilter::Changed => value != last_value,
+            RamSearchFilter::Increased => value > last_value,
+            RamSearchFilter::Decreased => value < last_value,
+            RamSearchFilter::Value(expected) => value == *expected
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct RamSearchCandidate{
+    pub address:Address,
+    pub value:u8
+}
+
+pub struct RamSearch{
+    candidates:Vec<RamSearchCandidate>
+}
+
+impl RamSearch{
+    pub fn new()->Self{
+        Self { candidates: Vec::new() }
+    }
+
+    /// Snapshots the memory and sets all the addresses as candidates - every bank of the cartridge ram (sram_size bytes)
+    /// and the switchable WRAM (banks 1 to wram_banks), read returns the value of an address in a specific bank regardless of the mapped one
+    pub fn start(&mut self, sram_size:usize, wram_banks:u16, mut read:impl FnMut(Address)->u8){
+        let sram = (0..sram_size).map(|offset| Address::new(SRAM_START_ADDRESS + (offset % SRAM_BANK_SIZE) as u16, (offset / SRAM_BANK_SIZE) as u16));
+        let wram_bank0 = WRAM_BANK0_RANGE.map(|address| Address::new(address, 0));
+        let wram = (1..=wram_banks).flat_map(|bank| WRAM_SWITCHABLE_RANGE.map(move |address| Address::new(address, bank)));
+        let hram = HRAM_RANGE.map(|address| Address::new(address, 0));
+        self.candidates = sram.chain(wram_bank0).chain(wram).chain(hram)
+            .map(|address| RamSearchCandidate { address, value: read(address) })
+            .collect();
+    }
+
+    pub fn filter(&mut self, filter:RamSearchFilter, mut read:impl FnMut(Address)->u8){
+        self.candidates.retain_mut(|candidate|{
+            let value = read(candidate.address);
+            let matches = filter.matches(candidate.value, value);
+            candidate.value = value;
+            return matches;
+        });
+    }
+
+    pub fn candidates(&self)->&[RamSearchCandidate]{&self.candidates}
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    // Every bank gets its own copy of the address space so the banks can be told apart
+    struct BankedMemoryStub{
+        banks:Vec<Vec<u8>>
+    }
+
+    impl BankedMemoryStub{
+        fn new()->Self{
+            Self { banks: vec![vec![0;0x1_0000];8] }
+        }
+
+        fn read(&self, address:Address)->u8{self.banks[address.bank as usize][address.mem_addr as usize]}
+    }
+
+    fn candidate_addresses(search:&RamSearch)->Vec<(u16, u16)>{
+        search.candidates().iter().map(|c|(c.address.mem_addr, c.address.bank)).collect()
+    }
+
+    #[test]
+    fn start_covers_all_the_banks(){
+        let memory = BankedMemoryStub::new();
+        let mut search = RamSearch::new();
+        search.start(0x4000, 7, |address|memory.read(address));
+
+        let addresses = candidate_addresses(&search);
+        assert_eq!(addresses.len(), 0x4000 + 0x1000 * 8 + 0x7F);
+        assert!(addresses.contains(&(0xBFFF, 1)));
+        assert!(addresses.contains(&(0xD000, 7)));
+        assert!(!addresses.contains(&(0xD000, 0)));
+        assert!(!addresses.contains(&(0xA000, 2)));
+
+        // Partial sram bank (2KB ram)
+        search.start(0x800, 1, |address|memory.read(address));
+        let addresses = candidate_addresses(&search);
+        assert!(addresses.contains(&(0xA7FF, 0)));
+        assert!(!addresses.contains(&(0xA800, 0)));
+    }
+
+    #[test]
+    fn filter_compares_to_the_last_step(){
+        let mut memory = BankedMemoryStub::new();
+        let mut search = RamSearch::new();
+        search.start(0, 7, |address|memory.read(address));
+
+        memory.banks[3][0xD123] = 5;
+        memory.banks[4][0xD123] = 1;
+        search.filter(RamSearchFilter::Increased, |address|memory.read(address));
+        assert_eq!(candidate_addresses(&search), vec![(0xD123, 3), (0xD123, 4)]);
+
+        memory.banks[3][0xD123] = 4;
+        search.filter(RamSearchFilter::Decreased, |address|memory.read(address));
+        assert_eq!(candidate_addresses(&search), vec![(0xD123, 3)]);
+        assert_eq!(search.candidates()[0].value, 4);
+
+        search.filter(RamSearchFilter::Equal, |address|memory.read(address));
+        assert_eq!(search.candidates().len(), 1);
+        search.filter(RamSearchFilter::Changed, |address|memory.read(address));
+        assert!(search.candidates().is_empty());
+    }
+
+    #[test]
+    fn filter_by_value(){
+        let mut memory = BankedMemoryStub::new();
+        memory.banks[0][0xFF90] = 0x42;
+        memory.banks[0][0xC010] = 0x42;
+        let mut search = RamSearch::new();
+        search.start(0, 1, |address|memory.read(address));
+
+        search.filter(RamSearchFilter::Value(0x42), |address|memory.read(address));
+        assert_eq!(candidate_addresses(&search), vec![(0xC010, 0), (0xFF90, 0)]);
+    }
+}
@@ -0,0 +1,53 @@
+use crate::utils::{bit_masks::*, save_state::*};
+use super::infrared_device::InfraredDevice;
+
+const LED_MASK:u8 = BIT_0_MASK;
+const RECEIVE_MASK:u8 = BIT_1_MASK;
+const READ_ENABLE_MASK:u8 = BIT_7_MASK | BIT_6_MASK;
+
+/// The RP register
+pub struct GbInfrared{
+    rp_register:u8,
+    device:Option<&'static mut dyn InfraredDevice>
+}
+
+impl GbInfrared{
+    pub fn new()->Self{
+        Self { rp_register: 0, device: None }
+    }
+
+    pub fn set_device(&mut self, device:&'static mut dyn InfraredDevice){
+        self.device = Some(device);
+    }
+
+    pub fn get_rp_register(&mut self)->u8{
+        // The receive bit is 0 while receiving light and only when reading is enabled, the unused bits are read as 1s
+        let receiving = self.rp_register & READ_ENABLE_MASK == READ_ENABLE_MASK && self.device.as_mut().is_some_and(|device|device.receive());
+        let receive_bit = if receiving {0} else {RECEIVE_MASK};
+        return self.rp_register | receive_bit | 0b0011_1100;
+    }
+
+    pub fn set_rp_register(&mut self, value:u8){
+        let led_changed = (self.rp_register ^ value) & LED_MASK != 0;
+        self.rp_register = value & (READ_ENABLE_MASK | LED_MASK);
+        if led_changed{
+            if let Some(device) = self.device.as_mut(){
+                device.set_led(value & LED_MASK != 0);
+            }
+        }
+    }
+}
+
+impl SaveState for GbInfrared{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_u8(self.rp_register);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.rp_register = reader.read_u8()?;
+        if let Some(device) = self.device.as_mut(){
+            device.set_led(self.rp_register & LED_MASK != 0);
+        }
+        return Ok(());
+    }
+}
@@ -0,0 +1,6 @@
+/// The CGB infrared port, sends and senses the light of the partner
+pub trait InfraredDevice{
+    fn set_led(&mut self, enabled:bool);
+    /// Returns true while light from the partner is received
+    fn receive(&mut self)->bool;
+}
@@ -0,0 +1,2 @@
+pub mod gb_infrared;
+pub mod infrared_device;
@@ -1,4 +1,4 @@
-use crate::utils::bit_masks::*;
+use crate::utils::{bit_masks::*, save_state::*};
 use super::{joypad_provider::JoypadProvider, joypad::Joypad, button::Button};
 
 
@@ -45,4 +45,21 @@ impl<JP:JoypadProvider> JoypadHandler<JP>{
         self.register &= 0b1100_1111;   // Reset bit 4 & 5
         self.register |= value & 0b0011_0000;   // Seting the bits
     }
+}
+
+impl<JP:JoypadProvider> SaveState for JoypadHandler<JP>{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_u8(self.register);
+        for button in self.joypad.buttons{
+            writer.write_bool(button);
+        }
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.register = reader.read_u8()?;
+        for button in &mut self.joypad.buttons{
+            *button = reader.read_bool()?;
+        }
+        return Ok(());
+    }
 }
\ No newline at end of file
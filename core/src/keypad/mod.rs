@@ -1,4 +1,5 @@
 pub mod joypad;
 pub mod joypad_provider;
 pub mod button;
-pub mod joypad_handler;
\ No newline at end of file
+pub mod joypad_handler;
+pub mod tilt_provider;
\ No newline at end of file
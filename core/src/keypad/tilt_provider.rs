@@ -0,0 +1,5 @@
+/// Accelerometer input for cartridges with a tilt sensor (like the MBC7).
+/// The values are in g units, x is positive when tilting right and y is positive when tilting down (towards the player)
+pub trait TiltProvider{
+    fn provide(&mut self)->(f32, f32);
+}
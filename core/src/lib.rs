@@ -7,6 +7,10 @@ pub mod mmu;
 pub mod keypad;
 pub mod apu;
 pub mod timer;
+pub mod serial;
+pub mod infrared;
+pub mod sgb;
+pub mod cheats;
 pub mod utils;
 #[cfg(feature = "dbg")]
 pub mod debugger;
@@ -14,8 +18,13 @@ pub mod debugger;
 pub use {
     machine::{gameboy::GameBoy, Mode},
     ppu::gfx_device::*,
+    sgb::gb_sgb::{SGB_SCREEN_HEIGHT, SGB_SCREEN_WIDTH},
     apu::audio_device::AudioDevice,
-    keypad::joypad_provider::JoypadProvider,
-    utils::GB_FREQUENCY, 
+    keypad::{joypad_provider::JoypadProvider, tilt_provider::TiltProvider},
+    mmu::carts::{RumbleDevice, CartridgeError, CartridgeHeader},
+    serial::serial_device::SerialDevice,
+    infrared::infrared_device::InfraredDevice,
+    cheats::cheat::Cheat,
+    utils::{GB_FREQUENCY, save_state::SaveStateError}, 
     mmu::external_memory_bus::{Bootrom, GB_BOOT_ROM_SIZE, GBC_BOOT_ROM_SIZE}
 };
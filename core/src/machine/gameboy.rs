@@ -1,5 +1,9 @@
-use crate::{*, apu::gb_apu::GbApu, cpu::gb_cpu::GbCpu, mmu::{Memory, carts::Mbc, gb_mmu::GbMmu, external_memory_bus::Bootrom}};
+use crate::{*, apu::gb_apu::GbApu, cheats::cheat::Cheat, cpu::gb_cpu::GbCpu, mmu::{Memory, carts::Mbc, gb_mmu::GbMmu, external_memory_bus::Bootrom}, utils::save_state::*};
 use super::Mode;
+
+const SAVE_STATE_MAGIC:[u8;4] = *b"MGBS";
+// Bump this on every change to the state layout
+pub const SAVE_STATE_VERSION:u16 = 7;
 #[cfg(feature = "dbg")]
 use crate::debugger::*;
 
@@ -36,6 +40,12 @@ impl_gameboy! {{
                 *cpu.bc.value_mut() = 0x0;
                 *cpu.de.value_mut() = 0xFF56;
                 *cpu.hl.value_mut() = 0xD;
+            },
+            Mode::SGB=>{
+                *cpu.af.value_mut() = 0x100;
+                *cpu.bc.value_mut() = 0x14;
+                *cpu.de.value_mut() = 0x0;
+                *cpu.hl.value_mut() = 0xC060;
             }
         }
         cpu.stack_pointer = 0xFFFE;
@@ -63,6 +73,24 @@ impl_gameboy! {{
         };
     }
 
+    pub fn set_serial_device(&mut self, device:&'static mut dyn SerialDevice){
+        self.mmu.set_serial_device(device);
+    }
+
+    /// Used by the cartridge IR port (HuC1 and HuC3) when exists, otherwise by the CGB infrared port
+    pub fn set_infrared_device(&mut self, device:&'static mut dyn InfraredDevice){
+        self.mmu.set_infrared_device(device);
+    }
+
+    /// Returns false when there is no room for more cheats of this type
+    pub fn add_cheat(&mut self, cheat:Cheat)->bool{
+        self.mmu.add_cheat(cheat)
+    }
+
+    pub fn clear_cheats(&mut self){
+        self.mmu.clear_cheats();
+    }
+
     pub fn cycle_frame(&mut self){
         self.mmu.poll_joypad_state();
 
@@ -71,9 +99,100 @@ impl_gameboy! {{
             self.run_debugger();
             self.step();
         }
+        self.mmu.apply_game_shark_codes();
+    }
+
+    /// Executes a single step without polling the joypad, used to run several machines in lockstep.
+    /// Returns true when a frame has completed and the M-cycles the step took (an M-cycle is twice as fast in double speed mode)
+    pub fn cycle_step(&mut self)->(bool, u32){
+        #[cfg(feature = "dbg")]
+        self.run_debugger();
+        let m_cycles = self.step();
+        let frame_done = self.mmu.consume_vblank_event();
+        if frame_done{
+            self.mmu.apply_game_shark_codes();
+        }
+        return (frame_done, m_cycles);
+    }
+
+    pub fn poll_joypad_state(&mut self){
+        self.mmu.poll_joypad_state();
+    }
+
+    /// The CPU registers and state, used by test runners to detect the test results
+    pub fn cpu(&self)->&GbCpu{
+        &self.cpu
+    }
+
+    /// Reads memory as the CPU sees it without advancing the machine
+    pub fn peek_memory(&mut self, address:u16)->u8{
+        self.mmu.read(address, 0)
+    }
+
+    /// The size is constant for a specific cartridge and mode, variable length parts (like the PPU FIFOs) are saved at their full capacity
+    pub fn save_state_size(&self)->usize{
+        let mut writer = StateWriter::new(&mut []);
+        self.write_state(&mut writer);
+        return writer.position();
+    }
+
+    /// Returns the amount of bytes written to the buffer
+    pub fn save_state(&self, buffer:&mut [u8])->Result<usize, SaveStateError>{
+        let mut writer = StateWriter::new(buffer);
+        self.write_state(&mut writer);
+        if writer.overflowed(){
+            return Err(SaveStateError::BufferTooSmall);
+        }
+        return Ok(writer.position());
     }
 
-    pub(crate) fn step(&mut self) {
+    /// The header and machine configuration are validated before loading,
+    /// In case of an error after that (a corrupted state) the machine state is undefined.
+    pub fn load_state(&mut self, buffer:&[u8])->Result<(), SaveStateError>{
+        let mut reader = StateReader::new(buffer);
+        let mut magic = [0;SAVE_STATE_MAGIC.len()];
+        reader.read_bytes(&mut magic).map_err(|_|SaveStateError::InvalidHeader)?;
+        if magic != SAVE_STATE_MAGIC{
+            return Err(SaveStateError::InvalidHeader);
+        }
+        let version = reader.read_u16()?;
+        if version != SAVE_STATE_VERSION{
+            return Err(SaveStateError::UnsupportedVersion(version));
+        }
+        if buffer.len() < self.save_state_size(){
+            return Err(SaveStateError::BufferTooSmall);
+        }
+
+        // The MMU verifies the machine configuration so loading it first
+        self.mmu.load_state(&mut reader)?;
+        self.cpu.load_state(&mut reader)?;
+        return Ok(());
+    }
+
+    fn write_state(&self, writer:&mut StateWriter){
+        writer.write_bytes(&SAVE_STATE_MAGIC);
+        writer.write_u16(SAVE_STATE_VERSION);
+        self.mmu.save_state(writer);
+        self.cpu.save_state(writer);
+    }
+
+    /// Returns the M-cycles the step took
+    pub(crate) fn step(&mut self)->u32{
+        // A locked up CPU does nothing while the rest of the hardware keeps running
+        if self.cpu.locked{
+            self.mmu.cycle(1);
+            return 1;
+        }
+        // Same goes for the pause after a speed switch, interrupts are not serviced as well
+        if self.cpu.speed_switch_pause_cycles != 0{
+            self.cpu.speed_switch_pause_cycles -= 1;
+            self.mmu.cycle(1);
+            return 1;
+        }
+
+        // The memory accesses of the opcode cycle the hardware as well so measuring all of them
+        let start_m_cycles = self.mmu.m_cycles_counter();
+
         //CPU
         let mut cpu_cycles_passed = 1;
         if !self.cpu.halt && !self.mmu.dma_block_cpu(){
@@ -89,6 +208,7 @@ impl_gameboy! {{
         if interrupt_cycles != 0{
             self.mmu.cycle(interrupt_cycles);
         }
+        return self.mmu.m_cycles_counter().wrapping_sub(start_m_cycles);
     }
 
     fn execute_opcode(&mut self)->u8{
@@ -1,8 +1,8 @@
 use crate::{mmu::carts::*, utils::global_static_alloctor::*};
 
-const CARTRIDGE_TYPE_ADDRESS:usize = 0x147;
+pub fn initialize_mbc(program:&[u8], save_data:Option<&[u8]>)->Result<&'static mut dyn Mbc, CartridgeError>{
+    let header = validate_program(program)?;
 
-pub fn initialize_mbc(program:&[u8], save_data:Option<&[u8]>)->&'static mut dyn Mbc{
     let program_clone:&mut [u8] = static_alloc_array(program.len());
     program_clone.clone_from_slice(program);
     let save_data_clone:Option<&'static mut[u8]> = if let Some(sd) = save_data{
@@ -12,29 +12,52 @@ pub fn initialize_mbc(program:&[u8], save_data:Option<&[u8]>)->&'static mut dyn
     }
     else{None};
 
-    let mbc_type = program[CARTRIDGE_TYPE_ADDRESS];
+    let mbc_type = header.cartridge_type;
 
     let mbc:&'static mut dyn Mbc = match mbc_type{
         0x0 | 
-        0x8 => static_alloc(Rom::new(program_clone,false, None)),
-        0x9 => static_alloc(Rom::new(program_clone, true, save_data_clone)),
+        0x8 => static_alloc(Rom::new(program_clone,false, None)?),
+        0x9 => static_alloc(Rom::new(program_clone, true, save_data_clone)?),
         0x1 | 
-        0x2 => static_alloc(Mbc1::new(program_clone,false, None)),
-        0x3 => static_alloc(Mbc1::new(program_clone,true, save_data_clone)),
-        0xF => static_alloc(Mbc3::new(program_clone, true, None)),  // The battery is for the RTC which isnt supported right now
-        0x10 |  // The battery is also used for the RTC which isnt supported right now
-        0x13 => static_alloc(Mbc3::new(program_clone, true, save_data_clone)),
+        0x2 => static_alloc(Mbc1::new(program_clone,false, None)?),
+        0x3 => static_alloc(Mbc1::new(program_clone,true, save_data_clone)?),
+        0x5 => static_alloc(Mbc2::new(program_clone, false, None)?),
+        0x6 => static_alloc(Mbc2::new(program_clone, true, save_data_clone)?),
+        0xF |
+        0x10 => static_alloc(Mbc3::new(program_clone, true, save_data_clone, true)?),
+        0x13 => static_alloc(Mbc3::new(program_clone, true, save_data_clone, false)?),
         0x11 | 
-        0x12 => static_alloc(Mbc3::new(program_clone,false,None)),
+        0x12 => static_alloc(Mbc3::new(program_clone,false,None, false)?),
         0x19 | 
-        0x1A => static_alloc(Mbc5::new(program_clone, false, save_data_clone)),
-        0x1B |
-        0x1E => static_alloc(Mbc5::new(program_clone, true, save_data_clone)),
-        _=> core::panic!("not supported cartridge: {:#X}",mbc_type)
+        0x1A => static_alloc(Mbc5::new(program_clone, false, save_data_clone, false)?),
+        0x1B => static_alloc(Mbc5::new(program_clone, true, save_data_clone, false)?),
+        0x1C |
+        0x1D => static_alloc(Mbc5::new(program_clone, false, save_data_clone, true)?),
+        0x1E => static_alloc(Mbc5::new(program_clone, true, save_data_clone, true)?),
+        0x22 => static_alloc(Mbc7::new(program_clone, save_data_clone)?),
+        0xFC => static_alloc(PocketCamera::new(program_clone, save_data_clone)?),
+        0xFE => static_alloc(Huc3::new(program_clone, true, save_data_clone)?),
+        0xFF => static_alloc(Huc1::new(program_clone, true, save_data_clone)?),
+        _=> return Err(CartridgeError::UnsupportedMapper(mbc_type))
     };
     
-    let cart_compatibility = mbc.read_bank0(CGB_FLAG_ADDRESS as u16);
-    log::info!("initialized cartridge of type: {:#X} with compatibility {:#X}", mbc_type, cart_compatibility);
+    log::info!("initialized cartridge: {} of type: {:#X} with compatibility {:#X}", header.title(), mbc_type, header.cgb_flag);
 
-    return mbc;
+    return Ok(mbc);
+}
+
+// Checking the header before allocating since the static allocator never frees
+fn validate_program(program:&[u8])->Result<CartridgeHeader, CartridgeError>{
+    let header = CartridgeHeader::parse(program)?;
+    if !header.header_checksum_valid{
+        return Err(CartridgeError::HeaderChecksumMismatch{expected: calculate_header_checksum(program), actual: header.header_checksum});
+    }
+    // Unofficial sizes are ignored
+    if let Some(rom_size) = header.rom_size.filter(|size| program.len() < *size){
+        return Err(CartridgeError::TruncatedRom{expected: rom_size, actual: program.len()});
+    }
+    if !header.logo_valid{
+        log::warn!("The cartridge logo is invalid, a real Gameboy would not boot it");
+    }
+    return Ok(header);
 }
\ No newline at end of file
@@ -1,6 +1,6 @@
 use core::convert::TryFrom;
 
-use crate::{mmu::carts::{Mbc, CGB_FLAG_ADDRESS}, utils::bit_masks::BIT_7_MASK};
+use crate::mmu::carts::{CartridgeHeader, Mbc, HEADER_SIZE};
 
 pub mod gameboy;
 pub mod mbc_initializer;
@@ -8,7 +8,8 @@ pub mod mbc_initializer;
 #[derive(Clone, Copy, PartialEq)]
 pub enum Mode{
     DMG,
-    CGB
+    CGB,
+    SGB
 }
 
 impl TryFrom<&str> for Mode{
@@ -18,6 +19,7 @@ impl TryFrom<&str> for Mode{
         match value{
             "DMG"=>Result::Ok(Mode::DMG),
             "CGB"=>Result::Ok(Mode::CGB),
+            "SGB"=>Result::Ok(Mode::SGB),
             _=>Result::Err(())
         }
     }
@@ -27,7 +29,8 @@ impl From<Mode> for &str{
     fn from(mode: Mode) -> &'static str {
         match mode{
             Mode::CGB => "CGB",
-            Mode::DMG => "DMG"
+            Mode::DMG => "DMG",
+            Mode::SGB => "SGB"
         }
     }
 }
@@ -35,8 +38,17 @@ impl From<Mode> for &str{
 // for some reason the lifetime is important here for the
 // compiler to accept this call on any Mbc lifetine and not just 'static
 impl<'a> dyn Mbc + 'a{
+    pub fn read_header(&self)->CartridgeHeader{
+        let mut header = [0;HEADER_SIZE];
+        for (address, value) in header.iter_mut().enumerate(){
+            *value = self.read_bank0(address as u16);
+        }
+        // The buffer is always large enough
+        return CartridgeHeader::parse(&header).unwrap();
+    }
+
+    /// SGB is never detected since it changes the look of DMG games, it is opt in with the mode option
     pub fn detect_preferred_mode(&self)->Mode{
-        let cart_compatibility_reg = self.read_bank0(CGB_FLAG_ADDRESS as u16);
-        return if cart_compatibility_reg & BIT_7_MASK == 0 {Mode::DMG} else{Mode::CGB};
+        return if self.read_header().cgb_supported() {Mode::CGB} else {Mode::DMG};
     }
 }
\ No newline at end of file
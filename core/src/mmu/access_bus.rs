@@ -1,3 +1,5 @@
+use crate::utils::save_state::*;
+
 pub enum AccessBus{
     External,
     Video
@@ -12,4 +14,24 @@ impl Clone for AccessBus{
     }
 }
 
-impl Copy for AccessBus{}
\ No newline at end of file
+impl Copy for AccessBus{}
+
+impl AccessBus{
+    // Encoding the Option in the same byte since the bus is usually saved as an Option
+    pub(crate) fn save_option_state(bus:&Option<AccessBus>, writer:&mut StateWriter){
+        writer.write_u8(match bus{
+            None=>0,
+            Some(AccessBus::External)=>1,
+            Some(AccessBus::Video)=>2
+        });
+    }
+
+    pub(crate) fn load_option_state(reader:&mut StateReader)->Result<Option<AccessBus>, SaveStateError>{
+        return match reader.read_u8()?{
+            0=>Ok(None),
+            1=>Ok(Some(AccessBus::External)),
+            2=>Ok(Some(AccessBus::Video)),
+            _=>Err(SaveStateError::Corrupted)
+        };
+    }
+}
\ No newline at end of file
@@ -0,0 +1,30 @@
+/// The sensor dimensions visible to the software, the real sensor has a few more rows that are cropped
+pub const CAMERA_IMAGE_WIDTH:usize = 128;
+pub const CAMERA_IMAGE_HEIGHT:usize = 112;
+
+/// Input for the Pocket Camera sensor
+pub trait CameraImageSource{
+    /// Fill the buffer with grayscale pixels (0 is black and 0xFF is white)
+    fn capture(&mut self, buffer:&mut [[u8; CAMERA_IMAGE_WIDTH]; CAMERA_IMAGE_HEIGHT]);
+}
+
+/// Procedural image used when no other source is attached - a moving gradient with a checkerboard on top
+pub struct TestPatternImageSource{
+    frame:u8
+}
+
+impl TestPatternImageSource{
+    pub const fn new()->Self{Self{frame:0}}
+}
+
+impl CameraImageSource for TestPatternImageSource{
+    fn capture(&mut self, buffer:&mut [[u8; CAMERA_IMAGE_WIDTH]; CAMERA_IMAGE_HEIGHT]) {
+        for (y, row) in buffer.iter_mut().enumerate(){
+            for (x, pixel) in row.iter_mut().enumerate(){
+                let gradient = (((x + y + self.frame as usize) % (CAMERA_IMAGE_WIDTH + CAMERA_IMAGE_HEIGHT)) * 0xFF / (CAMERA_IMAGE_WIDTH + CAMERA_IMAGE_HEIGHT)) as u8;
+                *pixel = if ((x / 16) + (y / 16)) % 2 == 0 {gradient} else {!gradient};
+            }
+        }
+        self.frame = self.frame.wrapping_add(1);
+    }
+}
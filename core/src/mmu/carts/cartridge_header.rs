@@ -0,0 +1,102 @@
+use crate::utils::bit_masks::BIT_7_MASK;
+use super::*;
+
+const NINTENDO_LOGO_ADDRESS:usize = 0x104;
+const NINTENDO_LOGO:[u8;0x30] = [
+    0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+    0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+    0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E
+];
+const TITLE_ADDRESS:usize = 0x134;
+// The title was 16 bytes long and later shortened to 15 (for the CGB flag) and 11 (for the manufacturer code)
+const TITLE_MAX_SIZE:usize = 16;
+const CGB_TITLE_SIZE:usize = 11;
+const MANUFACTURER_CODE_ADDRESS:usize = 0x13F;
+const MANUFACTURER_CODE_SIZE:usize = 4;
+const NEW_LICENSEE_CODE_ADDRESS:usize = 0x144;
+const CARTRIDGE_TYPE_ADDRESS:usize = 0x147;
+const VERSION_ADDRESS:usize = 0x14C;
+const GLOBAL_CHECKSUM_ADDRESS:usize = 0x14E;
+const CGB_ONLY_FLAG:u8 = 0xC0;
+const USE_NEW_LICENSEE_CODE:u8 = 0x33;
+const SGB_SUPPORT_FLAG:u8 = 0x3;
+
+/// The cartridge header at 0x100-0x14F of the rom
+#[derive(Clone, Copy)]
+pub struct CartridgeHeader{
+    title:[u8; TITLE_MAX_SIZE],
+    title_size:usize,
+    pub manufacturer_code:Option<[u8; MANUFACTURER_CODE_SIZE]>,
+    pub cgb_flag:u8,
+    pub sgb_flag:u8,
+    /// The new licensee code is used only when the old one is 0x33
+    pub old_licensee_code:u8,
+    pub new_licensee_code:[u8;2],
+    pub cartridge_type:u8,
+    /// None for sizes that are not in the official docs
+    pub rom_size:Option<usize>,
+    pub ram_size:Option<usize>,
+    pub version:u8,
+    pub header_checksum:u8,
+    pub global_checksum:u16,
+    /// The bootrom refuses to run the cartridge when any of those fails
+    pub logo_valid:bool,
+    pub header_checksum_valid:bool
+}
+
+impl CartridgeHeader{
+    pub fn parse(program:&[u8])->Result<Self, CartridgeError>{
+        if program.len() < HEADER_SIZE{
+            return Err(CartridgeError::TruncatedRom{expected: HEADER_SIZE, actual: program.len()});
+        }
+
+        let cgb_flag = program[CGB_FLAG_ADDRESS];
+        let manufacturer_code:[u8; MANUFACTURER_CODE_SIZE] = program[MANUFACTURER_CODE_ADDRESS..MANUFACTURER_CODE_ADDRESS + MANUFACTURER_CODE_SIZE].try_into().unwrap();
+        // There is no flag for the manufacturer code, guessing by its content like most tools
+        let has_manufacturer_code = cgb_flag & BIT_7_MASK != 0 && manufacturer_code.iter().all(|c|c.is_ascii_uppercase() || c.is_ascii_digit());
+        let title_area_size = if has_manufacturer_code {CGB_TITLE_SIZE} else if cgb_flag & BIT_7_MASK != 0 {TITLE_MAX_SIZE - 1} else {TITLE_MAX_SIZE};
+        let mut title = [0; TITLE_MAX_SIZE];
+        title[..title_area_size].copy_from_slice(&program[TITLE_ADDRESS..TITLE_ADDRESS + title_area_size]);
+        let title_size = title[..title_area_size].iter().position(|c|*c == 0 || !c.is_ascii()).unwrap_or(title_area_size);
+
+        let rom_size_register = program[ROM_SIZE_ADDRESS];
+        let header_checksum = program[HEADER_CHECKSUM_ADDRESS];
+        return Ok(Self{
+            title,
+            title_size,
+            manufacturer_code: has_manufacturer_code.then_some(manufacturer_code),
+            cgb_flag,
+            sgb_flag: program[SGB_FLAG_ADDRESS],
+            old_licensee_code: program[OLD_LICENSEE_CODE_ADDRESS],
+            new_licensee_code: [program[NEW_LICENSEE_CODE_ADDRESS], program[NEW_LICENSEE_CODE_ADDRESS + 1]],
+            cartridge_type: program[CARTRIDGE_TYPE_ADDRESS],
+            // Sizes are 32KB << value
+            rom_size: (rom_size_register <= 8).then(|| (ROM_BANK_SIZE * 2) << rom_size_register),
+            ram_size: get_ram_size(program[MBC_RAM_SIZE_LOCATION]).ok(),
+            version: program[VERSION_ADDRESS],
+            header_checksum,
+            global_checksum: u16::from_be_bytes([program[GLOBAL_CHECKSUM_ADDRESS], program[GLOBAL_CHECKSUM_ADDRESS + 1]]),
+            logo_valid: program[NINTENDO_LOGO_ADDRESS..NINTENDO_LOGO_ADDRESS + NINTENDO_LOGO.len()] == NINTENDO_LOGO,
+            header_checksum_valid: calculate_header_checksum(program) == header_checksum
+        });
+    }
+
+    /// The title with the padding removed, might be empty for homebrew roms
+    pub fn title(&self)->&str{
+        core::str::from_utf8(&self.title[..self.title_size]).unwrap_or("").trim_end()
+    }
+
+    pub fn cgb_supported(&self)->bool{self.cgb_flag & BIT_7_MASK != 0}
+
+    pub fn cgb_only(&self)->bool{self.cgb_flag == CGB_ONLY_FLAG}
+
+    /// The SGB functions are enabled only for carts with the SGB flag and the new licensee code
+    pub fn sgb_supported(&self)->bool{self.sgb_flag == SGB_SUPPORT_FLAG && self.old_licensee_code == USE_NEW_LICENSEE_CODE}
+
+    /// The global checksum is the sum of all the rom bytes except itself, it is not verified by the hardware
+    pub fn global_checksum_valid(&self, program:&[u8])->bool{
+        let sum = program.iter().fold(0u16, |sum, value| sum.wrapping_add(*value as u16));
+        let checksum_bytes = self.global_checksum.to_be_bytes();
+        return sum.wrapping_sub(checksum_bytes[0] as u16).wrapping_sub(checksum_bytes[1] as u16) == self.global_checksum;
+    }
+}
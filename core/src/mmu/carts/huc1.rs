@@ -0,0 +1,147 @@
+use crate::{infrared::infrared_device::InfraredDevice, utils::save_state::*};
+use super::*;
+
+const IR_MODE_VALUE:u8 = 0xE;
+// Bit 0 is set when the sensor detects light, the upper bits reads as 1s
+const IR_NO_LIGHT_VALUE:u8 = 0xC0;
+const IR_LIGHT_VALUE:u8 = 0xC1;
+
+pub struct Huc1<'a>{
+    program:&'a[u8],
+    ram:&'static mut [u8],
+    battery:bool,
+    ir_mode:bool,
+    ir_led:bool,
+    // Reads cant access the device (since it's mutable), so the sensor is sampled while in IR mode
+    ir_light:bool,
+    rom_bank_register:u8,
+    ram_bank_register:u8,
+    infrared_device:Option<&'static mut dyn InfraredDevice>
+}
+
+impl<'a> Mbc for Huc1<'a>{
+    fn get_ram(&mut self) ->&mut [u8] {
+        self.ram
+    }
+
+    fn has_battery(&self) ->bool {
+        self.battery
+    }
+
+    fn read_bank0(&self, address: u16)->u8{
+        self.program[address as usize]
+    }
+
+    fn read_current_bank(&self, address:u16)->u8{
+        let bank = self.get_current_rom_bank() as usize;
+        return self.program[(ROM_BANK_SIZE * bank + address as usize) % self.program.len()];
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8){
+        match address{
+            // There is no ram enable, any value other than the IR one maps the ram
+            0..=0x1FFF      =>{
+                self.ir_mode = value & 0xF == IR_MODE_VALUE;
+                self.sample_ir_sensor();
+            }
+            0x2000..=0x3FFF =>self.rom_bank_register = value & 0x3F,
+            0x4000..=0x5FFF =>self.ram_bank_register = value & 0x3,
+            0x6000..=0x7FFF =>{}
+            _=>core::panic!("cannot write to this address in huc1 cartridge")
+        }
+    }
+
+    fn read_external_ram(&self, address: u16)->u8{
+        if self.ir_mode{
+            return if self.ir_light {IR_LIGHT_VALUE} else {IR_NO_LIGHT_VALUE};
+        }
+        if self.ram.is_empty(){
+            return 0xFF;
+        }
+        let address = get_external_ram_valid_address(self.ram_bank_register as usize * RAM_BANK_SIZE + address as usize, &self.ram);
+        return self.ram[address];
+    }
+
+    fn write_external_ram(&mut self, address: u16, value: u8){
+        if self.ir_mode{
+            self.set_ir_led(value & 1 != 0);
+        }
+        else if !self.ram.is_empty(){
+            let address = get_external_ram_valid_address(self.ram_bank_register as usize * RAM_BANK_SIZE + address as usize, &self.ram);
+            self.ram[address] = value;
+        }
+    }
+
+    fn cycle(&mut self, _m_cycles:u32){
+        self.sample_ir_sensor();
+    }
+
+    fn has_infrared_port(&self)->bool{true}
+
+    fn set_infrared_device(&mut self, device:&'static mut dyn InfraredDevice){
+        self.infrared_device = Some(device);
+    }
+
+    #[cfg(feature = "dbg")]
+    fn get_bank_number(&self)->u16 { self.get_current_rom_bank() as u16 }
+}
+
+impl<'a> Huc1<'a>{
+    pub fn new(program:&'a[u8], battery:bool, ram:Option<&'static mut[u8]>)->Result<Self, CartridgeError>{
+        let ram = init_ram(program[MBC_RAM_SIZE_LOCATION], ram)?;
+        return Ok(Self{
+            program,
+            ram,
+            battery,
+            ir_mode:false,
+            ir_led:false,
+            ir_light:false,
+            rom_bank_register:0,
+            ram_bank_register:0,
+            infrared_device:None
+        });
+    }
+
+    fn get_current_rom_bank(&self)->u8{
+        if self.rom_bank_register == 0{
+            return 1;
+        }
+        return self.rom_bank_register;
+    }
+
+    fn sample_ir_sensor(&mut self){
+        self.ir_light = self.ir_mode && self.infrared_device.as_mut().is_some_and(|device|device.receive());
+    }
+
+    fn set_ir_led(&mut self, enabled:bool){
+        let led_changed = self.ir_led != enabled;
+        self.ir_led = enabled;
+        if led_changed{
+            if let Some(device) = self.infrared_device.as_mut(){
+                device.set_led(enabled);
+            }
+        }
+    }
+}
+
+impl<'a> SaveState for Huc1<'a>{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_bool(self.ir_mode);
+        writer.write_bool(self.ir_led);
+        writer.write_u8(self.rom_bank_register);
+        writer.write_u8(self.ram_bank_register);
+        save_ram_state(self.ram, writer);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.ir_mode = reader.read_bool()?;
+        self.ir_led = reader.read_bool()?;
+        self.rom_bank_register = reader.read_u8()?;
+        self.ram_bank_register = reader.read_u8()?;
+        if let Some(device) = self.infrared_device.as_mut(){
+            device.set_led(self.ir_led);
+        }
+        self.sample_ir_sensor();
+        return load_ram_state(self.ram, reader);
+    }
+}
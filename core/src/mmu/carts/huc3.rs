@@ -0,0 +1,301 @@
+use crate::{infrared::infrared_device::InfraredDevice, utils::{GB_FREQUENCY, save_state::*}};
+use super::*;
+
+const EXTERNAL_RAM_READ_ERROR_VALUE:u8 = 0xFF;
+// Bit 0 is set when the sensor detects light
+const IR_NO_LIGHT_VALUE:u8 = 0xC0;
+const IR_LIGHT_VALUE:u8 = 0xC1;
+// Bit 0 is set when the RTC is ready to receive a command, commands are executed immediately so its always ready
+const SEMAPHORE_READY_VALUE:u8 = 0xFF;
+
+const RTC_MEMORY_SIZE:usize = 0x100;
+const MINUTES_PER_DAY:u16 = 60 * 24;
+const CYCLES_PER_MINUTE:u32 = GB_FREQUENCY * 60;
+// The time is stored in the RTC memory as nibbles - 3 for the minute of the day and 3 for the day counter
+const TIME_NIBBLES_COUNT:usize = 6;
+// The start of the RTC memory holds the registers the games use (like the alarm), it is packed as nibble pairs into the footer
+const FOOTER_RTC_MEMORY_OFFSET:usize = 8;
+const FOOTER_RTC_MEMORY_NIBBLES:usize = (RTC_FOOTER_SIZE - 8 - FOOTER_RTC_MEMORY_OFFSET) * 2;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Huc3Mode{
+    RamReadOnly,
+    RamReadWrite,
+    RtcCommand,
+    RtcResponse,
+    RtcSemaphore,
+    Infrared,
+    Unmapped
+}
+
+impl From<u8> for Huc3Mode{
+    fn from(value: u8) -> Self {
+        match value & 0xF{
+            0x0 => Self::RamReadOnly,
+            0xA => Self::RamReadWrite,
+            0xB => Self::RtcCommand,
+            0xC => Self::RtcResponse,
+            0xD => Self::RtcSemaphore,
+            0xE => Self::Infrared,
+            _ => Self::Unmapped
+        }
+    }
+}
+
+/// The tone generator (the piezo speaker) is not emulated - the tone commands are ignored
+pub struct Huc3<'a>{
+    program:&'a[u8],
+    ram:&'static mut [u8],
+    battery:bool,
+    mode_register:u8,
+    rom_bank_register:u8,
+    ram_bank_register:u8,
+    ir_led:bool,
+    // Reads cant access the device (since it's mutable), so the sensor is sampled while in IR mode
+    ir_light:bool,
+    infrared_device:Option<&'static mut dyn InfraredDevice>,
+
+    // The RTC is a separate chip that is controlled with 4 bit commands
+    rtc_memory:[u8;RTC_MEMORY_SIZE],
+    rtc_address:u8,
+    rtc_last_command:u8,
+    rtc_result:u8,
+    minutes:u16,
+    days:u16,
+    cycles_counter:u32
+}
+
+impl<'a> Mbc for Huc3<'a>{
+    fn get_ram(&mut self) ->&mut [u8] {
+        self.ram
+    }
+
+    fn has_battery(&self) ->bool {
+        self.battery
+    }
+
+    fn read_bank0(&self, address: u16)->u8{
+        self.program[address as usize]
+    }
+
+    fn read_current_bank(&self, address:u16)->u8{
+        let bank = self.get_current_rom_bank() as usize;
+        return self.program[(ROM_BANK_SIZE * bank + address as usize) % self.program.len()];
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8){
+        match address{
+            0..=0x1FFF      =>{
+                self.mode_register = value;
+                self.sample_ir_sensor();
+            }
+            0x2000..=0x3FFF =>self.rom_bank_register = value & 0x7F,
+            0x4000..=0x5FFF =>self.ram_bank_register = value & 0x3,
+            0x6000..=0x7FFF =>{}
+            _=>core::panic!("cannot write to this address in huc3 cartridge")
+        }
+    }
+
+    fn read_external_ram(&self, address: u16)->u8{
+        return match Huc3Mode::from(self.mode_register){
+            Huc3Mode::RamReadOnly |
+            Huc3Mode::RamReadWrite if !self.ram.is_empty() => self.ram[self.get_ram_address(address)],
+            Huc3Mode::RtcResponse => (self.rtc_last_command << 4) | self.rtc_result,
+            Huc3Mode::RtcSemaphore => SEMAPHORE_READY_VALUE,
+            Huc3Mode::Infrared => if self.ir_light {IR_LIGHT_VALUE} else {IR_NO_LIGHT_VALUE},
+            _ => EXTERNAL_RAM_READ_ERROR_VALUE
+        };
+    }
+
+    fn write_external_ram(&mut self, address: u16, value: u8){
+        match Huc3Mode::from(self.mode_register){
+            Huc3Mode::RamReadWrite if !self.ram.is_empty() => {
+                let address = self.get_ram_address(address);
+                self.ram[address] = value;
+            }
+            Huc3Mode::RtcCommand => self.execute_rtc_command(value),
+            Huc3Mode::Infrared => self.set_ir_led(value & 1 != 0),
+            _ => {}
+        }
+    }
+
+    fn cycle(&mut self, m_cycles:u32) {
+        self.cycles_counter += m_cycles;
+        while self.cycles_counter >= CYCLES_PER_MINUTE{
+            self.cycles_counter -= CYCLES_PER_MINUTE;
+            self.advance_minutes(1);
+        }
+        self.sample_ir_sensor();
+    }
+
+    fn get_rtc_footer(&self, current_timestamp:u64)->Option<[u8;RTC_FOOTER_SIZE]> {
+        // Using the same size as the MBC3 footer - the time, the RTC registers and the timestamp
+        let mut footer = [0;RTC_FOOTER_SIZE];
+        footer[0..4].copy_from_slice(&(self.minutes as u32).to_le_bytes());
+        footer[4..8].copy_from_slice(&(self.days as u32).to_le_bytes());
+        for i in 0..FOOTER_RTC_MEMORY_NIBBLES / 2{
+            footer[FOOTER_RTC_MEMORY_OFFSET + i] = self.rtc_memory[i * 2] | (self.rtc_memory[i * 2 + 1] << 4);
+        }
+        footer[RTC_FOOTER_SIZE - 8..].copy_from_slice(&current_timestamp.to_le_bytes());
+        return Some(footer);
+    }
+
+    fn load_rtc_footer(&mut self, footer:&[u8], current_timestamp:u64) {
+        if footer.len() != RTC_FOOTER_SIZE{
+            log::warn!("Invalid HuC3 RTC save footer, ignoring it");
+            return;
+        }
+        self.minutes = u32::from_le_bytes(footer[0..4].try_into().unwrap()) as u16 % MINUTES_PER_DAY;
+        self.days = u32::from_le_bytes(footer[4..8].try_into().unwrap()) as u16 & 0xFFF;
+        for i in 0..FOOTER_RTC_MEMORY_NIBBLES / 2{
+            let value = footer[FOOTER_RTC_MEMORY_OFFSET + i];
+            self.rtc_memory[i * 2] = value & 0xF;
+            self.rtc_memory[i * 2 + 1] = value >> 4;
+        }
+        let saved_timestamp = u64::from_le_bytes(footer[RTC_FOOTER_SIZE - 8..].try_into().unwrap());
+        let elapsed_minutes = current_timestamp.saturating_sub(saved_timestamp) / 60;
+        self.advance_minutes(elapsed_minutes);
+    }
+
+    fn has_infrared_port(&self)->bool{true}
+
+    fn set_infrared_device(&mut self, device:&'static mut dyn InfraredDevice){
+        self.infrared_device = Some(device);
+    }
+
+    #[cfg(feature = "dbg")]
+    fn get_bank_number(&self)->u16 { self.get_current_rom_bank() as u16 }
+}
+
+impl<'a> Huc3<'a>{
+    pub fn new(program:&'a[u8], battery:bool, ram:Option<&'static mut[u8]>)->Result<Self, CartridgeError>{
+        let ram = init_ram(program[MBC_RAM_SIZE_LOCATION], ram)?;
+        return Ok(Self{
+            program,
+            ram,
+            battery,
+            mode_register:0,
+            rom_bank_register:0,
+            ram_bank_register:0,
+            ir_led:false,
+            ir_light:false,
+            infrared_device:None,
+            rtc_memory:[0;RTC_MEMORY_SIZE],
+            rtc_address:0,
+            rtc_last_command:0,
+            rtc_result:0,
+            minutes:0,
+            days:0,
+            cycles_counter:0
+        });
+    }
+
+    fn get_current_rom_bank(&self)->u8{
+        if self.rom_bank_register == 0{
+            return 1;
+        }
+        return self.rom_bank_register;
+    }
+
+    fn sample_ir_sensor(&mut self){
+        self.ir_light = Huc3Mode::from(self.mode_register) == Huc3Mode::Infrared && self.infrared_device.as_mut().is_some_and(|device|device.receive());
+    }
+
+    fn set_ir_led(&mut self, enabled:bool){
+        let led_changed = self.ir_led != enabled;
+        self.ir_led = enabled;
+        if led_changed{
+            if let Some(device) = self.infrared_device.as_mut(){
+                device.set_led(enabled);
+            }
+        }
+    }
+
+    fn get_ram_address(&self, address:u16)->usize{
+        get_external_ram_valid_address(self.ram_bank_register as usize * RAM_BANK_SIZE + address as usize, &self.ram)
+    }
+
+    // The upper nibble is the command and the lower nibble is its argument
+    fn execute_rtc_command(&mut self, value:u8){
+        let command = (value >> 4) & 0x7;
+        let argument = value & 0xF;
+        match command{
+            // Read and increment the address
+            0x1 => {
+                self.rtc_result = self.rtc_memory[self.rtc_address as usize];
+                self.rtc_address = self.rtc_address.wrapping_add(1);
+            }
+            // Write and increment the address
+            0x3 => {
+                self.rtc_memory[self.rtc_address as usize] = argument;
+                self.rtc_address = self.rtc_address.wrapping_add(1);
+            }
+            0x4 => self.rtc_address = (self.rtc_address & 0xF0) | argument,
+            0x5 => self.rtc_address = (self.rtc_address & 0x0F) | (argument << 4),
+            0x6 => match argument{
+                // Copy the current time to the memory
+                0x0 => {
+                    let time = self.minutes as u32 | ((self.days as u32) << 12);
+                    for i in 0..TIME_NIBBLES_COUNT{
+                        self.rtc_memory[i] = ((time >> (i * 4)) & 0xF) as u8;
+                    }
+                }
+                // Set the time from the memory
+                0x1 => {
+                    let time = (0..TIME_NIBBLES_COUNT).fold(0, |time, i| time | ((self.rtc_memory[i] as u32) << (i * 4)));
+                    self.minutes = (time & 0xFFF) as u16 % MINUTES_PER_DAY;
+                    self.days = (time >> 12) as u16 & 0xFFF;
+                    self.cycles_counter = 0;
+                }
+                // Status, always ready
+                0x2 => self.rtc_result = 1,
+                // The rest control the tone generator which is not emulated
+                _ => log::debug!("Ignoring HuC3 tone generator command: {:#X}", argument)
+            },
+            _ => log::debug!("Unsupported HuC3 RTC command: {:#X}", value)
+        }
+        self.rtc_last_command = command;
+    }
+
+    fn advance_minutes(&mut self, minutes:u64){
+        let total_minutes = self.minutes as u64 + minutes;
+        self.minutes = (total_minutes % MINUTES_PER_DAY as u64) as u16;
+        self.days = ((self.days as u64 + (total_minutes / MINUTES_PER_DAY as u64)) & 0xFFF) as u16;
+    }
+}
+
+impl<'a> SaveState for Huc3<'a>{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_u8(self.mode_register);
+        writer.write_u8(self.rom_bank_register);
+        writer.write_u8(self.ram_bank_register);
+        writer.write_bool(self.ir_led);
+        writer.write_bytes(&self.rtc_memory);
+        writer.write_u8(self.rtc_address);
+        writer.write_u8(self.rtc_last_command);
+        writer.write_u8(self.rtc_result);
+        writer.write_u16(self.minutes);
+        writer.write_u16(self.days);
+        writer.write_u32(self.cycles_counter);
+        save_ram_state(self.ram, writer);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.mode_register = reader.read_u8()?;
+        self.rom_bank_register = reader.read_u8()?;
+        self.ram_bank_register = reader.read_u8()?;
+        self.ir_led = reader.read_bool()?;
+        reader.read_bytes(&mut self.rtc_memory)?;
+        self.rtc_address = reader.read_u8()?;
+        self.rtc_last_command = reader.read_u8()?;
+        self.rtc_result = reader.read_u8()?;
+        self.minutes = reader.read_u16()?;
+        self.days = reader.read_u16()?;
+        self.cycles_counter = reader.read_u32()?;
+        if let Some(device) = self.infrared_device.as_mut(){
+            device.set_led(self.ir_led);
+        }
+        self.sample_ir_sensor();
+        return load_ram_state(self.ram, reader);
+    }
+}
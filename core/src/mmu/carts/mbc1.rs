@@ -1,5 +1,11 @@
+use crate::utils::save_state::*;
 use super::*;
 
+const NINTENDO_LOGO_ADDRESS:usize = 0x104;
+const NINTENDO_LOGO_SIZE:usize = 0x30;
+// Multicarts are made of 256KB games, each has its own header
+const MULTICART_GAME_SIZE:usize = ROM_BANK_SIZE * 0x10;
+const MULTICART_ROM_SIZE:usize = 0x10_0000;
 
 pub struct Mbc1<'a>{
     program:&'a[u8],
@@ -8,7 +14,9 @@ pub struct Mbc1<'a>{
     register1:u8,
     register2:u8,
     register3:u8,
-    battery:bool
+    battery:bool,
+    // MBC1M - the BANK1 register is wired with only 4 bits
+    multicart:bool
 }
 
 impl<'a> Mbc for Mbc1<'a>{
@@ -21,12 +29,14 @@ impl<'a> Mbc for Mbc1<'a>{
     }
 
     fn read_bank0(&self, address: u16)->u8{
-        self.program[address as usize]
+        // In mode 1 the BANK2 register affects the bank0 area as well
+        let bank = if self.register3 == 1 {self.get_bank2_bits()} else {0} as usize;
+        return self.program[(ROM_BANK_SIZE * bank + address as usize) % self.program.len()];
     }
 
     fn read_current_bank(&self, address:u16)->u8{
         let bank:u16 = self.get_current_rom_bank() as u16;
-        return self.program[ROM_BANK_SIZE as usize * bank as usize + address as usize];
+        return self.program[(ROM_BANK_SIZE as usize * bank as usize + address as usize) % self.program.len()];
     }
 
     fn write_rom(&mut self, address: u16, value: u8){
@@ -64,18 +74,34 @@ impl<'a> Mbc for Mbc1<'a>{
 }
 
 impl<'a> Mbc1<'a>{
-    pub fn new(program:&'a[u8], battery:bool, ram:Option<&'static mut[u8]>)->Self{
-        let ram = init_ram(program[MBC_RAM_SIZE_LOCATION], ram);
+    pub fn new(program:&'a[u8], battery:bool, ram:Option<&'static mut[u8]>)->Result<Self, CartridgeError>{
+        let ram = init_ram(program[MBC_RAM_SIZE_LOCATION], ram)?;
+        let multicart = Self::is_multicart(program);
+        if multicart{
+            log::info!("Detected MBC1M multicart");
+        }
 
-        return Mbc1{
+        return Ok(Mbc1{
             program,
             ram,
             register0:0,
             register1:0,
             register2:0,
             register3:0,
-            battery:battery
-        };
+            battery:battery,
+            multicart
+        });
+    }
+
+    // Multicarts has the nintendo logo (of each game header) repeated at the start of every game
+    fn is_multicart(program:&[u8])->bool{
+        if program.len() != MULTICART_ROM_SIZE{
+            return false;
+        }
+        let logo = &program[NINTENDO_LOGO_ADDRESS..NINTENDO_LOGO_ADDRESS + NINTENDO_LOGO_SIZE];
+        return program.chunks_exact(MULTICART_GAME_SIZE)
+            .skip(1)
+            .any(|game| &game[NINTENDO_LOGO_ADDRESS..NINTENDO_LOGO_ADDRESS + NINTENDO_LOGO_SIZE] == logo);
     }
 
     fn get_current_rom_bank(&self)->u8{
@@ -85,11 +111,17 @@ impl<'a> Mbc1<'a>{
         if bank == 0{
             bank+=1;
         }
-        if self.register3 == 0{
-            bank |= (self.register2 & 0b11)<<5;
+        // The zero check above is done on the full 5 bits even on multicarts
+        if self.multicart{
+            bank &= 0b1111;
         }
 
-        return bank;
+        return bank | self.get_bank2_bits();
+    }
+
+    fn get_bank2_bits(&self)->u8{
+        let shift = if self.multicart {4} else {5};
+        return (self.register2 & 0b11) << shift;
     }
 
     fn get_current_ram_bank(&self)->u8{
@@ -99,4 +131,22 @@ impl<'a> Mbc1<'a>{
 
         return 0;
     }
+}
+
+impl<'a> SaveState for Mbc1<'a>{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_u8(self.register0);
+        writer.write_u8(self.register1);
+        writer.write_u8(self.register2);
+        writer.write_u8(self.register3);
+        save_ram_state(self.ram, writer);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.register0 = reader.read_u8()?;
+        self.register1 = reader.read_u8()?;
+        self.register2 = reader.read_u8()?;
+        self.register3 = reader.read_u8()?;
+        return load_ram_state(self.ram, reader);
+    }
 }
\ No newline at end of file
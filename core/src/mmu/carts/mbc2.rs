@@ -0,0 +1,107 @@
+use crate::utils::{bit_masks::BIT_8_MASK, save_state::*, global_static_alloctor::static_alloc_array};
+use super::*;
+
+// The MBC2 has a built in 512x4 bits ram
+const MBC2_RAM_SIZE:usize = 0x200;
+const RAM_ENABLE_VALUE:u8 = 0xA;
+const EXTERNAL_RAM_READ_ERROR_VALUE:u8 = 0xFF;
+
+pub struct Mbc2<'a>{
+    program:&'a[u8],
+    ram:&'static mut [u8],
+    ram_enable_register:u8,
+    rom_bank_register:u8,
+    battery:bool
+}
+
+impl<'a> Mbc for Mbc2<'a>{
+    fn get_ram(&mut self) ->&mut [u8] {
+        self.ram
+    }
+
+    fn has_battery(&self) ->bool {
+        self.battery
+    }
+
+    fn read_bank0(&self, address: u16)->u8{
+        self.program[address as usize]
+    }
+
+    fn read_current_bank(&self, address:u16)->u8{
+        let bank = self.get_current_rom_bank() as usize;
+        return self.program[(ROM_BANK_SIZE * bank + address as usize) % self.program.len()];
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8){
+        match address{
+            // Bit 8 of the address selects the register
+            0..=0x3FFF if address & BIT_8_MASK == 0 =>self.ram_enable_register = value,
+            0..=0x3FFF =>self.rom_bank_register = value,
+            0x4000..=0x7FFF =>{}
+            _=>core::panic!("cannot write to this address in mbc2 cartridge")
+        }
+    }
+
+    fn read_external_ram(&self, address: u16)->u8{
+        if self.ram_enable_register & 0xF != RAM_ENABLE_VALUE{
+            return EXTERNAL_RAM_READ_ERROR_VALUE;
+        }
+        // Only the lower nibble is connected, the upper one reads as 1s
+        return self.ram[address as usize % MBC2_RAM_SIZE] | 0xF0;
+    }
+
+    fn write_external_ram(&mut self, address: u16, value: u8){
+        if self.ram_enable_register & 0xF == RAM_ENABLE_VALUE{
+            self.ram[address as usize % MBC2_RAM_SIZE] = value & 0xF;
+        }
+    }
+
+    #[cfg(feature = "dbg")]
+    fn get_bank_number(&self)->u16 { self.get_current_rom_bank() as u16 }
+}
+
+impl<'a> Mbc2<'a>{
+    pub fn new(program:&'a[u8], battery:bool, ram:Option<&'static mut[u8]>)->Result<Self, CartridgeError>{
+        // The ram size in the header is 0 since the ram is built into the MBC
+        let ram = match ram{
+            Some(ram)=>{
+                if ram.len() != MBC2_RAM_SIZE{
+                    return Err(CartridgeError::SaveSizeMismatch{expected:MBC2_RAM_SIZE, actual:ram.len()});
+                }
+                ram
+            }
+            None=>static_alloc_array(MBC2_RAM_SIZE)
+        };
+
+        return Ok(Mbc2{
+            program,
+            ram,
+            ram_enable_register:0,
+            rom_bank_register:0,
+            battery
+        });
+    }
+
+    fn get_current_rom_bank(&self)->u8{
+        let bank = self.rom_bank_register & 0xF;
+        if bank == 0{
+            return 1;
+        }
+
+        return bank;
+    }
+}
+
+impl<'a> SaveState for Mbc2<'a>{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_u8(self.ram_enable_register);
+        writer.write_u8(self.rom_bank_register);
+        save_ram_state(self.ram, writer);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.ram_enable_register = reader.read_u8()?;
+        self.rom_bank_register = reader.read_u8()?;
+        return load_ram_state(self.ram, reader);
+    }
+}
@@ -1,10 +1,9 @@
-use crate::utils::bit_masks::BIT_7_MASK;
+use crate::utils::{bit_masks::BIT_7_MASK, save_state::*};
 
-use super::*;
+use super::{*, rtc::*};
 
 const RAM_TIMER_ENABLE_VALUE:u8 = 0xA;
 const EXTERNAL_RAM_READ_ERROR_VALUE:u8 = 0xFF;
-const RTC_REGISTERS_COUNT:usize = 5;
 
 pub struct Mbc3<'a>{
     program:&'a[u8],
@@ -14,7 +13,7 @@ pub struct Mbc3<'a>{
     ram_timer_enable:u8,
     ram_rtc_select:u8,
     latch_clock_data:u8,
-    rtc_registers:[u8;RTC_REGISTERS_COUNT]
+    rtc:Option<Rtc>
 }
 
 impl<'a> Mbc for Mbc3<'a>{
@@ -43,7 +42,15 @@ impl<'a> Mbc for Mbc3<'a>{
             0..=0x1FFF=>self.ram_timer_enable = value,
             0x2000..=0x3FFF=>self.current_bank = value,
             0x4000..=0x5FFF=>self.ram_rtc_select = value,
-            0x6000..=0x7FFF=>self.latch_clock_data = value,
+            0x6000..=0x7FFF=>{
+                // Latching on the 0 -> 1 transition
+                if self.latch_clock_data == 0 && value == 1{
+                    if let Some(rtc) = self.rtc.as_mut(){
+                        rtc.latch();
+                    }
+                }
+                self.latch_clock_data = value;
+            },
             _=>core::panic!("cannot write to this address in mbc3 cartridge")
         }
     }
@@ -59,7 +66,10 @@ impl<'a> Mbc for Mbc3<'a>{
                 let address = get_external_ram_valid_address(internal_address, &self.ram);
                 return self.ram[address];
             },
-            0x8..=0xC=>self.rtc_registers[(self.ram_rtc_select - 8) as usize],
+            0x8..=0xC=>match &self.rtc{
+                Some(rtc)=>rtc.read((self.ram_rtc_select - 8) as usize),
+                None=>EXTERNAL_RAM_READ_ERROR_VALUE
+            },
             _=>EXTERNAL_RAM_READ_ERROR_VALUE
         };
     }
@@ -72,20 +82,38 @@ impl<'a> Mbc for Mbc3<'a>{
                     let address = get_external_ram_valid_address(internal_address, &self.ram);
                     self.ram[address] = value;
                 },
-                0x8..=0xC=>self.rtc_registers[(self.ram_rtc_select - 8) as usize] = value,
+                0x8..=0xC=>if let Some(rtc) = self.rtc.as_mut(){
+                    rtc.write((self.ram_rtc_select - 8) as usize, value);
+                },
                 _=>{}
             }
         }
     }
     
+    fn cycle(&mut self, m_cycles:u32) {
+        if let Some(rtc) = self.rtc.as_mut(){
+            rtc.cycle(m_cycles);
+        }
+    }
+
+    fn get_rtc_footer(&self, current_timestamp:u64)->Option<[u8;RTC_FOOTER_SIZE]> {
+        self.rtc.as_ref().map(|rtc|rtc.to_footer(current_timestamp))
+    }
+
+    fn load_rtc_footer(&mut self, footer:&[u8], current_timestamp:u64) {
+        if let Some(rtc) = self.rtc.as_mut(){
+            rtc.load_footer(footer, current_timestamp);
+        }
+    }
+
     #[cfg(feature = "dbg")]
     fn get_bank_number(&self)->u16 { self.get_current_rom_bank() as u16 }
 }
 
 impl<'a> Mbc3<'a>{
-    pub fn new(program:&'a[u8], battery:bool, ram:Option<&'static mut[u8]>)->Self{
-        let ram = init_ram(program[MBC_RAM_SIZE_LOCATION], ram);
-        return Self{
+    pub fn new(program:&'a[u8], battery:bool, ram:Option<&'static mut[u8]>, rtc:bool)->Result<Self, CartridgeError>{
+        let ram = init_ram(program[MBC_RAM_SIZE_LOCATION], ram)?;
+        return Ok(Self{
             current_bank:0,
             battery:battery,
             latch_clock_data:0,
@@ -93,8 +121,8 @@ impl<'a> Mbc3<'a>{
             ram,
             ram_rtc_select:0,
             ram_timer_enable:0,
-            rtc_registers:[0;RTC_REGISTERS_COUNT]
-        };
+            rtc:rtc.then(Rtc::new)
+        });
     }
 
     fn get_current_rom_bank(&self)->u8{
@@ -106,4 +134,28 @@ impl<'a> Mbc3<'a>{
 
         return value;
     }
+}
+
+impl<'a> SaveState for Mbc3<'a>{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_u8(self.current_bank);
+        writer.write_u8(self.ram_timer_enable);
+        writer.write_u8(self.ram_rtc_select);
+        writer.write_u8(self.latch_clock_data);
+        if let Some(rtc) = &self.rtc{
+            rtc.save_state(writer);
+        }
+        save_ram_state(self.ram, writer);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.current_bank = reader.read_u8()?;
+        self.ram_timer_enable = reader.read_u8()?;
+        self.ram_rtc_select = reader.read_u8()?;
+        self.latch_clock_data = reader.read_u8()?;
+        if let Some(rtc) = self.rtc.as_mut(){
+            rtc.load_state(reader)?;
+        }
+        return load_ram_state(self.ram, reader);
+    }
 }
\ No newline at end of file
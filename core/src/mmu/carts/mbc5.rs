@@ -1,6 +1,9 @@
+use crate::utils::{bit_masks::BIT_3_MASK, save_state::*};
 use super::*;
 
 const ENABLE_RAM_VALUE:u8 = 0xA;
+// On rumble carts bit 3 of the ram bank register controls the motor
+const RUMBLE_MOTOR_MASK:u8 = BIT_3_MASK;
 
 pub struct Mbc5<'a>{
     program:&'a [u8],
@@ -9,6 +12,8 @@ pub struct Mbc5<'a>{
     ram_enable_register:u8,
     rom_bank_number_register:u16,
     ram_bank_number:u8,
+    rumble:bool,
+    rumble_device:Option<&'static mut dyn RumbleDevice>
 }
 
 impl<'a> Mbc for Mbc5<'a> {
@@ -38,14 +43,24 @@ impl<'a> Mbc for Mbc5<'a> {
             2=>self.rom_bank_number_register = (self.rom_bank_number_register & 0xFF00) | value as u16,
             // high bit 9
             3=>self.rom_bank_number_register = (self.rom_bank_number_register & 0x00FF) | ((value as u16) << 8),
-            4|5=>self.ram_bank_number = value & 0xF,
+            4|5=>{
+                if self.rumble{
+                    let motor_on = value & RUMBLE_MOTOR_MASK != 0;
+                    if motor_on != (self.ram_bank_number & RUMBLE_MOTOR_MASK != 0){
+                        if let Some(device) = self.rumble_device.as_mut(){
+                            device.set_rumble(motor_on);
+                        }
+                    }
+                }
+                self.ram_bank_number = value & 0xF;
+            },
             _=>{}
         }
     }
 
     fn read_external_ram(&self, address:u16)->u8 {
         if self.ram_enable_register == ENABLE_RAM_VALUE{
-            let bank = self.ram_bank_number as usize * RAM_BANK_SIZE;
+            let bank = self.get_ram_bank() as usize * RAM_BANK_SIZE;
             let address= get_external_ram_valid_address(address as usize + bank, &self.ram);
             return self.ram[address];
         }
@@ -56,26 +71,57 @@ impl<'a> Mbc for Mbc5<'a> {
 
     fn write_external_ram(&mut self, address:u16, value:u8) {
         if self.ram_enable_register == ENABLE_RAM_VALUE{
-            let bank = self.ram_bank_number as usize * RAM_BANK_SIZE;
+            let bank = self.get_ram_bank() as usize * RAM_BANK_SIZE;
             let address= get_external_ram_valid_address(address as usize + bank, &self.ram);
             self.ram[address] = value;
         }
     }
+
+    fn set_rumble_device(&mut self, device:&'static mut dyn RumbleDevice) {
+        if self.rumble{
+            self.rumble_device = Some(device);
+        }
+    }
     
     #[cfg(feature = "dbg")]
     fn get_bank_number(&self)->u16 { self.rom_bank_number_register & 0x1FF }
 }
 
 impl<'a> Mbc5<'a>{
-    pub fn new(program:&'a[u8], battery:bool, ram:Option<&'static mut[u8]>)->Self{
-        let ram = init_ram(program[MBC_RAM_SIZE_LOCATION], ram);
-        return Self{
+    pub fn new(program:&'a[u8], battery:bool, ram:Option<&'static mut[u8]>, rumble:bool)->Result<Self, CartridgeError>{
+        let ram = init_ram(program[MBC_RAM_SIZE_LOCATION], ram)?;
+        return Ok(Self{
             program,
             ram,
             battery,
             ram_enable_register: 0,
             rom_bank_number_register: 0,
             ram_bank_number: 0,
-        };
+            rumble,
+            rumble_device: None
+        });
+    }
+
+    fn get_ram_bank(&self)->u8{
+        if self.rumble{
+            return self.ram_bank_number & !RUMBLE_MOTOR_MASK;
+        }
+        return self.ram_bank_number;
+    }
+}
+
+impl<'a> SaveState for Mbc5<'a>{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_u8(self.ram_enable_register);
+        writer.write_u16(self.rom_bank_number_register);
+        writer.write_u8(self.ram_bank_number);
+        save_ram_state(self.ram, writer);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.ram_enable_register = reader.read_u8()?;
+        self.rom_bank_number_register = reader.read_u16()?;
+        self.ram_bank_number = reader.read_u8()?;
+        return load_ram_state(self.ram, reader);
     }
 }
\ No newline at end of file
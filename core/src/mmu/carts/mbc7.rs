@@ -0,0 +1,352 @@
+use crate::{keypad::tilt_provider::TiltProvider, utils::{bit_masks::*, global_static_alloctor::static_alloc_array, save_state::*}};
+use super::*;
+
+const RAM_ENABLE_1_VALUE:u8 = 0xA;
+const RAM_ENABLE_2_VALUE:u8 = 0x40;
+const EXTERNAL_RAM_READ_ERROR_VALUE:u8 = 0xFF;
+
+const ACCELEROMETER_ERASE_VALUE:u8 = 0x55;
+const ACCELEROMETER_LATCH_VALUE:u8 = 0xAA;
+const ACCELEROMETER_ERASED_VALUE:u16 = 0x8000;
+const ACCELEROMETER_CENTER_VALUE:f32 = 0x81D0 as f32;
+// Around 1g
+const ACCELEROMETER_G_VALUE:f32 = 0x70 as f32;
+
+// The 93LC56 is 2K bits organized as 128 16 bit words
+const EEPROM_SIZE:usize = 0x100;
+const EEPROM_WORDS_COUNT:u8 = (EEPROM_SIZE / 2) as u8;
+// The opcode (2 bits) and the address (8 bits) that follows the start bit
+const EEPROM_COMMAND_BITS:u8 = 10;
+const EEPROM_WORD_BITS:u8 = 16;
+const EEPROM_CS_MASK:u8 = BIT_7_MASK;
+const EEPROM_CLK_MASK:u8 = BIT_6_MASK;
+const EEPROM_DI_MASK:u8 = BIT_1_MASK;
+const EEPROM_DO_MASK:u8 = BIT_0_MASK;
+
+#[derive(Clone, Copy, PartialEq)]
+enum EepromState{
+    // Waiting for a start bit
+    Idle,
+    Command,
+    Read{address:u8, bit:u8},
+    // None is for writing to all the addresses
+    Write{address:Option<u8>}
+}
+
+/// The 93LC56 serial EEPROM, the data is shifted in and out bit by bit on the rising edge of the clock
+struct Eeprom{
+    data:&'static mut [u8],
+    cs:bool,
+    clk:bool,
+    di:bool,
+    do_:bool,
+    write_enabled:bool,
+    state:EepromState,
+    shift_register:u16,
+    shifted_bits:u8
+}
+
+impl Eeprom{
+    fn new(data:&'static mut [u8])->Self{
+        Self { data, cs: false, clk: false, di: false, do_: true, write_enabled: false, state: EepromState::Idle, shift_register: 0, shifted_bits: 0 }
+    }
+
+    fn read(&self)->u8{
+        let mut value = 0;
+        if self.cs {value |= EEPROM_CS_MASK}
+        if self.clk {value |= EEPROM_CLK_MASK}
+        if self.di {value |= EEPROM_DI_MASK}
+        if self.do_ {value |= EEPROM_DO_MASK}
+        return value;
+    }
+
+    fn write(&mut self, value:u8){
+        let cs = value & EEPROM_CS_MASK != 0;
+        let clk = value & EEPROM_CLK_MASK != 0;
+        self.di = value & EEPROM_DI_MASK != 0;
+
+        if !cs{
+            // Lowering CS aborts any command, operations are instant so the chip is always ready
+            self.state = EepromState::Idle;
+            self.do_ = true;
+        }
+        else if !self.clk && clk{
+            self.clock_bit();
+        }
+        self.cs = cs;
+        self.clk = clk;
+    }
+
+    fn clock_bit(&mut self){
+        match self.state{
+            EepromState::Idle => {
+                if self.di{
+                    self.state = EepromState::Command;
+                    self.shift_register = 0;
+                    self.shifted_bits = 0;
+                }
+            }
+            EepromState::Command => {
+                if self.shift_in() == EEPROM_COMMAND_BITS{
+                    self.execute_command();
+                }
+            }
+            EepromState::Read { address, bit } => {
+                self.do_ = (self.read_word(address) >> (EEPROM_WORD_BITS - 1 - bit)) & 1 != 0;
+                // Keeps reading the next words sequentially
+                self.state = match bit + 1{
+                    EEPROM_WORD_BITS => EepromState::Read { address: (address + 1) % EEPROM_WORDS_COUNT, bit: 0 },
+                    bit => EepromState::Read { address, bit }
+                };
+            }
+            EepromState::Write { address } => {
+                if self.shift_in() == EEPROM_WORD_BITS{
+                    let value = self.shift_register;
+                    match address{
+                        Some(address) => self.write_word(address, value),
+                        None => (0..EEPROM_WORDS_COUNT).for_each(|address| self.write_word(address, value))
+                    }
+                    self.state = EepromState::Idle;
+                    self.do_ = true;
+                }
+            }
+        }
+    }
+
+    fn shift_in(&mut self)->u8{
+        self.shift_register = (self.shift_register << 1) | self.di as u16;
+        self.shifted_bits += 1;
+        return self.shifted_bits;
+    }
+
+    fn execute_command(&mut self){
+        let opcode = (self.shift_register >> 8) & 0b11;
+        let address = self.shift_register as u8;
+        self.shift_register = 0;
+        self.shifted_bits = 0;
+        self.state = EepromState::Idle;
+        match opcode{
+            0b10 => {
+                self.state = EepromState::Read { address: address % EEPROM_WORDS_COUNT, bit: 0 };
+                // A dummy zero bit is outputted before the data
+                self.do_ = false;
+            }
+            0b01 => self.state = EepromState::Write { address: Some(address % EEPROM_WORDS_COUNT) },
+            0b11 => self.write_word(address % EEPROM_WORDS_COUNT, 0xFFFF),
+            _ => match address >> 6{
+                0b00 => self.write_enabled = false,
+                0b01 => self.state = EepromState::Write { address: None },
+                0b10 => (0..EEPROM_WORDS_COUNT).for_each(|address| self.write_word(address, 0xFFFF)),
+                _ => self.write_enabled = true,
+            }
+        }
+    }
+
+    fn read_word(&self, address:u8)->u16{
+        let index = address as usize * 2;
+        return u16::from_le_bytes([self.data[index], self.data[index + 1]]);
+    }
+
+    fn write_word(&mut self, address:u8, value:u16){
+        if self.write_enabled{
+            let index = address as usize * 2;
+            self.data[index..index + 2].copy_from_slice(&value.to_le_bytes());
+        }
+    }
+}
+
+pub struct Mbc7<'a>{
+    program:&'a[u8],
+    eeprom:Eeprom,
+    ram_enable_1_register:u8,
+    ram_enable_2_register:u8,
+    rom_bank_register:u8,
+    accelerometer_x:u16,
+    accelerometer_y:u16,
+    accelerometer_erased:bool,
+    tilt_provider:Option<&'static mut dyn TiltProvider>
+}
+
+impl<'a> Mbc for Mbc7<'a>{
+    fn get_ram(&mut self) ->&mut [u8] {
+        self.eeprom.data
+    }
+
+    fn has_battery(&self) ->bool {true}
+
+    fn read_bank0(&self, address: u16)->u8{
+        self.program[address as usize]
+    }
+
+    fn read_current_bank(&self, address:u16)->u8{
+        return self.program[(ROM_BANK_SIZE * self.rom_bank_register as usize + address as usize) % self.program.len()];
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8){
+        match address{
+            0..=0x1FFF      =>self.ram_enable_1_register = value,
+            0x2000..=0x3FFF =>self.rom_bank_register = value,
+            0x4000..=0x5FFF =>self.ram_enable_2_register = value,
+            0x6000..=0x7FFF =>{}
+            _=>core::panic!("cannot write to this address in mbc7 cartridge")
+        }
+    }
+
+    // The registers are mapped to 0xA000-0xAFFF and selected by bits 4-7 of the address
+    fn read_external_ram(&self, address: u16)->u8{
+        if !self.ram_enabled() || address > 0xFFF{
+            return EXTERNAL_RAM_READ_ERROR_VALUE;
+        }
+        return match (address >> 4) & 0xF{
+            0x2 => self.accelerometer_x as u8,
+            0x3 => (self.accelerometer_x >> 8) as u8,
+            0x4 => self.accelerometer_y as u8,
+            0x5 => (self.accelerometer_y >> 8) as u8,
+            0x6 => 0,
+            0x8 => self.eeprom.read(),
+            _ => EXTERNAL_RAM_READ_ERROR_VALUE
+        };
+    }
+
+    fn write_external_ram(&mut self, address: u16, value: u8){
+        if !self.ram_enabled() || address > 0xFFF{
+            return;
+        }
+        match (address >> 4) & 0xF{
+            0x0 if value == ACCELEROMETER_ERASE_VALUE => {
+                self.accelerometer_x = ACCELEROMETER_ERASED_VALUE;
+                self.accelerometer_y = ACCELEROMETER_ERASED_VALUE;
+                self.accelerometer_erased = true;
+            }
+            0x1 if value == ACCELEROMETER_LATCH_VALUE && self.accelerometer_erased => {
+                let (x, y) = self.tilt_provider.as_mut().map_or((0.0, 0.0), |provider|provider.provide());
+                self.accelerometer_x = (ACCELEROMETER_CENTER_VALUE + (x * ACCELEROMETER_G_VALUE)) as u16;
+                self.accelerometer_y = (ACCELEROMETER_CENTER_VALUE + (y * ACCELEROMETER_G_VALUE)) as u16;
+                self.accelerometer_erased = false;
+            }
+            0x8 => self.eeprom.write(value),
+            _ => {}
+        }
+    }
+
+    fn set_tilt_provider(&mut self, provider:&'static mut dyn TiltProvider) {
+        self.tilt_provider = Some(provider);
+    }
+
+    #[cfg(feature = "dbg")]
+    fn get_bank_number(&self)->u16 { self.rom_bank_register as u16 }
+}
+
+impl<'a> Mbc7<'a>{
+    pub fn new(program:&'a[u8], eeprom:Option<&'static mut[u8]>)->Result<Self, CartridgeError>{
+        // The EEPROM is not reported in the header ram size
+        let eeprom = match eeprom{
+            Some(eeprom)=>{
+                if eeprom.len() != EEPROM_SIZE{
+                    return Err(CartridgeError::SaveSizeMismatch{expected:EEPROM_SIZE, actual:eeprom.len()});
+                }
+                eeprom
+            }
+            None=>{
+                let eeprom = static_alloc_array(EEPROM_SIZE);
+                eeprom.fill(0xFF);
+                eeprom
+            }
+        };
+
+        return Ok(Self{
+            program,
+            eeprom:Eeprom::new(eeprom),
+            ram_enable_1_register:0,
+            ram_enable_2_register:0,
+            rom_bank_register:1,
+            accelerometer_x:ACCELEROMETER_ERASED_VALUE,
+            accelerometer_y:ACCELEROMETER_ERASED_VALUE,
+            accelerometer_erased:false,
+            tilt_provider:None
+        });
+    }
+
+    fn ram_enabled(&self)->bool{
+        self.ram_enable_1_register == RAM_ENABLE_1_VALUE && self.ram_enable_2_register == RAM_ENABLE_2_VALUE
+    }
+}
+
+impl SaveState for EepromState{
+    fn save_state(&self, writer:&mut StateWriter) {
+        match self{
+            EepromState::Idle => writer.write_u8(0),
+            EepromState::Command => writer.write_u8(1),
+            EepromState::Read { address, bit } => {
+                writer.write_u8(2);
+                writer.write_u8(*address);
+                writer.write_u8(*bit);
+            }
+            EepromState::Write { address: Some(address) } => {
+                writer.write_u8(3);
+                writer.write_u8(*address);
+            }
+            EepromState::Write { address: None } => writer.write_u8(4)
+        }
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        *self = match reader.read_u8()?{
+            0 => EepromState::Idle,
+            1 => EepromState::Command,
+            2 => EepromState::Read { address: reader.read_u8()?, bit: reader.read_u8()? },
+            3 => EepromState::Write { address: Some(reader.read_u8()?) },
+            4 => EepromState::Write { address: None },
+            _ => return Err(SaveStateError::Corrupted)
+        };
+        return Ok(());
+    }
+}
+
+impl SaveState for Eeprom{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_bool(self.cs);
+        writer.write_bool(self.clk);
+        writer.write_bool(self.di);
+        writer.write_bool(self.do_);
+        writer.write_bool(self.write_enabled);
+        self.state.save_state(writer);
+        writer.write_u16(self.shift_register);
+        writer.write_u8(self.shifted_bits);
+        save_ram_state(self.data, writer);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.cs = reader.read_bool()?;
+        self.clk = reader.read_bool()?;
+        self.di = reader.read_bool()?;
+        self.do_ = reader.read_bool()?;
+        self.write_enabled = reader.read_bool()?;
+        self.state.load_state(reader)?;
+        self.shift_register = reader.read_u16()?;
+        self.shifted_bits = reader.read_u8()?;
+        return load_ram_state(self.data, reader);
+    }
+}
+
+impl<'a> SaveState for Mbc7<'a>{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_u8(self.ram_enable_1_register);
+        writer.write_u8(self.ram_enable_2_register);
+        writer.write_u8(self.rom_bank_register);
+        writer.write_u16(self.accelerometer_x);
+        writer.write_u16(self.accelerometer_y);
+        writer.write_bool(self.accelerometer_erased);
+        self.eeprom.save_state(writer);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.ram_enable_1_register = reader.read_u8()?;
+        self.ram_enable_2_register = reader.read_u8()?;
+        self.rom_bank_register = reader.read_u8()?;
+        self.accelerometer_x = reader.read_u16()?;
+        self.accelerometer_y = reader.read_u16()?;
+        self.accelerometer_erased = reader.read_bool()?;
+        return self.eeprom.load_state(reader);
+    }
+}
@@ -1,45 +1,97 @@
 pub mod rom;
 pub mod mbc1;
+pub mod mbc2;
 pub mod mbc3;
 pub mod mbc5;
+pub mod mbc7;
+pub mod pocket_camera;
+pub mod camera_image_source;
+pub mod huc1;
+pub mod huc3;
+pub mod rtc;
+pub mod rumble_device;
+pub mod cartridge_header;
 
 pub use rom::Rom;
 pub use mbc1::Mbc1;
+pub use mbc2::Mbc2;
 pub use mbc3::Mbc3;
 pub use mbc5::Mbc5;
+pub use mbc7::Mbc7;
+pub use pocket_camera::PocketCamera;
+pub use camera_image_source::CameraImageSource;
+pub use huc1::Huc1;
+pub use huc3::Huc3;
+pub use rtc::{RTC_FOOTER_SIZE, split_rtc_footer};
+pub use rumble_device::RumbleDevice;
+pub use cartridge_header::CartridgeHeader;
 
-use crate::utils::global_static_alloctor::static_alloc_array;
+use crate::{infrared::infrared_device::InfraredDevice, keypad::tilt_provider::TiltProvider, utils::{global_static_alloctor::static_alloc_array, save_state::*}};
 
 pub const ROM_BANK_SIZE:usize = 0x4000;
 pub const RAM_BANK_SIZE:usize = 0x2000;
 
 pub const CGB_FLAG_ADDRESS:usize = 0x143;
+pub const SGB_FLAG_ADDRESS:usize = 0x146;
+pub const OLD_LICENSEE_CODE_ADDRESS:usize = 0x14B;
 pub const MBC_RAM_SIZE_LOCATION:usize = 0x149;
+pub const ROM_SIZE_ADDRESS:usize = 0x148;
+pub const HEADER_CHECKSUM_ADDRESS:usize = 0x14D;
+pub const HEADER_SIZE:usize = 0x150;
 
-fn get_ram_size(ram_size_register:u8)->usize{
+/// The checksum the bootrom verifies over the header bytes 0x134-0x14C
+pub fn calculate_header_checksum(program:&[u8])->u8{
+    program[0x134..HEADER_CHECKSUM_ADDRESS].iter().fold(0u8, |checksum, value| checksum.wrapping_sub(*value).wrapping_sub(1))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CartridgeError{
+    UnsupportedMapper(u8),
+    /// The rom is smaller than the size declared in the header
+    TruncatedRom{expected:usize, actual:usize},
+    HeaderChecksumMismatch{expected:u8, actual:u8},
+    InvalidRamSize(u8),
+    /// The save file does not match the ram size of the cartridge
+    SaveSizeMismatch{expected:usize, actual:usize}
+}
+
+impl core::fmt::Display for CartridgeError{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self{
+            CartridgeError::UnsupportedMapper(mbc_type) => write!(f, "not supported cartridge: {:#X}", mbc_type),
+            CartridgeError::TruncatedRom{expected, actual} => write!(f, "the rom is truncated, expected {:#X} bytes but got {:#X}", expected, actual),
+            CartridgeError::HeaderChecksumMismatch{expected, actual} => write!(f, "header checksum mismatch, expected {:#X} but got {:#X}", expected, actual),
+            CartridgeError::InvalidRamSize(ram_size_register) => write!(f, "invalid ram size register {:#X}", ram_size_register),
+            CartridgeError::SaveSizeMismatch{expected, actual} => 
+                write!(f, "the save is {:#X} bytes but the cartridge ram is {:#X} bytes, the save seems corrupted, either fix or delete it and try again", actual, expected)
+        }
+    }
+}
+
+fn get_ram_size(ram_size_register:u8)->Result<usize, CartridgeError>{
     match ram_size_register{
-        0x0=>0,
-        0x1=>0x800,     // Unofficial - Undefined according to official docs
-        0x2=>0x2000,
-        0x3=>0x8000,
-        0x4=>0x2_0000,
-        0x5=>0x1_0000,
-        _=>core::panic!("invalid ram size register {:#X}", ram_size_register)
+        0x0=>Ok(0),
+        0x1=>Ok(0x800),     // Unofficial - Undefined according to official docs
+        0x2=>Ok(0x2000),
+        0x3=>Ok(0x8000),
+        0x4=>Ok(0x2_0000),
+        0x5=>Ok(0x1_0000),
+        _=>Err(CartridgeError::InvalidRamSize(ram_size_register))
     }
 }
 
-pub fn init_ram(ram_reg:u8, external_ram:Option<&'static mut[u8]>)->&'static mut [u8]{
-    let ram_size = get_ram_size(ram_reg);
+pub fn init_ram(ram_reg:u8, external_ram:Option<&'static mut[u8]>)->Result<&'static mut [u8], CartridgeError>{
+    let ram_size = get_ram_size(ram_reg)?;
     
     match external_ram{
         Some(ram)=>{
             if ram.len() != ram_size{
-                core::panic!("External ram is not in the correct size for the cartridge, the save seems corrupted, either fix or delete it and try again");
+                return Err(CartridgeError::SaveSizeMismatch{expected:ram_size, actual:ram.len()});
             }
 
-            return ram;
+            return Ok(ram);
         }
-        None=>static_alloc_array(ram_size)
+        None=>Ok(static_alloc_array(ram_size))
     }
 }
 
@@ -50,7 +102,20 @@ pub(self) fn get_external_ram_valid_address(address:usize, external_ram:&[u8])->
     address as usize & (external_ram.len() - 1)
 }
 
-pub trait Mbc{
+pub(self) fn save_ram_state(ram:&[u8], writer:&mut StateWriter){
+    writer.write_u32(ram.len() as u32);
+    writer.write_bytes(ram);
+}
+
+pub(self) fn load_ram_state(ram:&mut [u8], reader:&mut StateReader)->Result<(), SaveStateError>{
+    if reader.read_u32()? as usize != ram.len(){
+        return Err(SaveStateError::MachineMismatch);
+    }
+    return reader.read_bytes(ram);
+}
+
+/// The state of the cartridge (banking registers and ram) is part of the machine save state
+pub trait Mbc: SaveState{
     fn get_ram(&mut self)->&mut [u8];
     fn has_battery(&self)->bool;
 
@@ -60,6 +125,23 @@ pub trait Mbc{
     fn read_external_ram(&self, address:u16)->u8;
     fn write_external_ram(&mut self, address:u16, value:u8);
 
+    /// Advances the cartridge internal hardware (like a real time clock) by m_cycles in normal speed
+    fn cycle(&mut self, _m_cycles:u32){}
+    /// The real time clock state (if exists) in the BGB/VBA format, should be appended to the ram in the save file.
+    /// Other RTC's (like the HuC3) use the same footer size with their own layout
+    fn get_rtc_footer(&self, _current_timestamp:u64)->Option<[u8;RTC_FOOTER_SIZE]>{None}
+    fn load_rtc_footer(&mut self, _footer:&[u8], _current_timestamp:u64){}
+    /// Cartridges without a rumble motor ignores the device
+    fn set_rumble_device(&mut self, _device:&'static mut dyn RumbleDevice){}
+    /// Cartridges without an accelerometer ignores the provider
+    fn set_tilt_provider(&mut self, _provider:&'static mut dyn TiltProvider){}
+    /// Cartridges without a camera ignores the source
+    fn set_camera_image_source(&mut self, _source:&'static mut dyn CameraImageSource){}
+    /// Cartridges with an IR port (like the HuC1 and HuC3) use the infrared device instead of the CGB port
+    fn has_infrared_port(&self)->bool{false}
+    /// Cartridges without an IR port ignores the device
+    fn set_infrared_device(&mut self, _device:&'static mut dyn InfraredDevice){}
+
     #[cfg(feature = "dbg")]
     fn get_bank_number(&self)->u16;
 }
\ No newline at end of file
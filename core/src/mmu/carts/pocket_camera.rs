@@ -0,0 +1,210 @@
+use crate::utils::{bit_masks::*, save_state::*};
+use super::{*, camera_image_source::*};
+
+const RAM_ENABLE_VALUE:u8 = 0xA;
+// Setting bit 4 of the ram bank register maps the sensor registers instead of the ram
+const REGISTERS_MODE_MASK:u8 = BIT_4_MASK;
+
+// The M64282FP sensor registers
+const REGISTERS_COUNT:usize = 0x36;
+const CONTROL_REGISTER:usize = 0;
+const N_VH_GAIN_REGISTER:usize = 1;
+const EXPOSURE_HIGH_REGISTER:usize = 2;
+const EXPOSURE_LOW_REGISTER:usize = 3;
+const EDGE_INVERT_REGISTER:usize = 4;
+// 4x4 matrix of 3 thresholds each
+const DITHERING_MATRIX_REGISTER:usize = 6;
+const CAPTURE_MASK:u8 = BIT_0_MASK;
+const N_MASK:u8 = BIT_7_MASK;
+const GAIN_MASK:u8 = 0x1F;
+const INVERT_MASK:u8 = BIT_3_MASK;
+
+// The image is written to the start of ram as 2bpp tiles
+const IMAGE_RAM_ADDRESS:usize = 0x100;
+const TILES_PER_ROW:usize = CAMERA_IMAGE_WIDTH / 8;
+const TILE_SIZE:usize = 16;
+
+const CAPTURE_BASE_CYCLES:u32 = 32446;
+const CAPTURE_N_CYCLES:u32 = 512;
+// Exposure of this value with the minimum gain outputs the source image as is
+const EXPOSURE_REFERENCE:f32 = 0x1000 as f32;
+// Every gain step is about 0.5dB (10^(0.5/20))
+const GAIN_STEP:f32 = 1.0593;
+
+pub struct PocketCamera<'a>{
+    program:&'a[u8],
+    ram:&'static mut [u8],
+    ram_enable_register:u8,
+    rom_bank_register:u8,
+    ram_bank_register:u8,
+    registers:[u8;REGISTERS_COUNT],
+    capture_cycles_left:u32,
+    image:[[u8;CAMERA_IMAGE_WIDTH];CAMERA_IMAGE_HEIGHT],
+    test_pattern:TestPatternImageSource,
+    image_source:Option<&'static mut dyn CameraImageSource>
+}
+
+impl<'a> Mbc for PocketCamera<'a>{
+    fn get_ram(&mut self) ->&mut [u8] {
+        self.ram
+    }
+
+    fn has_battery(&self) ->bool {true}
+
+    fn read_bank0(&self, address: u16)->u8{
+        self.program[address as usize]
+    }
+
+    fn read_current_bank(&self, address:u16)->u8{
+        return self.program[(ROM_BANK_SIZE * self.rom_bank_register as usize + address as usize) % self.program.len()];
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8){
+        match address{
+            0..=0x1FFF      =>self.ram_enable_register = value,
+            0x2000..=0x3FFF =>self.rom_bank_register = value & 0x3F,
+            0x4000..=0x5FFF =>self.ram_bank_register = value,
+            0x6000..=0x7FFF =>{}
+            _=>core::panic!("cannot write to this address in pocket camera cartridge")
+        }
+    }
+
+    fn read_external_ram(&self, address: u16)->u8{
+        if self.ram_bank_register & REGISTERS_MODE_MASK != 0{
+            // Only the control register is readable, the capture bit is set while capturing
+            return match address as usize & 0x7F{
+                CONTROL_REGISTER => self.registers[CONTROL_REGISTER],
+                _ => 0
+            };
+        }
+        return self.ram[self.get_ram_address(address)];
+    }
+
+    fn write_external_ram(&mut self, address: u16, value: u8){
+        if self.ram_bank_register & REGISTERS_MODE_MASK != 0{
+            let register = address as usize & 0x7F;
+            if register < REGISTERS_COUNT{
+                self.registers[register] = value;
+                if register == CONTROL_REGISTER && value & CAPTURE_MASK != 0 && self.capture_cycles_left == 0{
+                    self.start_capture();
+                }
+            }
+        }
+        else if self.ram_enable_register & 0xF == RAM_ENABLE_VALUE{
+            let address = self.get_ram_address(address);
+            self.ram[address] = value;
+        }
+    }
+
+    fn cycle(&mut self, m_cycles:u32) {
+        if self.capture_cycles_left == 0{
+            return;
+        }
+        self.capture_cycles_left = self.capture_cycles_left.saturating_sub(m_cycles);
+        if self.capture_cycles_left == 0{
+            self.finish_capture();
+        }
+    }
+
+    fn set_camera_image_source(&mut self, source:&'static mut dyn CameraImageSource) {
+        self.image_source = Some(source);
+    }
+
+    #[cfg(feature = "dbg")]
+    fn get_bank_number(&self)->u16 { self.rom_bank_register as u16 }
+}
+
+impl<'a> PocketCamera<'a>{
+    pub fn new(program:&'a[u8], ram:Option<&'static mut[u8]>)->Result<Self, CartridgeError>{
+        let ram = init_ram(program[MBC_RAM_SIZE_LOCATION], ram)?;
+        return Ok(Self{
+            program,
+            ram,
+            ram_enable_register:0,
+            rom_bank_register:1,
+            ram_bank_register:0,
+            registers:[0;REGISTERS_COUNT],
+            capture_cycles_left:0,
+            image:[[0;CAMERA_IMAGE_WIDTH];CAMERA_IMAGE_HEIGHT],
+            test_pattern:TestPatternImageSource::new(),
+            image_source:None
+        });
+    }
+
+    fn get_ram_address(&self, address:u16)->usize{
+        get_external_ram_valid_address((self.ram_bank_register & 0xF) as usize * RAM_BANK_SIZE + address as usize, &self.ram)
+    }
+
+    fn get_exposure(&self)->u16{
+        (self.registers[EXPOSURE_HIGH_REGISTER] as u16) << 8 | self.registers[EXPOSURE_LOW_REGISTER] as u16
+    }
+
+    fn start_capture(&mut self){
+        let n_cycles = if self.registers[N_VH_GAIN_REGISTER] & N_MASK == 0 {CAPTURE_N_CYCLES} else {0};
+        self.capture_cycles_left = CAPTURE_BASE_CYCLES + n_cycles + (16 * self.get_exposure() as u32);
+        // The sensor is sampled at the start of the capture
+        match self.image_source.as_mut(){
+            Some(source) => source.capture(&mut self.image),
+            None => self.test_pattern.capture(&mut self.image)
+        }
+    }
+
+    fn finish_capture(&mut self){
+        // This is an approximation of the sensor analog output (edge enhancement is not emulated)
+        let gain = (0..self.registers[N_VH_GAIN_REGISTER] & GAIN_MASK).fold(1.0, |gain, _| gain * GAIN_STEP);
+        let exposure_factor = gain * self.get_exposure() as f32 / EXPOSURE_REFERENCE;
+        let invert = self.registers[EDGE_INVERT_REGISTER] & INVERT_MASK != 0;
+        for y in 0..CAMERA_IMAGE_HEIGHT{
+            for x in 0..CAMERA_IMAGE_WIDTH{
+                let mut value = (self.image[y][x] as f32 * exposure_factor).min(u8::MAX as f32) as u8;
+                if invert{
+                    value = !value;
+                }
+                let color = self.dither(x, y, value);
+                let address = IMAGE_RAM_ADDRESS + (((y / 8) * TILES_PER_ROW) + (x / 8)) * TILE_SIZE + ((y % 8) * 2);
+                let bit = 7 - (x % 8);
+                flip_bit_u8(&mut self.ram[address], bit as u8, color & 1 != 0);
+                flip_bit_u8(&mut self.ram[address + 1], bit as u8, color & 2 != 0);
+            }
+        }
+        self.registers[CONTROL_REGISTER] &= !CAPTURE_MASK;
+    }
+
+    // Each pixel is compared against the 3 thresholds of its matrix cell, darker than the first is black (3)
+    fn dither(&self, x:usize, y:usize, value:u8)->u8{
+        let matrix_index = DITHERING_MATRIX_REGISTER + (((y % 4) * 4) + (x % 4)) * 3;
+        let thresholds = &self.registers[matrix_index..matrix_index + 3];
+        return match value{
+            v if v < thresholds[0] => 3,
+            v if v < thresholds[1] => 2,
+            v if v < thresholds[2] => 1,
+            _ => 0
+        };
+    }
+}
+
+impl<'a> SaveState for PocketCamera<'a>{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_u8(self.ram_enable_register);
+        writer.write_u8(self.rom_bank_register);
+        writer.write_u8(self.ram_bank_register);
+        writer.write_bytes(&self.registers);
+        writer.write_u32(self.capture_cycles_left);
+        for row in &self.image{
+            writer.write_bytes(row);
+        }
+        save_ram_state(self.ram, writer);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.ram_enable_register = reader.read_u8()?;
+        self.rom_bank_register = reader.read_u8()?;
+        self.ram_bank_register = reader.read_u8()?;
+        reader.read_bytes(&mut self.registers)?;
+        self.capture_cycles_left = reader.read_u32()?;
+        for row in &mut self.image{
+            reader.read_bytes(row)?;
+        }
+        return load_ram_state(self.ram, reader);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::utils::save_state::*;
 use super::*;
 
 pub struct Rom<'a>{
@@ -29,10 +30,17 @@ impl<'a> Mbc for Rom<'a>{
     }
 
     fn read_external_ram(&self, address:u16)->u8{
+        // Without a ram chip the bus is open
+        if self.external_ram.is_empty(){
+            return 0xFF;
+        }
         self.external_ram[get_external_ram_valid_address(address as usize, &self.external_ram)]
     }
 
     fn write_external_ram(&mut self, address:u16, value:u8){
+        if self.external_ram.is_empty(){
+            return;
+        }
         self.external_ram[get_external_ram_valid_address(address as usize, &self.external_ram)] = value
     }
 
@@ -42,13 +50,23 @@ impl<'a> Mbc for Rom<'a>{
 
 impl<'a> Rom<'a>{
     
-    pub fn new(program:&'a[u8], battery:bool, ram:Option<&'static mut [u8]>)->Self{
+    pub fn new(program:&'a[u8], battery:bool, ram:Option<&'static mut [u8]>)->Result<Self, CartridgeError>{
         let ram_reg = program[MBC_RAM_SIZE_LOCATION];
-        let external_ram = init_ram(ram_reg, ram);
-        return Self{
+        let external_ram = init_ram(ram_reg, ram)?;
+        return Ok(Self{
             program,
             external_ram,
             battery
-        };
+        });
+    }
+}
+
+impl<'a> SaveState for Rom<'a>{
+    fn save_state(&self, writer:&mut StateWriter) {
+        save_ram_state(self.external_ram, writer);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        load_ram_state(self.external_ram, reader)
     }
 }
\ No newline at end of file
@@ -0,0 +1,162 @@
+use crate::utils::{GB_FREQUENCY, bit_masks::*, save_state::*};
+
+/// The BGB/VBA save footer - the current registers, the latched registers (each as u32) and a unix timestamp (u64)
+pub const RTC_FOOTER_SIZE:usize = 48;
+// Older VBA versions saves the timestamp as u32
+const RTC_LEGACY_FOOTER_SIZE:usize = 44;
+
+pub const RTC_REGISTERS_COUNT:usize = 5;
+const SECONDS:usize = 0;
+const MINUTES:usize = 1;
+const HOURS:usize = 2;
+const DAYS_LOW:usize = 3;
+const DAYS_HIGH:usize = 4;
+const REGISTERS_MASKS:[u8;RTC_REGISTERS_COUNT] = [0x3F, 0x3F, 0x1F, 0xFF, 0xC1];
+const HALT_MASK:u8 = BIT_6_MASK;
+const DAY_CARRY_MASK:u8 = BIT_7_MASK;
+const SECONDS_PER_DAY:u64 = 60 * 60 * 24;
+const MAX_DAYS:u64 = 512;
+
+/// The MBC3 real time clock, ticks every emulated second
+pub struct Rtc{
+    registers:[u8;RTC_REGISTERS_COUNT],
+    latched_registers:[u8;RTC_REGISTERS_COUNT],
+    cycles_counter:u32
+}
+
+impl Rtc{
+    pub fn new()->Self{
+        Self { registers: [0;RTC_REGISTERS_COUNT], latched_registers: [0;RTC_REGISTERS_COUNT], cycles_counter: 0 }
+    }
+
+    pub fn cycle(&mut self, m_cycles:u32){
+        if self.registers[DAYS_HIGH] & HALT_MASK != 0{
+            return;
+        }
+        self.cycles_counter += m_cycles;
+        while self.cycles_counter >= GB_FREQUENCY{
+            self.cycles_counter -= GB_FREQUENCY;
+            self.tick();
+        }
+    }
+
+    pub fn latch(&mut self){
+        self.latched_registers = self.registers;
+    }
+
+    pub fn read(&self, register:usize)->u8{
+        self.latched_registers[register]
+    }
+
+    pub fn write(&mut self, register:usize, value:u8){
+        let value = value & REGISTERS_MASKS[register];
+        // Writing to the seconds register resets the sub second counter
+        if register == SECONDS{
+            self.cycles_counter = 0;
+        }
+        self.registers[register] = value;
+        self.latched_registers[register] = value;
+    }
+
+    /// Used to catch up on the real time that passed while the emulator was closed
+    pub fn advance_seconds(&mut self, seconds:u64){
+        if self.registers[DAYS_HIGH] & HALT_MASK != 0{
+            return;
+        }
+        // Registers can be set to invalid values that behave differently when overflowing, tick one by one until they wrap
+        // back into range (at most a few hours of ticks) and calculate the rest
+        let mut seconds = seconds;
+        while seconds > 0 && (self.registers[SECONDS] >= 60 || self.registers[MINUTES] >= 60 || self.registers[HOURS] >= 24){
+            self.tick();
+            seconds -= 1;
+        }
+        let total_seconds = self.registers[SECONDS] as u64 + (self.registers[MINUTES] as u64 * 60) + (self.registers[HOURS] as u64 * 60 * 60)
+            + (self.get_days() as u64 * SECONDS_PER_DAY) + seconds;
+        let days = total_seconds / SECONDS_PER_DAY;
+        if days >= MAX_DAYS{
+            self.registers[DAYS_HIGH] |= DAY_CARRY_MASK;
+        }
+        self.set_days((days % MAX_DAYS) as u16);
+        self.registers[HOURS] = ((total_seconds / (60 * 60)) % 24) as u8;
+        self.registers[MINUTES] = ((total_seconds / 60) % 60) as u8;
+        self.registers[SECONDS] = (total_seconds % 60) as u8;
+    }
+
+    pub fn to_footer(&self, current_timestamp:u64)->[u8;RTC_FOOTER_SIZE]{
+        let mut footer = [0;RTC_FOOTER_SIZE];
+        for (i, value) in self.registers.iter().chain(self.latched_registers.iter()).enumerate(){
+            footer[i * 4..(i + 1) * 4].copy_from_slice(&(*value as u32).to_le_bytes());
+        }
+        footer[RTC_REGISTERS_COUNT * 8..].copy_from_slice(&current_timestamp.to_le_bytes());
+        return footer;
+    }
+
+    pub fn load_footer(&mut self, footer:&[u8], current_timestamp:u64){
+        let read_u32 = |index:usize| u32::from_le_bytes(footer[index * 4..(index + 1) * 4].try_into().unwrap());
+        for i in 0..RTC_REGISTERS_COUNT{
+            self.registers[i] = read_u32(i) as u8 & REGISTERS_MASKS[i];
+            self.latched_registers[i] = read_u32(i + RTC_REGISTERS_COUNT) as u8 & REGISTERS_MASKS[i];
+        }
+        let timestamp_index = RTC_REGISTERS_COUNT * 8;
+        let saved_timestamp = match footer.len(){
+            RTC_FOOTER_SIZE => u64::from_le_bytes(footer[timestamp_index..].try_into().unwrap()),
+            _ => read_u32(RTC_REGISTERS_COUNT * 2) as u64
+        };
+        self.cycles_counter = 0;
+        self.advance_seconds(current_timestamp.saturating_sub(saved_timestamp));
+    }
+
+    fn tick(&mut self){
+        // The counters wrap at their bit width, invalid values wont carry until they overflow
+        self.registers[SECONDS] = (self.registers[SECONDS] + 1) & REGISTERS_MASKS[SECONDS];
+        if self.registers[SECONDS] != 60 {return}
+        self.registers[SECONDS] = 0;
+        self.registers[MINUTES] = (self.registers[MINUTES] + 1) & REGISTERS_MASKS[MINUTES];
+        if self.registers[MINUTES] != 60 {return}
+        self.registers[MINUTES] = 0;
+        self.registers[HOURS] = (self.registers[HOURS] + 1) & REGISTERS_MASKS[HOURS];
+        if self.registers[HOURS] != 24 {return}
+        self.registers[HOURS] = 0;
+        let days = self.get_days() + 1;
+        if days as u64 == MAX_DAYS{
+            self.registers[DAYS_HIGH] |= DAY_CARRY_MASK;
+        }
+        self.set_days(days % MAX_DAYS as u16);
+    }
+
+    fn get_days(&self)->u16{
+        ((self.registers[DAYS_HIGH] & BIT_0_MASK) as u16) << 8 | self.registers[DAYS_LOW] as u16
+    }
+
+    fn set_days(&mut self, days:u16){
+        self.registers[DAYS_LOW] = days as u8;
+        self.registers[DAYS_HIGH] = (self.registers[DAYS_HIGH] & !BIT_0_MASK) | ((days >> 8) as u8 & BIT_0_MASK);
+    }
+}
+
+/// Splits the save data to the ram and the rtc footer (if exists),
+/// the footer can be detected since the ram sizes are all multiples of 0x800.
+pub fn split_rtc_footer(save_data:&[u8])->(&[u8], Option<&[u8]>){
+    return match save_data.len() % 0x800{
+        RTC_FOOTER_SIZE | RTC_LEGACY_FOOTER_SIZE => {
+            let (ram, footer) = save_data.split_at(save_data.len() - (save_data.len() % 0x800));
+            (ram, Some(footer))
+        }
+        _ => (save_data, None)
+    };
+}
+
+impl SaveState for Rtc{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_bytes(&self.registers);
+        writer.write_bytes(&self.latched_registers);
+        writer.write_u32(self.cycles_counter);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        reader.read_bytes(&mut self.registers)?;
+        reader.read_bytes(&mut self.latched_registers)?;
+        self.cycles_counter = reader.read_u32()?;
+        return Ok(());
+    }
+}
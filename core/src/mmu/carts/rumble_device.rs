@@ -0,0 +1,4 @@
+/// Output for cartridges with a rumble motor (like the MBC5 rumble carts)
+pub trait RumbleDevice{
+    fn set_rumble(&mut self, enabled:bool);
+}
@@ -1,3 +1,4 @@
+use crate::{cheats::cheat_engine::CheatEngine, infrared::infrared_device::InfraredDevice, utils::save_state::*};
 use super::{ram::Ram, carts::Mbc};
 
 pub const GB_BOOT_ROM_SIZE:usize = 0x100;
@@ -14,35 +15,41 @@ pub struct ExternalMemoryBus<'a>{
     mbc: &'a mut dyn Mbc,
     bootrom :Option<Bootrom>,
     bootrom_register:u8,
-    finished_boot: bool
+    finished_boot: bool,
+    cartridge_checksum:u16,
+    pub cheat_engine: CheatEngine
 }
 
 impl<'a> ExternalMemoryBus<'a> {
     pub fn new(mbc:&'a mut dyn Mbc, bootrom: Option<Bootrom>)->Self{
+        // The global checksum of the cartridge header, used to identify the cartridge.
+        // Reading it once since some MBCs (like MBC1) can remap the bank0 area later
+        let cartridge_checksum = (mbc.read_bank0(0x14E) as u16) << 8 | mbc.read_bank0(0x14F) as u16;
         Self{
+            cartridge_checksum,
             mbc,
             ram:Ram::default(),
             bootrom,
             bootrom_register: 0,
-            finished_boot: false
+            finished_boot: false,
+            cheat_engine: CheatEngine::new()
         }
     }
 
     pub fn read(&mut self, address:u16)->u8 {
-        return match address{
+        let value = match address{
             0x0000..=0x00FF=>{
                 match self.bootrom{
-                    Some(Bootrom::Gb(r)) => r[address as usize],
-                    Some(Bootrom::Gbc(r)) => r[address as usize],
-                    None=>self.mbc.read_bank0(address),
+                    Some(Bootrom::Gb(r)) if !self.finished_boot => r[address as usize],
+                    Some(Bootrom::Gbc(r)) if !self.finished_boot => r[address as usize],
+                    _=>self.mbc.read_bank0(address),
                 }
             }
             0x0100..=0x01FF=>self.mbc.read_bank0(address),
             0x0200..=0x08FF=>{
                 match self.bootrom {
-                    Some(Bootrom::Gbc(r))=>r[address as usize],
-                    Some(Bootrom::Gb(_)) | 
-                    None=>self.mbc.read_bank0(address)
+                    Some(Bootrom::Gbc(r)) if !self.finished_boot => r[address as usize],
+                    _=>self.mbc.read_bank0(address)
                 }
             }
             0x0900..=0x3FFF=>self.mbc.read_bank0(address),
@@ -52,7 +59,13 @@ impl<'a> ExternalMemoryBus<'a> {
             0xD000..=0xDFFF=>self.ram.read_current_bank(address - 0xD000),
             0xE000..=0xFDFF=>self.ram.read_bank0(address - 0xE000),
             _=>core::panic!("Error: attemp to read invalid external memory bus address: {:#X}", address)
+        };
+
+        // Game Genie codes patch only the cartridge ROM reads
+        if address <= 0x7FFF && self.finished_boot{
+            return self.cheat_engine.patch_rom_read(address, value);
         }
+        return value;
     }
 
     pub fn write(&mut self, address:u16, value:u8) {
@@ -66,17 +79,27 @@ impl<'a> ExternalMemoryBus<'a> {
         }
     }
 
+    pub fn cycle_mbc(&mut self, m_cycles:u32){self.mbc.cycle(m_cycles)}
+
+    pub fn cartridge_has_infrared_port(&self)->bool{self.mbc.has_infrared_port()}
+
+    pub fn set_cartridge_infrared_device(&mut self, device:&'static mut dyn InfraredDevice){self.mbc.set_infrared_device(device)}
+
     pub fn read_boot_reg(&self) -> u8 {self.bootrom_register}
     pub fn write_boot_reg(&mut self, value:u8) {
         self.bootrom_register = value;
+        // Keeping the bootrom around (but unmapped) in order to be able to load states saved during the boot sequence
         if value != 0 && !self.finished_boot{
-            self.bootrom = None;
             self.finished_boot = true;
         }
     }
 
     pub fn finished_boot(&self)->bool {self.finished_boot}
 
+    pub fn has_bootrom(&self)->bool {self.bootrom.is_some()}
+
+    pub fn read_cartridge_checksum(&self)->u16 {self.cartridge_checksum}
+
     pub fn read_svbk_reg(&self)->u8 {self.ram.get_bank()}
     pub fn write_svbk_reg(&mut self, value:u8) {self.ram.set_bank(value)}
 
@@ -85,4 +108,27 @@ impl<'a> ExternalMemoryBus<'a> {
 
     #[cfg(feature = "dbg")]
     pub fn get_current_ram_bank(&self)->u8 { self.ram.get_bank() }
+
+    #[cfg(feature = "dbg")]
+    pub fn dbg_read_wram_bank(&self, bank:u8, address:u16)->u8 { self.ram.read_bank(bank, address) }
+
+    #[cfg(feature = "dbg")]
+    pub fn dbg_get_sram(&mut self)->&[u8] { self.mbc.get_ram() }
+}
+
+impl<'a> SaveState for ExternalMemoryBus<'a>{
+    fn save_state(&self, writer:&mut StateWriter) {
+        self.ram.save_state(writer);
+        self.mbc.save_state(writer);
+        writer.write_u8(self.bootrom_register);
+        writer.write_bool(self.finished_boot);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.ram.load_state(reader)?;
+        self.mbc.load_state(reader)?;
+        self.bootrom_register = reader.read_u8()?;
+        self.finished_boot = reader.read_bool()?;
+        return Ok(());
+    }
 }
\ No newline at end of file
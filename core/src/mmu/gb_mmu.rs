@@ -1,5 +1,5 @@
 use super::{access_bus::AccessBus, carts::{Mbc, CGB_FLAG_ADDRESS}, external_memory_bus::{Bootrom, ExternalMemoryBus}, interrupts_handler::InterruptRequest, io_bus::IoBus, Memory};
-use crate::{apu::{audio_device::AudioDevice, gb_apu::GbApu}, keypad::joypad_provider::JoypadProvider, machine::Mode, ppu::{color::Color, gfx_device::GfxDevice, ppu_state::PpuState}, utils::{bit_masks::{flip_bit_u8, BIT_7_MASK}, memory_registers::*}};
+use crate::{apu::{audio_device::AudioDevice, gb_apu::GbApu}, cheats::{cheat::{Cheat, GameSharkCode}, cheat_engine::MAX_CHEATS}, keypad::joypad_provider::JoypadProvider, machine::Mode, ppu::{color::Color, gfx_device::GfxDevice, ppu_state::PpuState}, utils::{bit_masks::{flip_bit_u8, BIT_7_MASK}, memory_registers::*, save_state::*}};
 
 const HRAM_SIZE:usize = 0x7F;
 
@@ -13,6 +13,8 @@ pub struct GbMmu<'a, D:AudioDevice, G:GfxDevice, J:JoypadProvider>{
     double_speed_mode:bool,
     halt: bool,
     mode:Mode,
+    // Every M-cycle that passed, used to measure the duration of a step
+    m_cycles_counter:u32,
     #[cfg(feature = "dbg")]
     pub mem_watch: crate::debugger::MemoryWatcher,
 }
@@ -179,6 +181,7 @@ impl<'a, D:AudioDevice, G:GfxDevice, J:JoypadProvider> GbMmu<'a, D, G, J>{
             double_speed_mode:false,
             halt: false,
             mode,
+            m_cycles_counter: 0,
             #[cfg(feature = "dbg")]
             mem_watch: crate::debugger::MemoryWatcher::new()
         };
@@ -225,21 +228,71 @@ impl<'a, D:AudioDevice, G:GfxDevice, J:JoypadProvider> GbMmu<'a, D, G, J>{
     }
 
     pub fn cycle(&mut self, m_cycles:u8){
+        self.m_cycles_counter = self.m_cycles_counter.wrapping_add(m_cycles as u32);
         flip_bit_u8(&mut self.io_bus.speed_switch_register, 7, self.double_speed_mode);
         self.occupied_access_bus = self.io_bus.cycle(m_cycles as u32, self.double_speed_mode, self.halt, &mut self.external_memory_bus);
     }
 
+    pub fn m_cycles_counter(&self)->u32{
+        self.m_cycles_counter
+    }
+
     pub fn handle_interrupts(&mut self, master_interrupt_enable:bool)->InterruptRequest{
         return self.io_bus.interrupt_handler.handle_interrupts(master_interrupt_enable, self.io_bus.ppu.stat_register);
     }
 
+    pub fn set_serial_device(&mut self, device:&'static mut dyn crate::serial::serial_device::SerialDevice){
+        self.io_bus.serial.set_device(device);
+    }
+
+    pub fn set_infrared_device(&mut self, device:&'static mut dyn crate::infrared::infrared_device::InfraredDevice){
+        // A cartridge IR port takes over the device, the CGB port is left without a partner
+        if self.external_memory_bus.cartridge_has_infrared_port(){
+            self.external_memory_bus.set_cartridge_infrared_device(device);
+        }
+        else{
+            self.io_bus.infrared.set_device(device);
+        }
+    }
+
     pub fn poll_joypad_state(&mut self){
         self.io_bus.joypad_handler.poll_joypad_state();
     }
 
+    pub fn add_cheat(&mut self, cheat:Cheat)->bool{
+        self.external_memory_bus.cheat_engine.add(cheat)
+    }
+
+    pub fn clear_cheats(&mut self){
+        self.external_memory_bus.cheat_engine.clear();
+    }
+
+    /// GameShark codes are applied once every frame
+    pub fn apply_game_shark_codes(&mut self){
+        let engine = &self.external_memory_bus.cheat_engine;
+        if engine.game_shark_codes().is_empty(){
+            return;
+        }
+        let mut codes = [GameSharkCode::default();MAX_CHEATS];
+        let codes_count = engine.game_shark_codes().len();
+        codes[..codes_count].copy_from_slice(engine.game_shark_codes());
+        for code in &codes[..codes_count]{
+            // Banks 0x90-0x97 select the CGB WRAM bank
+            if (0xD000..=0xDFFF).contains(&code.address) && code.bank & 0xF8 == 0x90{
+                let ram_bank = self.external_memory_bus.read_svbk_reg();
+                self.external_memory_bus.write_svbk_reg(code.bank & 0b111);
+                self.write_unprotected(code.address, code.value);
+                self.external_memory_bus.write_svbk_reg(ram_bank);
+            }
+            else{
+                self.write_unprotected(code.address, code.value);
+            }
+        }
+    }
+
     pub fn dma_block_cpu(&self)->bool{
         return match self.mode {
-            Mode::DMG => false,
+            Mode::DMG | Mode::SGB => false,
             Mode::CGB => self.io_bus.vram_dma_controller.should_block_cpu(),
         };
     }
@@ -252,6 +305,27 @@ impl<'a, D:AudioDevice, G:GfxDevice, J:JoypadProvider> GbMmu<'a, D, G, J>{
     #[cfg(feature = "dbg")]
     pub fn dbg_read(&mut self, address:u16)->u8{self.read_unprotected(address)}
 
+    /// Reads the switchable WRAM and the cartridge ram from a specific bank regardless of the mapped one,
+    /// the cartridge ram is read directly (even when disabled) and reads past its end return 0xFF
+    #[cfg(feature = "dbg")]
+    pub fn dbg_read_bank(&mut self, address:u16, bank:u16)->u8{
+        return match address{
+            0xA000..=0xBFFF => {
+                let offset = bank as usize * 0x2000 + (address - 0xA000) as usize;
+                self.external_memory_bus.dbg_get_sram().get(offset).copied().unwrap_or(0xFF)
+            }
+            0xD000..=0xDFFF => self.external_memory_bus.dbg_read_wram_bank(bank as u8, address - 0xD000),
+            _ => self.read_unprotected(address)
+        };
+    }
+
+    /// The switchable WRAM banks are 1-7 in CGB mode and only 1 otherwise
+    #[cfg(feature = "dbg")]
+    pub fn wram_banks_count(&self)->u16{if self.mode == Mode::CGB {7} else {1}}
+
+    #[cfg(feature = "dbg")]
+    pub fn sram_size(&mut self)->usize{self.external_memory_bus.dbg_get_sram().len()}
+
     #[cfg(feature = "dbg")]
     pub fn get_current_bank(&self, address:u16)->u16{
         return match address{
@@ -287,4 +361,46 @@ impl<'a, D:AudioDevice, G:GfxDevice, J:JoypadProvider> GbMmu<'a, D, G, J>{
         self.write(address, (bgr555_value & 0xFF) as u8, 0);
         self.write(address, ((bgr555_value >> 8) & 0xFF) as u8, 0);
     }
+}
+
+// The machine configuration is saved first in order to verify it before overriding any of the state
+impl<'a, D:AudioDevice, G:GfxDevice, J:JoypadProvider> SaveState for GbMmu<'a, D, G, J>{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_u8(self.mode as u8);
+        writer.write_u16(self.external_memory_bus.read_cartridge_checksum());
+        writer.write_bool(self.external_memory_bus.finished_boot());
+        self.io_bus.save_state(writer);
+        self.external_memory_bus.save_state(writer);
+        AccessBus::save_option_state(&self.occupied_access_bus, writer);
+        writer.write_bytes(&self.hram);
+        writer.write_bool(self.double_speed_mode);
+        writer.write_bool(self.halt);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        let mode = reader.read_u8()?;
+        let cartridge_checksum = reader.read_u16()?;
+        let finished_boot = reader.read_bool()?;
+        if mode != self.mode as u8 || cartridge_checksum != self.external_memory_bus.read_cartridge_checksum(){
+            return Err(SaveStateError::MachineMismatch);
+        }
+        // States saved during the boot sequence requires the bootrom
+        if !finished_boot && !self.external_memory_bus.has_bootrom(){
+            return Err(SaveStateError::MachineMismatch);
+        }
+
+        self.io_bus.load_state(reader)?;
+        self.external_memory_bus.load_state(reader)?;
+        self.occupied_access_bus = AccessBus::load_option_state(reader)?;
+        reader.read_bytes(&mut self.hram)?;
+        self.double_speed_mode = reader.read_bool()?;
+        self.halt = reader.read_bool()?;
+
+        #[cfg(feature = "dbg")]
+        {
+            self.mem_watch.current_rom_bank_number = self.external_memory_bus.get_current_rom_bank();
+            self.mem_watch.current_ram_bank_number = self.external_memory_bus.get_current_ram_bank();
+        }
+        return Ok(());
+    }
 }
\ No newline at end of file
@@ -1,4 +1,4 @@
-use crate::utils::bit_masks::*;
+use crate::utils::{bit_masks::*, save_state::*};
 
 const V_BLANK_INTERRUPT_ADDERESS:u16    = 0x40;
 const LCD_STAT_INTERRUPT_ADDERESS:u16   = 0x48;
@@ -70,4 +70,19 @@ impl InterruptsHandler{
         
         return InterruptRequest::Interrupt(address);
     }
+}
+
+impl SaveState for InterruptsHandler{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_u8(self.interrupt_flag);
+        writer.write_u8(self.interrupt_enable_flag);
+        writer.write_bool(self.ei_triggered);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.interrupt_flag = reader.read_u8()?;
+        self.interrupt_enable_flag = reader.read_u8()?;
+        self.ei_triggered = reader.read_bool()?;
+        return Ok(());
+    }
 }
\ No newline at end of file
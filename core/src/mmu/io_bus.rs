@@ -3,7 +3,10 @@ use crate::{
     keypad::{joypad_handler::JoypadHandler, joypad_provider::JoypadProvider}, 
     machine::Mode, 
     ppu::{gb_ppu::GbPpu, gfx_device::GfxDevice}, 
-    timer::{gb_timer::GbTimer, timer_register_updater::*}, utils::bit_masks::BIT_2_MASK
+    serial::gb_serial::GbSerial,
+    infrared::gb_infrared::GbInfrared,
+    sgb::gb_sgb::GbSgb,
+    timer::{gb_timer::GbTimer, timer_register_updater::*}, utils::{bit_masks::BIT_2_MASK, save_state::*}
 };
 use super::{interrupts_handler::*, io_ports::*, oam_dma_controller::OamDmaController, vram_dma_controller::VramDmaController, external_memory_bus::ExternalMemoryBus, access_bus::AccessBus};
 
@@ -19,6 +22,10 @@ pub struct IoBus<AD:AudioDevice, GFX:GfxDevice, JP:JoypadProvider>{
     pub vram_dma_controller: VramDmaController,
     pub interrupt_handler:InterruptsHandler,
     pub joypad_handler: JoypadHandler<JP>,
+    pub serial: GbSerial,
+    pub infrared: GbInfrared,
+    // Exists only in SGB mode
+    pub sgb: Option<GbSgb>,
     pub speed_switch_register:u8,
     mode: Mode,
     key0_register:u8,
@@ -95,7 +102,13 @@ impl<AD:AudioDevice, GFX:GfxDevice, JP:JoypadProvider> IoBus<AD, GFX, JP>{
             WY_REGISTER_INDEX => self.ppu.window_pos.y,
             WX_REGISTER_INDEX=> self.ppu.get_wx_register(),
             //Joypad
-            JOYP_REGISTER_INDEX => self.joypad_handler.get_register(),
+            JOYP_REGISTER_INDEX => {
+                let joyp_register = self.joypad_handler.get_register();
+                self.sgb.as_ref().map_or(joyp_register, |sgb|sgb.read_joyp(joyp_register))
+            }
+            //Serial
+            SB_REGISTER_INDEX => self.serial.sb_register,
+            SC_REGISTER_INDEX => self.serial.get_sc_register(),
 
             // CGB registers
             _ if self.mode == Mode::CGB => match address{
@@ -106,6 +119,8 @@ impl<AD:AudioDevice, GFX:GfxDevice, JP:JoypadProvider> IoBus<AD, GFX, JP>{
                 ORPI_REGISTER_INDEX => self.ppu.get_orpi(),
                 // VRAM DMA
                 HDMA5_REGISTER_INDEX =>self.vram_dma_controller.get_mode_length(),
+                //Infrared
+                RP_REGISTER_INDEX => self.infrared.get_rp_register(),
                 //Color ram
                 BGPI_REGISTER_INDEX =>self.ppu.get_bgpi(),
                 BGPD_REGISTER_INDEX =>self.ppu.get_bgpd(),
@@ -168,7 +183,14 @@ impl<AD:AudioDevice, GFX:GfxDevice, JP:JoypadProvider> IoBus<AD, GFX, JP>{
             OBP1_REGISTER_INDEX=> self.ppu.set_obp_palette_register(value, true),
             WY_REGISTER_INDEX=> self.ppu.set_wy_register(value),
             WX_REGISTER_INDEX=> self.ppu.set_wx_register(value),
-            JOYP_REGISTER_INDEX => self.joypad_handler.set_register(value),
+            JOYP_REGISTER_INDEX => {
+                self.joypad_handler.set_register(value);
+                if let Some(sgb) = self.sgb.as_mut(){
+                    sgb.write_joyp(value);
+                }
+            }
+            SB_REGISTER_INDEX => self.serial.sb_register = value,
+            SC_REGISTER_INDEX => self.serial.set_sc_register(value),
 
             // CGB registers
             _ if self.mode == Mode::CGB => match address {
@@ -190,6 +212,7 @@ impl<AD:AudioDevice, GFX:GfxDevice, JP:JoypadProvider> IoBus<AD, GFX, JP>{
                 HDMA3_REGISTER_INDEX =>self.vram_dma_controller.set_dest_high(value),
                 HDMA4_REGISTER_INDEX =>self.vram_dma_controller.set_dest_low(value),
                 HDMA5_REGISTER_INDEX =>self.vram_dma_controller.set_mode_length(value),
+                RP_REGISTER_INDEX => self.infrared.set_rp_register(value),
                 // COLOR Ram
                 BGPI_REGISTER_INDEX =>self.ppu.set_bgpi(value),
                 BGPD_REGISTER_INDEX =>self.ppu.set_bgpd(value),
@@ -210,6 +233,9 @@ impl<AD:AudioDevice, GFX:GfxDevice, JP:JoypadProvider> IoBus<AD, GFX, JP>{
             vram_dma_controller: VramDmaController::new(),
             interrupt_handler: InterruptsHandler::default(),
             joypad_handler: JoypadHandler::new(joypad_provider),
+            serial: GbSerial::new(mode),
+            infrared: GbInfrared::new(),
+            sgb: if mode == Mode::SGB {Some(GbSgb::new())} else {None},
             speed_switch_register:0,
             speed_cycle_reminder:0,
             apu_cycles_counter:0,
@@ -232,15 +258,20 @@ impl<AD:AudioDevice, GFX:GfxDevice, JP:JoypadProvider> IoBus<AD, GFX, JP>{
             self.cycle_timer();
         }
 
+        // The serial clock is effected by double speed mode as well
+        self.serial.cycle(cycles, &mut self.interrupt_handler.interrupt_flag);
+
         let access_bus = self.oam_dma_controller.cycle(cycles, external_memory_bus, &mut self.ppu);
 
-        // APU, PPU and vram dma are not effected by the speed mode
+        // APU, PPU, vram dma and the cartridge RTC are not effected by the speed mode
         if double_speed_mode{
             cycles += self.speed_cycle_reminder as u32;
             self.speed_cycle_reminder = cycles as u8 & 1;   // Saves the LSB (the bit to indicate odd number)
             cycles >>= 1;                                   // divide by 2 (discard the LSB bit)
         }
 
+        external_memory_bus.cycle_mbc(cycles);
+
         if !halt {
             // HDMA is disabled during halt mode
             self.vram_dma_controller.cycle(cycles, external_memory_bus, &mut self.ppu);
@@ -276,6 +307,9 @@ impl<AD:AudioDevice, GFX:GfxDevice, JP:JoypadProvider> IoBus<AD, GFX, JP>{
     fn cycle_ppu(&mut self){
         self.ppu_event = self.ppu.cycle(self.ppu_cycles, &mut self.interrupt_handler.interrupt_flag);
         self.ppu_cycles = 0;
+        if let Some(sgb) = self.sgb.as_mut(){
+            self.ppu.present_sgb_frame(sgb);
+        }
     }
 
     fn cycle_apu(&mut self){
@@ -290,4 +324,60 @@ impl<AD:AudioDevice, GFX:GfxDevice, JP:JoypadProvider> IoBus<AD, GFX, JP>{
         self.timer_event_cycles = self.timer.cycle(self.timer_cycles, &mut self.interrupt_handler.interrupt_flag);
         self.timer_cycles = 0;
     }
+}
+
+impl<AD:AudioDevice, GFX:GfxDevice, JP:JoypadProvider> SaveState for IoBus<AD, GFX, JP>{
+    fn save_state(&self, writer:&mut StateWriter) {
+        self.apu.save_state(writer);
+        self.timer.save_state(writer);
+        self.ppu.save_state(writer);
+        self.oam_dma_controller.save_state(writer);
+        self.vram_dma_controller.save_state(writer);
+        self.interrupt_handler.save_state(writer);
+        self.joypad_handler.save_state(writer);
+        self.serial.save_state(writer);
+        self.infrared.save_state(writer);
+        if let Some(sgb) = self.sgb.as_ref(){
+            sgb.save_state(writer);
+        }
+        writer.write_u8(self.speed_switch_register);
+        writer.write_u8(self.key0_register);
+        writer.write_bool(self.boot_finished);
+        writer.write_u8(self.speed_cycle_reminder);
+        writer.write_u32(self.apu_cycles_counter);
+        writer.write_u32(self.ppu_cycles);
+        writer.write_u32(self.timer_cycles);
+        writer.write_u32(self.timer_event_cycles);
+        writer.write_u32(self.apu_event_cycles);
+        writer.write_bool(self.ppu_event.is_some());
+        writer.write_u32(self.ppu_event.unwrap_or(0));
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.apu.load_state(reader)?;
+        self.timer.load_state(reader)?;
+        self.ppu.load_state(reader)?;
+        self.oam_dma_controller.load_state(reader)?;
+        self.vram_dma_controller.load_state(reader)?;
+        self.interrupt_handler.load_state(reader)?;
+        self.joypad_handler.load_state(reader)?;
+        self.serial.load_state(reader)?;
+        self.infrared.load_state(reader)?;
+        if let Some(sgb) = self.sgb.as_mut(){
+            sgb.load_state(reader)?;
+        }
+        self.speed_switch_register = reader.read_u8()?;
+        self.key0_register = reader.read_u8()?;
+        self.boot_finished = reader.read_bool()?;
+        self.speed_cycle_reminder = reader.read_u8()?;
+        self.apu_cycles_counter = reader.read_u32()?;
+        self.ppu_cycles = reader.read_u32()?;
+        self.timer_cycles = reader.read_u32()?;
+        self.timer_event_cycles = reader.read_u32()?;
+        self.apu_event_cycles = reader.read_u32()?;
+        let ppu_on = reader.read_bool()?;
+        let ppu_event = reader.read_u32()?;
+        self.ppu_event = if ppu_on {Some(ppu_event)} else {None};
+        return Ok(());
+    }
 }
\ No newline at end of file
@@ -36,6 +36,7 @@ pub_io_port_index!(HDMA2_REGISTER_INDEX, HDMA2_REGISTER_ADDRESS);
 pub_io_port_index!(HDMA3_REGISTER_INDEX, HDMA3_REGISTER_ADDRESS);
 pub_io_port_index!(HDMA4_REGISTER_INDEX, HDMA4_REGISTER_ADDRESS);
 pub_io_port_index!(HDMA5_REGISTER_INDEX, HDMA5_REGISTER_ADDRESS);
+pub_io_port_index!(RP_REGISTER_INDEX, RP_REGISTER_ADDRESS);
 pub_io_port_index!(BGPI_REGISTER_INDEX, BGPI_REGISTER_ADDRESS);
 pub_io_port_index!(BGPD_REGISTER_INDEX, BGPD_REGISTER_ADDRESS);
 pub_io_port_index!(OBPI_REGISTER_INDEX, OBPI_REGISTER_ADDRESS);
@@ -44,6 +45,8 @@ pub_io_port_index!(ORPI_REGISTER_INDEX, OPRI_REGISTER_ADDRESS);
 pub_io_port_index!(IF_REGISTER_INDEX, IF_REGISTER_ADDRESS);
 
 pub_io_port_index!(JOYP_REGISTER_INDEX, JOYP_REGISTER_ADDRESS);
+pub_io_port_index!(SB_REGISTER_INDEX, SB_REGISTER_ADDRESS);
+pub_io_port_index!(SC_REGISTER_INDEX, SC_REGISTER_ADDRESS);
 pub_io_port_index!(NR10_REGISTER_INDEX, NR10_REGISTER_ADDRESS);
 pub_io_port_index!(NR11_REGISTER_INDEX, NR11_REGISTER_ADDRESS);
 pub_io_port_index!(NR12_REGISTER_INDEX, NR12_REGISTER_ADDRESS);
@@ -1,4 +1,4 @@
-use crate::ppu::{gb_ppu::GbPpu, gfx_device::GfxDevice};
+use crate::{ppu::{gb_ppu::GbPpu, gfx_device::GfxDevice}, utils::save_state::*};
 use super::{external_memory_bus::ExternalMemoryBus, access_bus::AccessBus};
 
 const DMA_SIZE:u16 = 0xA0;
@@ -55,4 +55,19 @@ impl OamDmaController{
             0x80..=0x9F=> Some(AccessBus::Video),
         }
     }
+}
+
+impl SaveState for OamDmaController{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_u16(self.soure_address);
+        AccessBus::save_option_state(&self.enable, writer);
+        writer.write_u16(self.dma_cycle_counter);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.soure_address = reader.read_u16()?;
+        self.enable = AccessBus::load_option_state(reader)?;
+        self.dma_cycle_counter = reader.read_u16()?;
+        return Ok(());
+    }
 }
\ No newline at end of file
@@ -1,3 +1,4 @@
+use crate::utils::save_state::*;
 
 const RAM_SZIE:usize = 0x8000;
 const BANK_SIZE:usize = 0x1000;
@@ -30,6 +31,11 @@ impl Ram{
 
     pub fn get_bank(&self)->u8{self.ram_bank_register}
 
+    #[cfg(feature = "dbg")]
+    pub fn read_bank(&self, bank:u8, address:u16)->u8{
+        return self.memory[BANK_SIZE * bank as usize + address as usize];
+    }
+
     fn get_valid_address(&self, address:u16)->usize{
         let mut bank = self.ram_bank_register;
         if bank == 0 { bank = 1 };
@@ -44,4 +50,17 @@ impl Default for Ram{
             ram_bank_register:1
         }
     }
+}
+
+impl SaveState for Ram{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_bytes(&self.memory);
+        writer.write_u8(self.ram_bank_register);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        reader.read_bytes(&mut self.memory)?;
+        self.ram_bank_register = reader.read_u8()?;
+        return Ok(());
+    }
 }
\ No newline at end of file
@@ -1,4 +1,4 @@
-use crate::{utils::bit_masks::BIT_7_MASK, ppu::{gb_ppu::GbPpu, gfx_device::GfxDevice, ppu_state::PpuState}};
+use crate::{utils::{bit_masks::BIT_7_MASK, save_state::*}, ppu::{gb_ppu::GbPpu, gfx_device::GfxDevice, ppu_state::PpuState}};
 
 use super::external_memory_bus::ExternalMemoryBus;
 
@@ -139,4 +139,37 @@ impl VramDmaController{
             }
         }
     }
+}
+
+impl SaveState for VramDmaController{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_u16(self.source_address);
+        writer.write_u16(self.dest_address);
+        writer.write_u8(match self.mode{
+            TransferMode::GeneralPurpose=>0,
+            TransferMode::Hblank=>1,
+            TransferMode::Terminated=>2
+        });
+        writer.write_u8(self.remaining_length);
+        writer.write_bool(self.last_ly.is_some());
+        writer.write_u8(self.last_ly.unwrap_or(0));
+        writer.write_u8(self.hblank_transfer_burst_counter);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.source_address = reader.read_u16()?;
+        self.dest_address = reader.read_u16()?;
+        self.mode = match reader.read_u8()?{
+            0=>TransferMode::GeneralPurpose,
+            1=>TransferMode::Hblank,
+            2=>TransferMode::Terminated,
+            _=>return Err(SaveStateError::Corrupted)
+        };
+        self.remaining_length = reader.read_u8()?;
+        let has_last_ly = reader.read_bool()?;
+        let last_ly = reader.read_u8()?;
+        self.last_ly = if has_last_ly {Some(last_ly)} else {None};
+        self.hblank_transfer_burst_counter = reader.read_u8()?;
+        return Ok(());
+    }
 }
\ No newline at end of file
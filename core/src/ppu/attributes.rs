@@ -1,4 +1,4 @@
-use crate::utils::bit_masks::*;
+use crate::utils::{bit_masks::*, save_state::*};
 
 #[derive(Clone, Copy, Default)]
 pub struct Attributes{
@@ -17,6 +17,11 @@ impl Attributes{
             gbc_bank:(attribute & BIT_3_MASK) != 0,
         }
     }
+
+    // Reconstruct the bits this struct was created from (the other bits are discarded)
+    fn as_u8(&self)->u8{
+        ((self.bg_priority as u8) << 7) | ((self.flip_y as u8) << 6) | ((self.flip_x as u8) << 5) | ((self.gbc_bank as u8) << 3)
+    }
 }
 
 #[derive(Clone, Copy, Default)]
@@ -53,4 +58,37 @@ impl SpriteAttributes{
             visibility_end, visibility_start
         }
     }
+}
+
+impl SaveState for GbcBackgroundAttributes{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_u8(self.attribute.as_u8() | self.cgb_pallete_number);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        *self = Self::new(reader.read_u8()?);
+        return Ok(());
+    }
+}
+
+impl SaveState for SpriteAttributes{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_u8(self.y);
+        writer.write_u8(self.x);
+        writer.write_u8(self.tile_number);
+        writer.write_u8(self.attributes.as_u8() | ((self.gb_palette_number as u8) << 4) | self.gbc_palette_number);
+        writer.write_u8(self.visibility_start);
+        writer.write_u8(self.visibility_end);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        let y = reader.read_u8()?;
+        let x = reader.read_u8()?;
+        let tile_number = reader.read_u8()?;
+        let attributes = reader.read_u8()?;
+        let visibility_start = reader.read_u8()?;
+        let visibility_end = reader.read_u8()?;
+        *self = Self::new(y, x, tile_number, attributes, visibility_start, visibility_end);
+        return Ok(());
+    }
 }
\ No newline at end of file
@@ -1,3 +1,4 @@
+use crate::utils::save_state::*;
 use super::gfx_device::Pixel;
 
 pub const WHITE:Color = Color {r: 255,g: 255,b: 255};
@@ -34,4 +35,17 @@ impl From<u32> for Color{
     fn from(color: u32) -> Self {
         Self{ r: ((color >> 16) & 0xFF) as u8, g: ((color >> 8) & 0xFF) as u8, b: (color & 0xFF) as u8 }
     }
+}
+
+impl SaveState for Color{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_bytes(&[self.r, self.g, self.b]);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.r = reader.read_u8()?;
+        self.g = reader.read_u8()?;
+        self.b = reader.read_u8()?;
+        return Ok(());
+    }
 }
\ No newline at end of file
@@ -1,4 +1,4 @@
-use crate::{utils::{bit_masks::*, fixed_size_queue::FixedSizeQueue, vec2::Vec2}, ppu::{VRam, attributes::GbcBackgroundAttributes}};
+use crate::{utils::{bit_masks::*, fixed_size_queue::FixedSizeQueue, vec2::Vec2, save_state::*}, ppu::{VRam, attributes::GbcBackgroundAttributes}};
 use super::{FIFO_SIZE, SPRITE_WIDTH, fetching_state::*, get_decoded_pixel};
 
 #[derive(Clone, Copy, Default)]
@@ -154,4 +154,40 @@ impl BackgroundFetcher{
     fn is_rendering_wnd(&self, lcd_control:u8, window_pos:&Vec2<u8>)->bool{
         window_pos.x <= self.current_x_pos && self.has_wy_reached_ly && (lcd_control & BIT_5_MASK) != 0
     }
+}
+
+impl SaveState for BackgroundPixel{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_u8(self.color_index);
+        self.attributes.save_state(writer);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.color_index = reader.read_u8()?;
+        return self.attributes.load_state(reader);
+    }
+}
+
+impl SaveState for BackgroundFetcher{
+    fn save_state(&self, writer:&mut StateWriter) {
+        self.fifo.save_state(writer);
+        writer.write_u8(self.window_line_counter);
+        writer.write_bool(self.has_wy_reached_ly);
+        writer.write_bool(self.rendering_window);
+        writer.write_u8(self.current_x_pos);
+        self.fetcher_state_machine.save_state(writer);
+        writer.write_bool(self.scanline_rendering_started);
+        self.cgb_attribute.save_state(writer);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.fifo.load_state(reader)?;
+        self.window_line_counter = reader.read_u8()?;
+        self.has_wy_reached_ly = reader.read_bool()?;
+        self.rendering_window = reader.read_bool()?;
+        self.current_x_pos = reader.read_u8()?;
+        self.fetcher_state_machine.load_state(reader)?;
+        self.scanline_rendering_started = reader.read_bool()?;
+        return self.cgb_attribute.load_state(reader);
+    }
 }
\ No newline at end of file
@@ -1,3 +1,5 @@
+use crate::utils::save_state::*;
+
 // Since each operation takes 2 t_cycles I pad them with sleep for my implementation
 pub enum FetchingState{
     FetchTileNumber,
@@ -50,4 +52,27 @@ impl FetcherStateMachine{
     pub fn current_state(&self)->&FetchingState{
         &self.state_machine[self.state]
     }
+}
+
+impl SaveState for FetcherStateMachine{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_u8(self.state as u8);
+        writer.write_u16(self.data.tile_data_address);
+        writer.write_u8(self.data.tile_data);
+        writer.write_u8(self.data.low_tile_data);
+        writer.write_u8(self.data.high_tile_data);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        let state = reader.read_u8()? as usize;
+        if state >= self.state_machine.len(){
+            return Err(SaveStateError::Corrupted);
+        }
+        self.state = state;
+        self.data.tile_data_address = reader.read_u16()?;
+        self.data.tile_data = reader.read_u8()?;
+        self.data.low_tile_data = reader.read_u8()?;
+        self.data.high_tile_data = reader.read_u8()?;
+        return Ok(());
+    }
 }
\ No newline at end of file
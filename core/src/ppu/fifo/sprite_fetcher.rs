@@ -1,4 +1,4 @@
-use crate::{ppu::{attributes::SpriteAttributes, VRam}, utils::{self, bit_masks::{BIT_0_MASK, BIT_2_MASK}, fixed_size_queue::FixedSizeQueue}};
+use crate::{ppu::{attributes::SpriteAttributes, VRam}, utils::{self, bit_masks::{BIT_0_MASK, BIT_2_MASK}, fixed_size_queue::FixedSizeQueue, save_state::*}};
 use super::{FIFO_SIZE, SPRITE_WIDTH, fetching_state::*, get_decoded_pixel};
 
 pub const NORMAL_SPRITE_HIGHT:u8 = 8;
@@ -150,4 +150,45 @@ impl SpriteFetcher{
             tile_num as u16 * 16 + (2 * (16 - (sprite_attrib.y - ly_register))) as u16
         };
     }
+}
+
+impl SaveState for SpritePixel{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_u8(self.color_index);
+        writer.write_u8(self.oam_entry);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.color_index = reader.read_u8()?;
+        self.oam_entry = reader.read_u8()?;
+        return Ok(());
+    }
+}
+
+impl SaveState for SpriteFetcher{
+    fn save_state(&self, writer:&mut StateWriter) {
+        self.fifo.save_state(writer);
+        for entry in &self.oam_entries{
+            entry.save_state(writer);
+        }
+        writer.write_u8(self.oam_entries_len);
+        writer.write_bool(self.rendering);
+        self.fetcher_state_machine.save_state(writer);
+        writer.write_u8(self.current_oam_entry);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.fifo.load_state(reader)?;
+        for entry in &mut self.oam_entries{
+            entry.load_state(reader)?;
+        }
+        self.oam_entries_len = reader.read_u8()?;
+        if self.oam_entries_len as usize > MAX_SPRITES_PER_LINE{
+            return Err(SaveStateError::Corrupted);
+        }
+        self.rendering = reader.read_bool()?;
+        self.fetcher_state_machine.load_state(reader)?;
+        self.current_oam_entry = reader.read_u8()?;
+        return Ok(());
+    }
 }
\ No newline at end of file
@@ -1,6 +1,6 @@
 use core::cmp;
 
-use crate::{machine::Mode, utils::{bit_masks::*, vec2::Vec2}};
+use crate::{machine::Mode, sgb::gb_sgb::GbSgb, utils::{bit_masks::*, vec2::Vec2, save_state::*}};
 use super::{fifo::{SPRITE_WIDTH, background_fetcher::*, FIFO_SIZE, sprite_fetcher::*}, VRam, gfx_device::*, ppu_state::PpuState, attributes::SpriteAttributes, color::*};
 
 const WX_OFFSET:u8 = 7;
@@ -48,6 +48,7 @@ pub struct GbPpu<GFX: GfxDevice>{
     pub coincidence_interrupt_request:bool,
 
     vblank_occurred:bool, // a way to signal the rest of the system a vblank occurred
+    sgb_frame_ready:bool, // in SGB mode the frames are passed to the SGB instead of the gfx device
 
     gfx_device: GFX,
     m_cycles_passed:u16,
@@ -98,6 +99,7 @@ impl<GFX:GfxDevice> GbPpu<GFX>{
             oam_search_interrupt_request:false, 
             coincidence_interrupt_request:false,
             vblank_occurred:false,
+            sgb_frame_ready:false,
             screen_buffer_index:0, 
             m_cycles_passed:0,
             stat_triggered:false,
@@ -152,8 +154,22 @@ impl<GFX:GfxDevice> GbPpu<GFX>{
         return last_vblank_state;
     }
 
+    pub fn present_sgb_frame(&mut self, sgb:&mut GbSgb){
+        if !self.sgb_frame_ready{
+            return;
+        }
+        self.sgb_frame_ready = false;
+        let last_buffer_index = (self.current_screen_buffer_index + BUFFERS_NUMBER - 1) % BUFFERS_NUMBER;
+        sgb.present_frame(&self.screen_buffers[last_buffer_index], &mut self.gfx_device);
+    }
+
     fn swap_buffer(&mut self){
-        self.gfx_device.swap_buffer(&self.screen_buffers[self.current_screen_buffer_index]);
+        if self.mode == Mode::SGB{
+            self.sgb_frame_ready = true;
+        }
+        else{
+            self.gfx_device.swap_buffer(&self.screen_buffers[self.current_screen_buffer_index]);
+        }
         self.screen_buffer_index = 0;
         self.current_screen_buffer_index = (self.current_screen_buffer_index + 1) % BUFFERS_NUMBER;
     }
@@ -726,4 +742,102 @@ impl<GFX:GfxDevice> GbPpu<GFX>{
             oam_entry += 1;
         }
     }
+}
+
+// Only the screen buffer currently drawn is saved since the other one was already handed to the gfx device
+impl<GFX:GfxDevice> SaveState for GbPpu<GFX>{
+    fn save_state(&self, writer:&mut StateWriter) {
+        self.vram.save_state(writer);
+        writer.write_bytes(&self.oam);
+        writer.write_u8(self.state as u8);
+        writer.write_u8(self.lcd_control);
+        writer.write_u8(self.stat_register);
+        writer.write_u8(self.lyc_register);
+        writer.write_u8(self.ly_register);
+        writer.write_bytes(&[self.window_pos.x, self.window_pos.y, self.bg_pos.x, self.bg_pos.y]);
+        writer.write_u8(self.bg_palette_register);
+        for color in &self.bg_color_mapping{
+            color.save_state(writer);
+        }
+        writer.write_u8(self.obj_pallete_0_register);
+        writer.write_u8(self.obj_pallete_1_register);
+        for color in self.obj_color_mapping0.iter().chain(self.obj_color_mapping1.iter()){
+            writer.write_bool(color.is_some());
+            color.unwrap_or_default().save_state(writer);
+        }
+        writer.write_bytes(&self.bg_color_ram);
+        writer.write_u8(self.bg_color_pallete_index);
+        writer.write_bytes(&self.obj_color_ram);
+        writer.write_u8(self.obj_color_pallete_index);
+        writer.write_bool(self.cgb_enabled);
+        writer.write_bool(self.cgb_priority_mode);
+        writer.write_bool(self.v_blank_interrupt_request);
+        writer.write_bool(self.h_blank_interrupt_request);
+        writer.write_bool(self.oam_search_interrupt_request);
+        writer.write_bool(self.coincidence_interrupt_request);
+        writer.write_bool(self.vblank_occurred);
+        writer.write_u16(self.m_cycles_passed);
+        writer.write_u8(self.current_screen_buffer_index as u8);
+        writer.write_u16(self.screen_buffer_index as u16);
+        writer.write_u16_slice(&self.screen_buffers[self.current_screen_buffer_index]);
+        writer.write_u8(self.pixel_x_pos);
+        writer.write_bool(self.scanline_started);
+        self.bg_fetcher.save_state(writer);
+        self.sprite_fetcher.save_state(writer);
+        writer.write_bool(self.stat_triggered);
+        writer.write_bool(self.trigger_stat_interrupt);
+        writer.write_u8(self.next_state as u8);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.vram.load_state(reader)?;
+        reader.read_bytes(&mut self.oam)?;
+        self.state = PpuState::try_from(reader.read_u8()?).map_err(|_|SaveStateError::Corrupted)?;
+        self.lcd_control = reader.read_u8()?;
+        self.stat_register = reader.read_u8()?;
+        self.lyc_register = reader.read_u8()?;
+        self.ly_register = reader.read_u8()?;
+        self.window_pos.x = reader.read_u8()?;
+        self.window_pos.y = reader.read_u8()?;
+        self.bg_pos.x = reader.read_u8()?;
+        self.bg_pos.y = reader.read_u8()?;
+        self.bg_palette_register = reader.read_u8()?;
+        for color in &mut self.bg_color_mapping{
+            color.load_state(reader)?;
+        }
+        self.obj_pallete_0_register = reader.read_u8()?;
+        self.obj_pallete_1_register = reader.read_u8()?;
+        for color in self.obj_color_mapping0.iter_mut().chain(self.obj_color_mapping1.iter_mut()){
+            let has_color = reader.read_bool()?;
+            let mut value = Color::default();
+            value.load_state(reader)?;
+            *color = if has_color {Some(value)} else {None};
+        }
+        reader.read_bytes(&mut self.bg_color_ram)?;
+        self.bg_color_pallete_index = reader.read_u8()?;
+        reader.read_bytes(&mut self.obj_color_ram)?;
+        self.obj_color_pallete_index = reader.read_u8()?;
+        self.cgb_enabled = reader.read_bool()?;
+        self.cgb_priority_mode = reader.read_bool()?;
+        self.v_blank_interrupt_request = reader.read_bool()?;
+        self.h_blank_interrupt_request = reader.read_bool()?;
+        self.oam_search_interrupt_request = reader.read_bool()?;
+        self.coincidence_interrupt_request = reader.read_bool()?;
+        self.vblank_occurred = reader.read_bool()?;
+        self.m_cycles_passed = reader.read_u16()?;
+        self.current_screen_buffer_index = reader.read_u8()? as usize;
+        self.screen_buffer_index = reader.read_u16()? as usize;
+        if self.current_screen_buffer_index >= BUFFERS_NUMBER || self.screen_buffer_index > SCREEN_HEIGHT * SCREEN_WIDTH{
+            return Err(SaveStateError::Corrupted);
+        }
+        reader.read_u16_slice(&mut self.screen_buffers[self.current_screen_buffer_index])?;
+        self.pixel_x_pos = reader.read_u8()?;
+        self.scanline_started = reader.read_bool()?;
+        self.bg_fetcher.load_state(reader)?;
+        self.sprite_fetcher.load_state(reader)?;
+        self.stat_triggered = reader.read_bool()?;
+        self.trigger_stat_interrupt = reader.read_bool()?;
+        self.next_state = PpuState::try_from(reader.read_u8()?).map_err(|_|SaveStateError::Corrupted)?;
+        return Ok(());
+    }
 }
\ No newline at end of file
@@ -1,3 +1,4 @@
+use crate::sgb::gb_sgb::{SGB_SCREEN_HEIGHT, SGB_SCREEN_WIDTH};
 use super::gb_ppu::{SCREEN_HEIGHT, SCREEN_WIDTH};
 
 /// Pixel is in the format of RGB565 even though the CGB stores pixels as BGR555 as the gbdev docs indicates, RGB565 is much more used format now days
@@ -6,4 +7,10 @@ pub type Pixel = u16;
 
 pub trait GfxDevice{
     fn swap_buffer(&mut self, buffer:&[Pixel; SCREEN_HEIGHT * SCREEN_WIDTH]);
+
+    /// In SGB mode devices that return true receive the frames with the border through swap_sgb_buffer instead of swap_buffer
+    fn sgb_border_supported(&self)->bool{false}
+
+    /// The game screen is centered inside the border
+    fn swap_sgb_buffer(&mut self, _buffer:&[Pixel; SGB_SCREEN_HEIGHT * SGB_SCREEN_WIDTH]){}
 }
\ No newline at end of file
@@ -5,4 +5,18 @@ pub enum PpuState{
     Vblank = 0b01,
     OamSearch = 0b10,
     PixelTransfer = 0b11
+}
+
+impl TryFrom<u8> for PpuState{
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value{
+            0b00=>Ok(PpuState::Hblank),
+            0b01=>Ok(PpuState::Vblank),
+            0b10=>Ok(PpuState::OamSearch),
+            0b11=>Ok(PpuState::PixelTransfer),
+            _=>Err(())
+        }
+    }
 }
\ No newline at end of file
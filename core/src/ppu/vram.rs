@@ -1,3 +1,5 @@
+use crate::utils::save_state::*;
+
 const VRAM_SIZE:usize = 0x4000;
 const VRAM_BANK_SIZE:usize = 0x2000;
 
@@ -41,4 +43,17 @@ impl Default for VRam{
             current_bank_register:0
         }
     }
+}
+
+impl SaveState for VRam{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_bytes(&self.memory);
+        writer.write_u8(self.current_bank_register);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        reader.read_bytes(&mut self.memory)?;
+        self.current_bank_register = reader.read_u8()? & 1;
+        return Ok(());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,92 @@
+use crate::{machine::Mode, utils::{bit_masks::*, save_state::*}};
+use super::serial_device::SerialDevice;
+
+const TRANSFER_ENABLE_MASK:u8 = BIT_7_MASK;
+const CLOCK_SPEED_MASK:u8 = BIT_1_MASK;
+const INTERNAL_CLOCK_MASK:u8 = BIT_0_MASK;
+// Reading SB when nothing is connected returns 0xFF since the line is pulled up
+const DISCONNECTED_VALUE:u8 = 0xFF;
+
+// 8192Hz and 262144Hz (CGB fast clock) per bit in m_cycles, the serial clock is effected by the double speed mode
+const NORMAL_TRANSFER_M_CYCLES:u32 = 128 * 8;
+const FAST_TRANSFER_M_CYCLES:u32 = 4 * 8;
+
+pub struct GbSerial{
+    pub sb_register:u8,
+    sc_register:u8,
+    mode:Mode,
+    cycles_counter:u32,
+    device:Option<&'static mut dyn SerialDevice>
+}
+
+impl GbSerial{
+    pub fn new(mode:Mode)->Self{
+        Self { sb_register: 0, sc_register: 0, mode, cycles_counter: 0, device: None }
+    }
+
+    pub fn set_device(&mut self, device:&'static mut dyn SerialDevice){
+        self.device = Some(device);
+    }
+
+    pub fn get_sc_register(&self)->u8{
+        // Unused bits are read as 1s
+        return match self.mode{
+            Mode::DMG | Mode::SGB => self.sc_register | 0b0111_1110,
+            Mode::CGB => self.sc_register | 0b0111_1100
+        };
+    }
+
+    pub fn set_sc_register(&mut self, value:u8){
+        self.sc_register = match self.mode{
+            Mode::DMG | Mode::SGB => value & (TRANSFER_ENABLE_MASK | INTERNAL_CLOCK_MASK),
+            Mode::CGB => value & (TRANSFER_ENABLE_MASK | CLOCK_SPEED_MASK | INTERNAL_CLOCK_MASK)
+        };
+        self.cycles_counter = 0;
+    }
+
+    pub fn cycle(&mut self, m_cycles:u32, if_register:&mut u8){
+        if self.sc_register & TRANSFER_ENABLE_MASK == 0{
+            return;
+        }
+
+        if self.sc_register & INTERNAL_CLOCK_MASK != 0{
+            self.cycles_counter += m_cycles;
+            let transfer_cycles = if self.sc_register & CLOCK_SPEED_MASK == 0 {NORMAL_TRANSFER_M_CYCLES} else {FAST_TRANSFER_M_CYCLES};
+            if self.cycles_counter >= transfer_cycles{
+                let data = self.sb_register;
+                match self.device.as_mut().map_or(Some(DISCONNECTED_VALUE), |device|device.poll_transfer_internal(data)){
+                    Some(received) => self.finish_transfer(received, if_register),
+                    // Waiting for the device, the transfer stays pending and the interrupt is raised once it completes
+                    None => self.cycles_counter = transfer_cycles
+                }
+            }
+        }
+        else if let Some(device) = self.device.as_mut(){
+            if let Some(received) = device.transfer_external(self.sb_register){
+                self.finish_transfer(received, if_register);
+            }
+        }
+    }
+
+    fn finish_transfer(&mut self, received:u8, if_register:&mut u8){
+        self.sb_register = received;
+        self.sc_register &= !TRANSFER_ENABLE_MASK;
+        self.cycles_counter = 0;
+        *if_register |= BIT_3_MASK;
+    }
+}
+
+impl SaveState for GbSerial{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_u8(self.sb_register);
+        writer.write_u8(self.sc_register);
+        writer.write_u32(self.cycles_counter);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.sb_register = reader.read_u8()?;
+        self.sc_register = reader.read_u8()?;
+        self.cycles_counter = reader.read_u32()?;
+        return Ok(());
+    }
+}
@@ -0,0 +1,2 @@
+pub mod gb_serial;
+pub mod serial_device;
@@ -0,0 +1,14 @@
+/// The other end of the link cable, exchanges a byte at a time with the link partner
+pub trait SerialDevice{
+    /// Called when a transfer with the internal clock (this side drives the clock) completes,
+    /// sends `data` and returns the byte received from the partner
+    fn transfer_internal(&mut self, data:u8)->u8;
+    /// Polled while waiting for the partner to drive the clock (external clock), `data` is the byte this side will send.
+    /// Returns the received byte once the partner completed the transfer
+    fn transfer_external(&mut self, data:u8)->Option<u8>;
+    /// Polled once a transfer with the internal clock is due, the transfer stays pending until it returns the received byte.
+    /// Devices with latency (like a network link) should override it instead of blocking in `transfer_internal`
+    fn poll_transfer_internal(&mut self, data:u8)->Option<u8>{
+        return Some(self.transfer_internal(data));
+    }
+}
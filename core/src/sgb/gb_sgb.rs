@@ -0,0 +1,585 @@
+use crate::{ppu::{color::*, gb_ppu::{BUFFERS_NUMBER, SCREEN_HEIGHT, SCREEN_WIDTH}, gfx_device::*}, utils::{bit_masks::*, global_static_alloctor::static_alloc_array, save_state::*}};
+
+pub const SGB_SCREEN_WIDTH:usize = 256;
+pub const SGB_SCREEN_HEIGHT:usize = 224;
+// The game screen is centered inside the border
+const GAME_SCREEN_X:usize = (SGB_SCREEN_WIDTH - SCREEN_WIDTH) / 2;
+const GAME_SCREEN_Y:usize = (SGB_SCREEN_HEIGHT - SCREEN_HEIGHT) / 2;
+
+const PACKET_SIZE:usize = 16;
+const PACKET_BITS:u8 = (PACKET_SIZE * 8) as u8;
+const MAX_PACKETS:usize = 7;
+
+// The attributes map holds a palette for each 8x8 cell of the game screen
+const ATTRIBUTE_MAP_WIDTH:usize = SCREEN_WIDTH / 8;
+const ATTRIBUTE_MAP_HEIGHT:usize = SCREEN_HEIGHT / 8;
+const ATTRIBUTE_MAP_SIZE:usize = ATTRIBUTE_MAP_WIDTH * ATTRIBUTE_MAP_HEIGHT;
+// 2 bits per cell
+const ATTRIBUTE_FILE_SIZE:usize = ATTRIBUTE_MAP_SIZE / 4;
+const ATTRIBUTE_FILES_COUNT:usize = 45;
+
+const VRAM_TRANSFER_SIZE:usize = 0x1000;
+const SYSTEM_PALETTE_SIZE:usize = 8;
+const SYSTEM_PALETTES_COUNT:usize = VRAM_TRANSFER_SIZE / SYSTEM_PALETTE_SIZE;
+
+// SNES 4bpp tiles
+const BORDER_TILE_SIZE:usize = 32;
+const BORDER_TILES_SIZE:usize = VRAM_TRANSFER_SIZE * 2;
+const BORDER_MAP_WIDTH:usize = SGB_SCREEN_WIDTH / 8;
+const BORDER_MAP_HEIGHT:usize = SGB_SCREEN_HEIGHT / 8;
+const BORDER_PALETTES_OFFSET:usize = 0x800;
+// The border uses palettes 4-7, 16 colors each
+const BORDER_COLORS_COUNT:usize = 16 * 4;
+
+const DEFAULT_PALETTE:[u16;4] = [0x7FFF, 0x5294, 0x294A, 0x0000];
+
+// Commands
+const PAL01:u8 = 0x00;
+const PAL23:u8 = 0x01;
+const PAL03:u8 = 0x02;
+const PAL12:u8 = 0x03;
+const ATTR_BLK:u8 = 0x04;
+const ATTR_LIN:u8 = 0x05;
+const ATTR_DIV:u8 = 0x06;
+const ATTR_CHR:u8 = 0x07;
+const PAL_SET:u8 = 0x0A;
+const PAL_TRN:u8 = 0x0B;
+const MLT_REQ:u8 = 0x11;
+const CHR_TRN:u8 = 0x13;
+const PCT_TRN:u8 = 0x14;
+const ATTR_TRN:u8 = 0x15;
+const ATTR_SET:u8 = 0x16;
+const MASK_EN:u8 = 0x17;
+
+// Pulling P14 low sends a 0 bit, pulling P15 low sends a 1 bit and pulling both sends a reset pulse
+const JOYP_LINES_MASK:u8 = BIT_4_MASK | BIT_5_MASK;
+const RESET_PULSE:u8 = 0;
+const ZERO_BIT_PULSE:u8 = BIT_5_MASK;
+const ONE_BIT_PULSE:u8 = BIT_4_MASK;
+
+#[derive(Clone, Copy, PartialEq)]
+enum VramTransfer{
+    Palettes,
+    AttributeFiles,
+    BorderTiles(usize),
+    BorderMap
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ScreenMask{
+    Disabled,
+    Freeze,
+    Black,
+    Color0
+}
+
+/// The Super Game Boy side of the machine, receives the command packets sent through the JOYP register,
+/// colorizes the DMG frames and draws them inside the border
+pub struct GbSgb{
+    joyp_lines:u8,
+    receiving_packet:bool,
+    packet_bits:u8,
+    packet:[u8;PACKET_SIZE],
+    command:[u8;PACKET_SIZE * MAX_PACKETS],
+    command_packets:u8,
+    received_packets:u8,
+
+    palettes:[[u16;4];4],
+    attribute_map:[u8;ATTRIBUTE_MAP_SIZE],
+    system_palettes:[u8;VRAM_TRANSFER_SIZE],
+    attribute_files:[u8;ATTRIBUTE_FILES_COUNT * ATTRIBUTE_FILE_SIZE],
+    border_tiles:[u8;BORDER_TILES_SIZE],
+    border_map:[u8;BORDER_PALETTES_OFFSET],
+    border_palettes:[u16;BORDER_COLORS_COUNT],
+    // VRAM transfers are read from the next frame displayed
+    pending_transfer:Option<VramTransfer>,
+    mask:ScreenMask,
+    players_count:u8,
+    current_player:u8,
+
+    dmg_shades:[Pixel;4],
+    buffer_index:usize,
+    screen_buffers:&'static mut [Pixel],
+    border_buffers:&'static mut [Pixel],
+    // The border pixels are cached since they rarely change, None marks a transparent pixel
+    border_layer:&'static mut [Option<Pixel>],
+    border_dirty:bool
+}
+
+impl GbSgb{
+    pub fn new()->Self{
+        Self{
+            joyp_lines: JOYP_LINES_MASK,
+            receiving_packet: false,
+            packet_bits: 0,
+            packet: [0;PACKET_SIZE],
+            command: [0;PACKET_SIZE * MAX_PACKETS],
+            command_packets: 0,
+            received_packets: 0,
+            palettes: [DEFAULT_PALETTE;4],
+            attribute_map: [0;ATTRIBUTE_MAP_SIZE],
+            system_palettes: [0;VRAM_TRANSFER_SIZE],
+            attribute_files: [0;ATTRIBUTE_FILES_COUNT * ATTRIBUTE_FILE_SIZE],
+            border_tiles: [0;BORDER_TILES_SIZE],
+            border_map: [0;BORDER_PALETTES_OFFSET],
+            border_palettes: [0;BORDER_COLORS_COUNT],
+            pending_transfer: None,
+            mask: ScreenMask::Disabled,
+            players_count: 1,
+            current_player: 0,
+            dmg_shades: [WHITE, LIGHT_GRAY, DARK_GRAY, BLACK].map(Pixel::from),
+            buffer_index: 0,
+            screen_buffers: static_alloc_array(SCREEN_WIDTH * SCREEN_HEIGHT * BUFFERS_NUMBER),
+            border_buffers: static_alloc_array(SGB_SCREEN_WIDTH * SGB_SCREEN_HEIGHT * BUFFERS_NUMBER),
+            border_layer: static_alloc_array(SGB_SCREEN_WIDTH * SGB_SCREEN_HEIGHT),
+            border_dirty: true
+        }
+    }
+
+    pub fn write_joyp(&mut self, value:u8){
+        let lines = value & JOYP_LINES_MASK;
+        let last_lines = self.joyp_lines;
+        self.joyp_lines = lines;
+
+        match lines{
+            RESET_PULSE => {
+                self.receiving_packet = true;
+                self.packet_bits = 0;
+                self.packet = [0;PACKET_SIZE];
+            }
+            // A bit is sampled only after both lines were released
+            ZERO_BIT_PULSE | ONE_BIT_PULSE if self.receiving_packet && last_lines == JOYP_LINES_MASK => self.receive_bit(lines == ONE_BIT_PULSE),
+            // Releasing P15 selects the next controller
+            _ if !self.receiving_packet && lines & BIT_5_MASK != 0 && last_lines & BIT_5_MASK == 0 => {
+                self.current_player = (self.current_player + 1) % self.players_count;
+            }
+            _=>{}
+        }
+    }
+
+    pub fn read_joyp(&self, joyp_register:u8)->u8{
+        if self.players_count == 1{
+            return joyp_register;
+        }
+        // With both lines released the lower nibble holds the controller id
+        if joyp_register & JOYP_LINES_MASK == JOYP_LINES_MASK{
+            return (joyp_register & 0xF0) | (0xF - self.current_player);
+        }
+        // Only the first controller is connected
+        if self.current_player != 0{
+            return joyp_register | 0xF;
+        }
+        return joyp_register;
+    }
+
+    /// Colorizes a frame outputed by the PPU and passes it to the gfx device, with the border if the device supports it
+    pub fn present_frame<GFX:GfxDevice>(&mut self, frame:&[Pixel; SCREEN_HEIGHT * SCREEN_WIDTH], gfx_device:&mut GFX){
+        if let Some(transfer) = self.pending_transfer.take(){
+            self.vram_transfer(transfer, frame);
+        }
+
+        let border_supported = gfx_device.sgb_border_supported();
+        // A frozen screen keeps displaying the last frame
+        if self.mask != ScreenMask::Freeze{
+            self.buffer_index = (self.buffer_index + 1) % BUFFERS_NUMBER;
+            self.render_screen(frame);
+            if border_supported{
+                self.render_border_frame();
+            }
+        }
+
+        if border_supported{
+            let start = self.buffer_index * SGB_SCREEN_WIDTH * SGB_SCREEN_HEIGHT;
+            gfx_device.swap_sgb_buffer(self.border_buffers[start..start + SGB_SCREEN_WIDTH * SGB_SCREEN_HEIGHT].try_into().unwrap());
+        }
+        else{
+            let start = self.buffer_index * SCREEN_WIDTH * SCREEN_HEIGHT;
+            gfx_device.swap_buffer(self.screen_buffers[start..start + SCREEN_WIDTH * SCREEN_HEIGHT].try_into().unwrap());
+        }
+    }
+
+    fn receive_bit(&mut self, bit:bool){
+        if self.packet_bits == PACKET_BITS{
+            self.receiving_packet = false;
+            // The stop bit must be 0
+            if !bit{
+                self.receive_packet();
+            }
+            return;
+        }
+
+        if bit{
+            self.packet[(self.packet_bits / 8) as usize] |= 1 << (self.packet_bits % 8);
+        }
+        self.packet_bits += 1;
+    }
+
+    fn receive_packet(&mut self){
+        if self.received_packets == 0{
+            self.command_packets = self.packet[0] & 0b111;
+            if self.command_packets == 0{
+                return;
+            }
+        }
+        let offset = self.received_packets as usize * PACKET_SIZE;
+        self.command[offset..offset + PACKET_SIZE].copy_from_slice(&self.packet);
+        self.received_packets += 1;
+        if self.received_packets == self.command_packets{
+            self.received_packets = 0;
+            self.execute_command();
+        }
+    }
+
+    fn execute_command(&mut self){
+        let data = self.command;
+        match data[0] >> 3{
+            PAL01 => self.set_palettes_pair(&data, 0, 1),
+            PAL23 => self.set_palettes_pair(&data, 2, 3),
+            PAL03 => self.set_palettes_pair(&data, 0, 3),
+            PAL12 => self.set_palettes_pair(&data, 1, 2),
+            ATTR_BLK => self.attribute_blocks(&data),
+            ATTR_LIN => self.attribute_lines(&data),
+            ATTR_DIV => self.attribute_divide(&data),
+            ATTR_CHR => self.attribute_characters(&data),
+            PAL_SET => {
+                for i in 0..4{
+                    self.palettes[i] = self.get_system_palette(read_u16(&data, 1 + (i * 2)));
+                }
+                // Color 0 is shared by all the palettes
+                for i in 1..4{
+                    self.palettes[i][0] = self.palettes[0][0];
+                }
+                self.apply_attribute_file_flags(data[9]);
+            }
+            PAL_TRN => self.pending_transfer = Some(VramTransfer::Palettes),
+            MLT_REQ => {
+                self.players_count = match data[1] & 0b11{
+                    1 => 2,
+                    3 => 4,
+                    _ => 1
+                };
+                self.current_player = 0;
+            }
+            CHR_TRN => self.pending_transfer = Some(VramTransfer::BorderTiles((data[1] & 1) as usize)),
+            PCT_TRN => self.pending_transfer = Some(VramTransfer::BorderMap),
+            ATTR_TRN => self.pending_transfer = Some(VramTransfer::AttributeFiles),
+            ATTR_SET => self.apply_attribute_file_flags(data[1] | BIT_7_MASK),
+            MASK_EN => self.mask = match data[1] & 0b11{
+                0 => ScreenMask::Disabled,
+                1 => ScreenMask::Freeze,
+                2 => ScreenMask::Black,
+                _ => ScreenMask::Color0
+            },
+            command => log::debug!("Ignoring unsupported SGB command: {:#X}", command)
+        }
+    }
+
+    fn set_palettes_pair(&mut self, data:&[u8], first:usize, second:usize){
+        let color0 = read_u16(data, 1);
+        for palette in &mut self.palettes{
+            palette[0] = color0;
+        }
+        for i in 1..4{
+            self.palettes[first][i] = read_u16(data, 1 + (i * 2));
+            self.palettes[second][i] = read_u16(data, 7 + (i * 2));
+        }
+    }
+
+    fn attribute_blocks(&mut self, data:&[u8]){
+        let blocks_count = (data[1] as usize).min(18);
+        for block in data[2..2 + (blocks_count * 6)].chunks_exact(6){
+            let control = block[0] & 0b111;
+            let inside_palette = block[1] & 0b11;
+            let line_palette = (block[1] >> 2) & 0b11;
+            let outside_palette = (block[1] >> 4) & 0b11;
+            let (x1, y1, x2, y2) = (block[2] & 0x1F, block[3] & 0x1F, block[4] & 0x1F, block[5] & 0x1F);
+            // When only the inside or the outside is changed the line is colored like it
+            let line_palette = match control{
+                0b001 => Some(inside_palette),
+                0b100 => Some(outside_palette),
+                _ if control & 0b010 != 0 => Some(line_palette),
+                _ => None
+            };
+            for y in 0..ATTRIBUTE_MAP_HEIGHT as u8{
+                for x in 0..ATTRIBUTE_MAP_WIDTH as u8{
+                    let palette = if x > x1 && x < x2 && y > y1 && y < y2 {
+                        if control & 0b001 != 0 {Some(inside_palette)} else {None}
+                    }
+                    else if x >= x1 && x <= x2 && y >= y1 && y <= y2 {
+                        line_palette
+                    }
+                    else if control & 0b100 != 0 {Some(outside_palette)} else {None};
+
+                    if let Some(palette) = palette{
+                        self.attribute_map[y as usize * ATTRIBUTE_MAP_WIDTH + x as usize] = palette;
+                    }
+                }
+            }
+        }
+    }
+
+    fn attribute_lines(&mut self, data:&[u8]){
+        let lines_count = (data[1] as usize).min(data.len() - 2);
+        for line in &data[2..2 + lines_count]{
+            let index = (line & 0x1F) as usize;
+            let palette = (line >> 5) & 0b11;
+            if line & BIT_7_MASK != 0{
+                if index < ATTRIBUTE_MAP_HEIGHT{
+                    self.attribute_map[index * ATTRIBUTE_MAP_WIDTH..(index + 1) * ATTRIBUTE_MAP_WIDTH].fill(palette);
+                }
+            }
+            else if index < ATTRIBUTE_MAP_WIDTH{
+                for y in 0..ATTRIBUTE_MAP_HEIGHT{
+                    self.attribute_map[y * ATTRIBUTE_MAP_WIDTH + index] = palette;
+                }
+            }
+        }
+    }
+
+    fn attribute_divide(&mut self, data:&[u8]){
+        let after_palette = data[1] & 0b11;
+        let before_palette = (data[1] >> 2) & 0b11;
+        let line_palette = (data[1] >> 4) & 0b11;
+        let horizontal_line = data[1] & BIT_6_MASK != 0;
+        let line = (data[2] & 0x1F) as usize;
+        for y in 0..ATTRIBUTE_MAP_HEIGHT{
+            for x in 0..ATTRIBUTE_MAP_WIDTH{
+                let position = if horizontal_line {y} else {x};
+                self.attribute_map[y * ATTRIBUTE_MAP_WIDTH + x] = match position.cmp(&line){
+                    core::cmp::Ordering::Less => before_palette,
+                    core::cmp::Ordering::Equal => line_palette,
+                    core::cmp::Ordering::Greater => after_palette
+                };
+            }
+        }
+    }
+
+    fn attribute_characters(&mut self, data:&[u8]){
+        let (mut x, mut y) = ((data[1] as usize).min(ATTRIBUTE_MAP_WIDTH - 1), (data[2] as usize).min(ATTRIBUTE_MAP_HEIGHT - 1));
+        let cells_count = (read_u16(data, 3) as usize).min(ATTRIBUTE_MAP_SIZE).min((data.len() - 6) * 4);
+        let vertical = data[5] & 1 != 0;
+        for i in 0..cells_count{
+            self.attribute_map[y * ATTRIBUTE_MAP_WIDTH + x] = read_packed_palette(&data[6..], i);
+            if vertical{
+                y += 1;
+                if y == ATTRIBUTE_MAP_HEIGHT{
+                    y = 0;
+                    x = (x + 1) % ATTRIBUTE_MAP_WIDTH;
+                }
+            }
+            else{
+                x += 1;
+                if x == ATTRIBUTE_MAP_WIDTH{
+                    x = 0;
+                    y = (y + 1) % ATTRIBUTE_MAP_HEIGHT;
+                }
+            }
+        }
+    }
+
+    // Bit 7 applies the attribute file in bits 0-5 and bit 6 cancels the screen mask
+    fn apply_attribute_file_flags(&mut self, flags:u8){
+        if flags & BIT_7_MASK != 0{
+            let file_index = (flags & 0x3F) as usize;
+            if file_index < ATTRIBUTE_FILES_COUNT{
+                let file = &self.attribute_files[file_index * ATTRIBUTE_FILE_SIZE..(file_index + 1) * ATTRIBUTE_FILE_SIZE];
+                for i in 0..ATTRIBUTE_MAP_SIZE{
+                    self.attribute_map[i] = read_packed_palette(file, i);
+                }
+            }
+        }
+        if flags & BIT_6_MASK != 0{
+            self.mask = ScreenMask::Disabled;
+        }
+    }
+
+    fn get_system_palette(&self, index:u16)->[u16;4]{
+        let offset = (index as usize % SYSTEM_PALETTES_COUNT) * SYSTEM_PALETTE_SIZE;
+        return core::array::from_fn(|i|read_u16(&self.system_palettes[offset..], i * 2));
+    }
+
+    // The data is read from the displayed tiles as if they were the VRAM, tile n is at cell n of the screen
+    fn vram_transfer(&mut self, transfer:VramTransfer, frame:&[Pixel; SCREEN_HEIGHT * SCREEN_WIDTH]){
+        let mut data = [0;VRAM_TRANSFER_SIZE];
+        for (tile_index, tile) in data.chunks_exact_mut(16).enumerate(){
+            let (tile_x, tile_y) = (tile_index % ATTRIBUTE_MAP_WIDTH, tile_index / ATTRIBUTE_MAP_WIDTH);
+            for row in 0..8{
+                let line_start = (((tile_y * 8) + row) * SCREEN_WIDTH) + (tile_x * 8);
+                for (x, pixel) in frame[line_start..line_start + 8].iter().enumerate(){
+                    let shade = get_shade(&self.dmg_shades, *pixel);
+                    tile[row * 2] |= (shade & 1) << (7 - x);
+                    tile[(row * 2) + 1] |= (shade >> 1) << (7 - x);
+                }
+            }
+        }
+
+        match transfer{
+            VramTransfer::Palettes => self.system_palettes.copy_from_slice(&data),
+            VramTransfer::AttributeFiles => {
+                let size = self.attribute_files.len();
+                self.attribute_files.copy_from_slice(&data[..size]);
+            }
+            VramTransfer::BorderTiles(bank) => {
+                self.border_tiles[bank * VRAM_TRANSFER_SIZE..(bank + 1) * VRAM_TRANSFER_SIZE].copy_from_slice(&data);
+                self.border_dirty = true;
+            }
+            VramTransfer::BorderMap => {
+                self.border_map.copy_from_slice(&data[..BORDER_PALETTES_OFFSET]);
+                for (i, color) in self.border_palettes.iter_mut().enumerate(){
+                    *color = read_u16(&data[BORDER_PALETTES_OFFSET..], i * 2);
+                }
+                self.border_dirty = true;
+            }
+        }
+    }
+
+    fn render_screen(&mut self, frame:&[Pixel; SCREEN_HEIGHT * SCREEN_WIDTH]){
+        let colors = self.palettes.map(|palette|palette.map(|color|Pixel::from(Color::from(color))));
+        let start = self.buffer_index * SCREEN_WIDTH * SCREEN_HEIGHT;
+        let screen = &mut self.screen_buffers[start..start + SCREEN_WIDTH * SCREEN_HEIGHT];
+        match self.mask{
+            ScreenMask::Black => screen.fill(Pixel::from(BLACK)),
+            ScreenMask::Color0 => screen.fill(colors[0][0]),
+            _ => for (i, pixel) in frame.iter().enumerate(){
+                let cell = ((i / SCREEN_WIDTH / 8) * ATTRIBUTE_MAP_WIDTH) + ((i % SCREEN_WIDTH) / 8);
+                screen[i] = colors[self.attribute_map[cell] as usize][get_shade(&self.dmg_shades, *pixel) as usize];
+            }
+        }
+    }
+
+    fn render_border_frame(&mut self){
+        if self.border_dirty{
+            self.render_border_layer();
+            self.border_dirty = false;
+        }
+
+        let backdrop = Pixel::from(Color::from(self.palettes[0][0]));
+        let screen_start = self.buffer_index * SCREEN_WIDTH * SCREEN_HEIGHT;
+        let screen = &self.screen_buffers[screen_start..screen_start + SCREEN_WIDTH * SCREEN_HEIGHT];
+        let frame_start = self.buffer_index * SGB_SCREEN_WIDTH * SGB_SCREEN_HEIGHT;
+        let frame = &mut self.border_buffers[frame_start..frame_start + SGB_SCREEN_WIDTH * SGB_SCREEN_HEIGHT];
+        for (i, pixel) in frame.iter_mut().enumerate(){
+            let (x, y) = (i % SGB_SCREEN_WIDTH, i / SGB_SCREEN_WIDTH);
+            let inside_game_screen = x >= GAME_SCREEN_X && x < GAME_SCREEN_X + SCREEN_WIDTH && y >= GAME_SCREEN_Y && y < GAME_SCREEN_Y + SCREEN_HEIGHT;
+            *pixel = if inside_game_screen {
+                screen[((y - GAME_SCREEN_Y) * SCREEN_WIDTH) + x - GAME_SCREEN_X]
+            }
+            else{
+                self.border_layer[i].unwrap_or(backdrop)
+            };
+        }
+    }
+
+    fn render_border_layer(&mut self){
+        for tile_y in 0..BORDER_MAP_HEIGHT{
+            for tile_x in 0..BORDER_MAP_WIDTH{
+                // bits 0-7 tile index, 10-12 palette, 14 x flip and 15 y flip
+                let entry = read_u16(&self.border_map, ((tile_y * BORDER_MAP_WIDTH) + tile_x) * 2);
+                let tile_start = (entry & 0xFF) as usize * BORDER_TILE_SIZE;
+                let tile = &self.border_tiles[tile_start..tile_start + BORDER_TILE_SIZE];
+                let palette = ((entry >> 10) & 0b11) as usize;
+                let x_flip = entry & (1 << 14) != 0;
+                let y_flip = entry & (1 << 15) != 0;
+                for y in 0..8{
+                    let row = if y_flip {7 - y} else {y};
+                    let planes = [tile[row * 2], tile[(row * 2) + 1], tile[16 + (row * 2)], tile[16 + (row * 2) + 1]];
+                    for x in 0..8{
+                        let bit = if x_flip {x} else {7 - x};
+                        let color_index = planes.iter().enumerate().fold(0, |index, (i, plane)| index | (((plane >> bit) & 1) << i)) as usize;
+                        // Color 0 is transparent
+                        let pixel = if color_index == 0 {None} else {Some(Pixel::from(Color::from(self.border_palettes[(palette * 16) + color_index])))};
+                        self.border_layer[(((tile_y * 8) + y) * SGB_SCREEN_WIDTH) + (tile_x * 8) + x] = pixel;
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn read_u16(data:&[u8], index:usize)->u16{
+    u16::from_le_bytes([data[index], data[index + 1]])
+}
+
+// 4 palettes indices per byte starting from the high bits
+fn read_packed_palette(data:&[u8], index:usize)->u8{
+    (data[index / 4] >> (6 - ((index % 4) * 2))) & 0b11
+}
+
+// Maps a DMG frame pixel back to its shade
+fn get_shade(shades:&[Pixel;4], pixel:Pixel)->u8{
+    shades.iter().position(|shade|*shade == pixel).unwrap_or(0) as u8
+}
+
+impl SaveState for GbSgb{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_u8(self.joyp_lines);
+        writer.write_bool(self.receiving_packet);
+        writer.write_u8(self.packet_bits);
+        writer.write_bytes(&self.packet);
+        writer.write_bytes(&self.command);
+        writer.write_u8(self.command_packets);
+        writer.write_u8(self.received_packets);
+        for palette in &self.palettes{
+            writer.write_u16_slice(palette);
+        }
+        writer.write_bytes(&self.attribute_map);
+        writer.write_bytes(&self.system_palettes);
+        writer.write_bytes(&self.attribute_files);
+        writer.write_bytes(&self.border_tiles);
+        writer.write_bytes(&self.border_map);
+        writer.write_u16_slice(&self.border_palettes);
+        writer.write_u8(match self.pending_transfer{
+            None => 0,
+            Some(VramTransfer::Palettes) => 1,
+            Some(VramTransfer::AttributeFiles) => 2,
+            Some(VramTransfer::BorderTiles(bank)) => 3 + bank as u8,
+            Some(VramTransfer::BorderMap) => 5
+        });
+        writer.write_u8(self.mask as u8);
+        writer.write_u8(self.players_count);
+        writer.write_u8(self.current_player);
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        self.joyp_lines = reader.read_u8()?;
+        self.receiving_packet = reader.read_bool()?;
+        self.packet_bits = reader.read_u8()?;
+        reader.read_bytes(&mut self.packet)?;
+        reader.read_bytes(&mut self.command)?;
+        self.command_packets = reader.read_u8()?;
+        self.received_packets = reader.read_u8()?;
+        for palette in &mut self.palettes{
+            reader.read_u16_slice(palette)?;
+        }
+        reader.read_bytes(&mut self.attribute_map)?;
+        reader.read_bytes(&mut self.system_palettes)?;
+        reader.read_bytes(&mut self.attribute_files)?;
+        reader.read_bytes(&mut self.border_tiles)?;
+        reader.read_bytes(&mut self.border_map)?;
+        reader.read_u16_slice(&mut self.border_palettes)?;
+        self.pending_transfer = match reader.read_u8()?{
+            0 => None,
+            1 => Some(VramTransfer::Palettes),
+            2 => Some(VramTransfer::AttributeFiles),
+            3 => Some(VramTransfer::BorderTiles(0)),
+            4 => Some(VramTransfer::BorderTiles(1)),
+            5 => Some(VramTransfer::BorderMap),
+            _ => return Err(SaveStateError::Corrupted)
+        };
+        self.mask = match reader.read_u8()?{
+            0 => ScreenMask::Disabled,
+            1 => ScreenMask::Freeze,
+            2 => ScreenMask::Black,
+            3 => ScreenMask::Color0,
+            _ => return Err(SaveStateError::Corrupted)
+        };
+        self.players_count = reader.read_u8()?;
+        self.current_player = reader.read_u8()?;
+        if !matches!(self.players_count, 1 | 2 | 4) || self.current_player >= self.players_count || self.packet_bits > PACKET_BITS
+            || self.received_packets as usize >= MAX_PACKETS {
+            return Err(SaveStateError::Corrupted);
+        }
+        self.border_dirty = true;
+        return Ok(());
+    }
+}
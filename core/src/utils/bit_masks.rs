@@ -7,6 +7,7 @@ pub const BIT_5_MASK:u8 = 1 << 5;
 pub const BIT_6_MASK:u8 = 1 << 6;
 pub const BIT_7_MASK:u8 = 1 << 7;
 
+pub const BIT_8_MASK:u16 = 1 << 8;
 pub const BIT_9_MASK:u16 = 1 << 9;
 
 #[inline]
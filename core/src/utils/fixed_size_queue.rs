@@ -1,6 +1,6 @@
 use core::{ops::{IndexMut, Index}, ptr};
 
-use super::global_static_alloctor::static_alloc_ptr;
+use super::{global_static_alloctor::static_alloc_ptr, save_state::*};
 
 pub struct FixedSizeQueue<T, const SIZE:usize>{
     end_alloc_pointer: *mut T,
@@ -116,6 +116,32 @@ impl<T:Copy, const SIZE:usize> IndexMut<usize> for FixedSizeQueue<T, SIZE>{
     }
 }
 
+// The whole capacity is saved (padded with defaults) so the state size does not depend on the queue length
+impl<T:Copy + Default + SaveState, const SIZE:usize> SaveState for FixedSizeQueue<T, SIZE>{
+    fn save_state(&self, writer:&mut StateWriter) {
+        writer.write_u8(self.length as u8);
+        for i in 0..SIZE{
+            if i < self.length {self[i].save_state(writer)} else {T::default().save_state(writer)}
+        }
+    }
+
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError> {
+        let length = reader.read_u8()? as usize;
+        if length > SIZE{
+            return Err(SaveStateError::Corrupted);
+        }
+        self.clear();
+        for i in 0..SIZE{
+            let mut t = T::default();
+            t.load_state(reader)?;
+            if i < length{
+                self.push(t);
+            }
+        }
+        return Ok(());
+    }
+}
+
 #[cfg(test)]
 mod tests{
     use super::*;
@@ -1,4 +1,6 @@
 pub const JOYP_REGISTER_ADDRESS:u16 = 0xFF00;
+pub const SB_REGISTER_ADDRESS:u16   = 0xFF01;
+pub const SC_REGISTER_ADDRESS:u16   = 0xFF02;
 pub const DIV_REGISTER_ADDRESS:u16  = 0xFF04;
 pub const TIMA_REGISTER_ADDRESS:u16 = 0xFF05;
 pub const TMA_REGISTER_ADDRESS:u16  = 0xFF06;
@@ -46,6 +48,7 @@ pub const HDMA2_REGISTER_ADDRESS:u16= 0xFF52;
 pub const HDMA3_REGISTER_ADDRESS:u16= 0xFF53;
 pub const HDMA4_REGISTER_ADDRESS:u16= 0xFF54;
 pub const HDMA5_REGISTER_ADDRESS:u16= 0xFF55;
+pub const RP_REGISTER_ADDRESS:u16   = 0xFF56;
 pub const BGPI_REGISTER_ADDRESS:u16 = 0xFF68;
 pub const BGPD_REGISTER_ADDRESS:u16 = 0xFF69;
 pub const OBPI_REGISTER_ADDRESS:u16 = 0xFF6A;
@@ -6,6 +6,7 @@ pub mod bit_masks;
 pub mod fixed_size_queue;
 pub mod static_allocator;
 pub mod global_static_alloctor;
+pub mod save_state;
 
 // Frequency in m_cycles (m_cycle = 4 t_cycles)
 pub const GB_FREQUENCY:u32 = 4_194_304 / 4;
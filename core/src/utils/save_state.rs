@@ -0,0 +1,161 @@
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SaveStateError{
+    BufferTooSmall,
+    InvalidHeader,
+    UnsupportedVersion(u16),
+    /// The state was created by a machine with a different configuration (mode, cartridge or bootrom)
+    MachineMismatch,
+    Corrupted
+}
+
+pub trait SaveState{
+    fn save_state(&self, writer:&mut StateWriter);
+    fn load_state(&mut self, reader:&mut StateReader)->Result<(), SaveStateError>;
+}
+
+/// Values are written in little endian and packed with no alignment so the blob is identical across platforms.
+/// Writing past the end of the buffer wont fail but will only advance the position,
+/// this allows calculating the state size by writing to an empty buffer.
+pub struct StateWriter<'a>{
+    buffer:&'a mut [u8],
+    position:usize
+}
+
+impl<'a> StateWriter<'a>{
+    pub fn new(buffer:&'a mut [u8])->Self{
+        Self{buffer, position:0}
+    }
+
+    pub fn position(&self)->usize{self.position}
+
+    pub fn overflowed(&self)->bool{self.position > self.buffer.len()}
+
+    pub fn write_bytes(&mut self, data:&[u8]){
+        let end = self.position + data.len();
+        if end <= self.buffer.len(){
+            self.buffer[self.position..end].copy_from_slice(data);
+        }
+        self.position = end;
+    }
+
+    pub fn write_u8(&mut self, value:u8){self.write_bytes(&[value])}
+    pub fn write_bool(&mut self, value:bool){self.write_u8(value as u8)}
+    pub fn write_u16(&mut self, value:u16){self.write_bytes(&value.to_le_bytes())}
+    pub fn write_i16(&mut self, value:i16){self.write_bytes(&value.to_le_bytes())}
+    pub fn write_u32(&mut self, value:u32){self.write_bytes(&value.to_le_bytes())}
+    pub fn write_u64(&mut self, value:u64){self.write_bytes(&value.to_le_bytes())}
+
+    pub fn write_u16_slice(&mut self, values:&[u16]){
+        for value in values{
+            self.write_u16(*value);
+        }
+    }
+}
+
+pub struct StateReader<'a>{
+    buffer:&'a [u8],
+    position:usize
+}
+
+impl<'a> StateReader<'a>{
+    pub fn new(buffer:&'a [u8])->Self{
+        Self{buffer, position:0}
+    }
+
+    pub fn position(&self)->usize{self.position}
+
+    pub fn read_bytes(&mut self, data:&mut [u8])->Result<(), SaveStateError>{
+        let end = self.position + data.len();
+        if end > self.buffer.len(){
+            return Err(SaveStateError::BufferTooSmall);
+        }
+        data.copy_from_slice(&self.buffer[self.position..end]);
+        self.position = end;
+        return Ok(());
+    }
+
+    pub fn read_u8(&mut self)->Result<u8, SaveStateError>{
+        let mut value = [0;1];
+        self.read_bytes(&mut value)?;
+        return Ok(value[0]);
+    }
+
+    pub fn read_bool(&mut self)->Result<bool, SaveStateError>{
+        return match self.read_u8()?{
+            0=>Ok(false),
+            1=>Ok(true),
+            _=>Err(SaveStateError::Corrupted)
+        };
+    }
+
+    pub fn read_u16(&mut self)->Result<u16, SaveStateError>{
+        let mut value = [0;2];
+        self.read_bytes(&mut value)?;
+        return Ok(u16::from_le_bytes(value));
+    }
+
+    pub fn read_i16(&mut self)->Result<i16, SaveStateError>{
+        let mut value = [0;2];
+        self.read_bytes(&mut value)?;
+        return Ok(i16::from_le_bytes(value));
+    }
+
+    pub fn read_u32(&mut self)->Result<u32, SaveStateError>{
+        let mut value = [0;4];
+        self.read_bytes(&mut value)?;
+        return Ok(u32::from_le_bytes(value));
+    }
+
+    pub fn read_u64(&mut self)->Result<u64, SaveStateError>{
+        let mut value = [0;8];
+        self.read_bytes(&mut value)?;
+        return Ok(u64::from_le_bytes(value));
+    }
+
+    pub fn read_u16_slice(&mut self, values:&mut [u16])->Result<(), SaveStateError>{
+        for value in values{
+            *value = self.read_u16()?;
+        }
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn writer_counts_size_past_the_buffer(){
+        let mut writer = StateWriter::new(&mut []);
+        writer.write_u32(1);
+        writer.write_bool(true);
+        assert_eq!(writer.position(), 5);
+        assert!(writer.overflowed());
+    }
+
+    #[test]
+    fn read_what_was_written(){
+        let mut buffer = [0;15];
+        let mut writer = StateWriter::new(&mut buffer);
+        writer.write_u8(0x12);
+        writer.write_bool(true);
+        writer.write_u16(0x3456);
+        writer.write_i16(-2);
+        writer.write_u32(0x789A_BCDE);
+        writer.write_u16_slice(&[1, 2]);
+        writer.write_u8(0xFF);
+        assert!(!writer.overflowed());
+
+        let mut reader = StateReader::new(&buffer);
+        assert_eq!(reader.read_u8(), Ok(0x12));
+        assert_eq!(reader.read_bool(), Ok(true));
+        assert_eq!(reader.read_u16(), Ok(0x3456));
+        assert_eq!(reader.read_i16(), Ok(-2));
+        assert_eq!(reader.read_u32(), Ok(0x789A_BCDE));
+        let mut values = [0;2];
+        assert_eq!(reader.read_u16_slice(&mut values), Ok(()));
+        assert_eq!(values, [1, 2]);
+        assert_eq!(reader.read_bool(), Err(SaveStateError::Corrupted));
+        assert_eq!(reader.read_u8(), Err(SaveStateError::BufferTooSmall));
+    }
+}
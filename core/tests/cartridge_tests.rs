@@ -0,0 +1,113 @@
+mod rom_fixture;
+
+use magenboy_core::{machine::mbc_initializer::initialize_mbc, mmu::carts::*};
+use rom_fixture::{create_rom, create_mbc};
+
+#[test]
+fn valid_rom_is_loaded(){
+    let rom = create_rom(0x8000, 0x3, 0x2);
+    let mbc = initialize_mbc(&rom, Some(&[0xAB;0x2000])).unwrap();
+    assert_eq!(mbc.get_ram()[0], 0xAB);
+}
+
+#[test]
+fn unsupported_mapper_is_an_error(){
+    let rom = create_rom(0x8000, 0x20, 0);
+    assert_eq!(initialize_mbc(&rom, None).err(), Some(CartridgeError::UnsupportedMapper(0x20)));
+}
+
+#[test]
+fn truncated_rom_is_an_error(){
+    assert_eq!(initialize_mbc(&[0;0x100], None).err(), Some(CartridgeError::TruncatedRom{expected: HEADER_SIZE, actual: 0x100}));
+
+    let mut rom = create_rom(0x8000, 0x1, 0);
+    // 64KB rom
+    rom[ROM_SIZE_ADDRESS] = 1;
+    rom[HEADER_CHECKSUM_ADDRESS] = calculate_header_checksum(&rom);
+    assert_eq!(initialize_mbc(&rom, None).err(), Some(CartridgeError::TruncatedRom{expected: 0x1_0000, actual: 0x8000}));
+}
+
+#[test]
+fn header_checksum_mismatch_is_an_error(){
+    let mut rom = create_rom(0x8000, 0, 0);
+    let checksum = rom[HEADER_CHECKSUM_ADDRESS];
+    rom[HEADER_CHECKSUM_ADDRESS] = checksum.wrapping_add(1);
+    assert_eq!(initialize_mbc(&rom, None).err(), Some(CartridgeError::HeaderChecksumMismatch{expected: checksum, actual: checksum.wrapping_add(1)}));
+}
+
+#[test]
+fn invalid_ram_size_is_an_error(){
+    let rom = create_rom(0x8000, 0x3, 0x9);
+    assert_eq!(initialize_mbc(&rom, None).err(), Some(CartridgeError::InvalidRamSize(0x9)));
+}
+
+#[test]
+fn save_size_mismatch_is_an_error(){
+    let rom = create_rom(0x8000, 0x3, 0x2);
+    assert_eq!(initialize_mbc(&rom, Some(&[0;0x100])).err(), Some(CartridgeError::SaveSizeMismatch{expected: 0x2000, actual: 0x100}));
+
+    // The MBC2 ram is built in and not declared in the header
+    let rom = create_rom(0x8000, 0x6, 0);
+    assert_eq!(initialize_mbc(&rom, Some(&[0;0x2000])).err(), Some(CartridgeError::SaveSizeMismatch{expected: 0x200, actual: 0x2000}));
+}
+
+#[test]
+fn header_is_parsed(){
+    let mut rom = vec![0;0x8000];
+    rom[0x134..0x13F].copy_from_slice(b"POKEMON RED");
+    rom[0x13F..0x143].copy_from_slice(b"APSE");
+    rom[CGB_FLAG_ADDRESS] = 0x80;
+    rom[SGB_FLAG_ADDRESS] = 0x3;
+    rom[OLD_LICENSEE_CODE_ADDRESS] = 0x33;
+    rom[0x144..0x146].copy_from_slice(b"01");
+    rom[0x147] = 0x13;
+    rom[ROM_SIZE_ADDRESS] = 0;
+    rom[MBC_RAM_SIZE_LOCATION] = 0x3;
+    rom[0x14C] = 1;
+    rom[HEADER_CHECKSUM_ADDRESS] = calculate_header_checksum(&rom);
+    let sum = rom.iter().fold(0u16, |sum, value| sum.wrapping_add(*value as u16));
+    rom[0x14E..0x150].copy_from_slice(&sum.to_be_bytes());
+
+    let header = CartridgeHeader::parse(&rom).unwrap();
+    assert_eq!(header.title(), "POKEMON RED");
+    assert_eq!(header.manufacturer_code, Some(*b"APSE"));
+    assert!(header.cgb_supported() && !header.cgb_only() && header.sgb_supported());
+    assert_eq!(header.new_licensee_code, *b"01");
+    assert_eq!(header.cartridge_type, 0x13);
+    assert_eq!(header.rom_size, Some(0x8000));
+    assert_eq!(header.ram_size, Some(0x8000));
+    assert_eq!(header.version, 1);
+    assert!(header.header_checksum_valid && !header.logo_valid);
+    assert!(header.global_checksum_valid(&rom));
+
+    let mbc = initialize_mbc(&rom, None).unwrap();
+    assert_eq!(mbc.read_header().title(), "POKEMON RED");
+}
+
+#[test]
+fn sgb_carts_default_to_dmg(){
+    let mut rom = vec![0;0x8000];
+    rom[SGB_FLAG_ADDRESS] = 0x3;
+    rom[OLD_LICENSEE_CODE_ADDRESS] = 0x33;
+    rom[HEADER_CHECKSUM_ADDRESS] = calculate_header_checksum(&rom);
+    let mbc = initialize_mbc(&rom, None).unwrap();
+    assert!(mbc.read_header().sgb_supported());
+    assert!(mbc.detect_preferred_mode() == magenboy_core::machine::Mode::DMG);
+}
+
+#[test]
+fn old_header_title_uses_the_whole_area(){
+    let mut rom = create_rom(0x8000, 0, 0);
+    rom[0x134..0x144].copy_from_slice(b"SIXTEEN CHARS 16");
+    let header = CartridgeHeader::parse(&rom).unwrap();
+    assert_eq!(header.title(), "SIXTEEN CHARS 16");
+    assert_eq!(header.manufacturer_code, None);
+    assert!(!header.header_checksum_valid);
+}
+
+#[test]
+fn rom_without_ram_reads_open_bus(){
+    let mbc = create_mbc(0x8000, 0, 0);
+    mbc.write_external_ram(0, 0x12);
+    assert_eq!(mbc.read_external_ram(0), 0xFF);
+}
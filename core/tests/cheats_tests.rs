@@ -0,0 +1,29 @@
+use magenboy_core::cheats::{cheat::*, cheat_engine::CheatEngine};
+
+#[test]
+fn parse_game_shark_code(){
+    assert_eq!(Cheat::try_from("010238CD"), Ok(Cheat::GameShark(GameSharkCode{bank:0x01, value:0x02, address:0xCD38})));
+    // ROM writes would switch banks
+    assert_eq!(Cheat::try_from("01023812"), Err(()));
+    assert_eq!(Cheat::try_from("0102GG12"), Err(()));
+}
+
+#[test]
+fn parse_game_genie_code(){
+    assert_eq!(Cheat::try_from("3EA-17B-F4E"), Ok(Cheat::GameGenie(GameGenieCode{address:0x4A17, value:0x3E, compare:Some(0x05)})));
+    assert_eq!(Cheat::try_from("3EA-17B"), Ok(Cheat::GameGenie(GameGenieCode{address:0x4A17, value:0x3E, compare:None})));
+    // Addresses outside of the ROM
+    assert_eq!(Cheat::try_from("3EA-177"), Err(()));
+}
+
+#[test]
+fn game_genie_patch_applies_only_on_matching_compare_value(){
+    let mut engine = CheatEngine::new();
+    assert!(engine.add(Cheat::try_from("3EA-17B-F4E").unwrap()));
+    assert_eq!(engine.patch_rom_read(0x4A17, 0x05), 0x3E);
+    assert_eq!(engine.patch_rom_read(0x4A17, 0x06), 0x06);
+    assert_eq!(engine.patch_rom_read(0x4A18, 0x05), 0x05);
+
+    engine.clear();
+    assert_eq!(engine.patch_rom_read(0x4A17, 0x05), 0x05);
+}
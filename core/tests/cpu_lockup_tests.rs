@@ -0,0 +1,35 @@
+mod full_memory_stub;
+
+use full_memory_stub::FullMemoryStub;
+use magenboy_core::cpu::gb_cpu::GbCpu;
+
+const ILLEGAL_OPCODES:[u8;11] = [0xD3, 0xDB, 0xDD, 0xE3, 0xE4, 0xEB, 0xEC, 0xED, 0xF4, 0xFC, 0xFD];
+
+#[test]
+fn illegal_opcodes_lock_the_cpu(){
+    for opcode in ILLEGAL_OPCODES{
+        let mut cpu = GbCpu::default();
+        let mut memory = FullMemoryStub::new(&[]);
+        cpu.program_counter = 0x100;
+        memory.data[0x100] = opcode;
+
+        cpu.run_opcode(&mut memory);
+
+        assert!(cpu.locked, "opcode {:#X} did not lock the cpu", opcode);
+        assert_eq!(cpu.program_counter, 0x100);
+    }
+}
+
+#[test]
+fn stop_ignores_the_second_byte(){
+    let mut cpu = GbCpu::default();
+    let mut memory = FullMemoryStub::new(&[]);
+    cpu.program_counter = 0x100;
+    memory.data[0x100] = 0x10;
+    memory.data[0x101] = 0x42;
+
+    cpu.run_opcode(&mut memory);
+
+    assert!(!cpu.locked);
+    assert_eq!(cpu.program_counter, 0x102);
+}
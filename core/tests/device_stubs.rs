@@ -0,0 +1,37 @@
+use magenboy_core::{apu::audio_device::*, keypad::{joypad::Joypad, joypad_provider::JoypadProvider}, machine::{Mode, gameboy::GameBoy}, mmu::carts::Mbc, ppu::{gb_ppu::{SCREEN_HEIGHT, SCREEN_WIDTH}, gfx_device::*}};
+#[cfg(feature = "dbg")]
+use magenboy_core::debugger::{DebuggerCommand, DebuggerInterface, DebuggerResult};
+
+// Devices for tests that run a whole gameboy and don't check its output
+pub struct StubGfxDevice;
+impl GfxDevice for StubGfxDevice{
+    fn swap_buffer(&mut self, _:&[Pixel; SCREEN_HEIGHT * SCREEN_WIDTH]) {}
+}
+
+pub struct StubAudioDevice;
+impl AudioDevice for StubAudioDevice{
+    fn push_buffer(&mut self, _:&[StereoSample; BUFFER_SIZE]) {}
+}
+
+pub struct StubJoypadProvider;
+impl JoypadProvider for StubJoypadProvider{
+    fn provide(&mut self, _:&mut Joypad) {}
+}
+
+#[cfg(feature = "dbg")]
+pub struct StubDebugger;
+#[cfg(feature = "dbg")]
+impl DebuggerInterface for StubDebugger{
+    fn should_stop(&self)->bool {false}
+    fn recv_command(&self)->DebuggerCommand {DebuggerCommand::Continue}
+    fn send_result(&self, _:DebuggerResult) {}
+}
+
+#[cfg(not(feature = "dbg"))]
+pub type StubGameBoy = GameBoy<'static, StubJoypadProvider, StubAudioDevice, StubGfxDevice>;
+#[cfg(feature = "dbg")]
+pub type StubGameBoy = GameBoy<'static, StubJoypadProvider, StubAudioDevice, StubGfxDevice, StubDebugger>;
+
+pub fn create_gameboy(mbc:&'static mut dyn Mbc, mode:Mode)->StubGameBoy{
+    return GameBoy::new_with_mode(mbc, StubJoypadProvider, StubAudioDevice, StubGfxDevice, mode, #[cfg(feature = "dbg")] StubDebugger);
+}
@@ -0,0 +1,23 @@
+use magenboy_core::mmu::Memory;
+
+// Covers the whole address space (unlike MemoryStub) since halt and stop read the IE register
+pub struct FullMemoryStub{
+    pub data:Vec<u8>,
+    pub double_speed:bool
+}
+
+impl FullMemoryStub{
+    // The program is placed at the cartridge entry point
+    pub fn new(program:&[u8])->Self{
+        let mut data = vec![0;0x1_0000];
+        data[0x100..0x100 + program.len()].copy_from_slice(program);
+        Self{data, double_speed:false}
+    }
+}
+
+impl Memory for FullMemoryStub{
+    fn read(&mut self, address:u16, _m_cycles:u8)->u8{self.data[address as usize]}
+    fn write(&mut self, address:u16, value:u8, _m_cycles:u8){self.data[address as usize] = value}
+    fn set_double_speed_mode(&mut self, state:bool){self.double_speed = state}
+    fn set_halt(&mut self, _:bool){}
+}
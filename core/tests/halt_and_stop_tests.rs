@@ -0,0 +1,154 @@
+mod full_memory_stub;
+mod device_stubs;
+
+use full_memory_stub::FullMemoryStub;
+use device_stubs::*;
+use magenboy_core::{cpu::gb_cpu::GbCpu, machine::{Mode, mbc_initializer::initialize_mbc}, mmu::carts::{HEADER_CHECKSUM_ADDRESS, calculate_header_checksum}, utils::memory_registers::*};
+
+fn create_cpu()->GbCpu{
+    let mut cpu = GbCpu::default();
+    cpu.program_counter = 0x100;
+    return cpu;
+}
+
+// HALT, INC A, INC A
+const HALT_PROGRAM:[u8;3] = [0x76, 0x3C, 0x3C];
+
+#[test]
+fn halt_without_pending_interrupts_halts(){
+    let mut cpu = create_cpu();
+    let mut memory = FullMemoryStub::new(&HALT_PROGRAM);
+    memory.data[IE_REGISTER_ADDRESS as usize] = 1;
+
+    cpu.run_opcode(&mut memory);
+
+    assert!(cpu.halt);
+    assert!(!cpu.halt_bug);
+    assert_eq!(cpu.program_counter, 0x101);
+}
+
+#[test]
+fn halt_with_ime_and_pending_interrupt_halts(){
+    let mut cpu = create_cpu();
+    cpu.mie = true;
+    let mut memory = FullMemoryStub::new(&HALT_PROGRAM);
+    memory.data[IE_REGISTER_ADDRESS as usize] = 1;
+    memory.data[IF_REGISTER_ADDRESS as usize] = 1;
+
+    cpu.run_opcode(&mut memory);
+
+    // The interrupt handler will unhalt it right away
+    assert!(cpu.halt);
+    assert!(!cpu.halt_bug);
+}
+
+#[test]
+fn halt_bug_reads_the_next_byte_twice(){
+    let mut cpu = create_cpu();
+    let mut memory = FullMemoryStub::new(&HALT_PROGRAM);
+    memory.data[IE_REGISTER_ADDRESS as usize] = 0b100;
+    memory.data[IF_REGISTER_ADDRESS as usize] = 0b100;
+
+    cpu.run_opcode(&mut memory);
+    assert!(!cpu.halt);
+    assert!(cpu.halt_bug);
+
+    // The first INC A is executed twice
+    cpu.run_opcode(&mut memory);
+    assert_eq!(cpu.program_counter, 0x101);
+    cpu.run_opcode(&mut memory);
+    assert_eq!(cpu.program_counter, 0x102);
+    cpu.run_opcode(&mut memory);
+    assert_eq!(cpu.program_counter, 0x103);
+    assert_eq!(*cpu.af.high(), 3);
+    assert!(!cpu.halt_bug);
+}
+
+#[test]
+fn halt_bug_with_operand_reads_the_opcode_as_operand(){
+    let mut cpu = create_cpu();
+    // HALT, LD A,n - the operand is the LD A,n opcode itself
+    let mut memory = FullMemoryStub::new(&[0x76, 0x3E, 0x14]);
+    memory.data[IE_REGISTER_ADDRESS as usize] = 1;
+    memory.data[IF_REGISTER_ADDRESS as usize] = 1;
+
+    cpu.run_opcode(&mut memory);
+    cpu.run_opcode(&mut memory);
+
+    assert_eq!(*cpu.af.high(), 0x3E);
+    assert_eq!(cpu.program_counter, 0x102);
+}
+
+#[test]
+fn stop_resets_div(){
+    let mut cpu = create_cpu();
+    let mut memory = FullMemoryStub::new(&[0x10, 0]);
+    memory.data[DIV_REGISTER_ADDRESS as usize] = 0x42;
+
+    cpu.run_opcode(&mut memory);
+
+    assert_eq!(memory.data[DIV_REGISTER_ADDRESS as usize], 0);
+    assert_eq!(cpu.speed_switch_pause_cycles, 0);
+    assert!(!cpu.double_speed);
+}
+
+#[test]
+fn speed_switch_pauses_the_cpu(){
+    let mut cpu = create_cpu();
+    cpu.cgb_mode = true;
+    let mut memory = FullMemoryStub::new(&[0x10, 0]);
+    memory.data[KEY1_REGISTER_ADDRESS as usize] = 1;
+    memory.data[DIV_REGISTER_ADDRESS as usize] = 0x42;
+
+    cpu.run_opcode(&mut memory);
+
+    assert!(cpu.double_speed);
+    assert!(memory.double_speed);
+    assert_eq!(memory.data[KEY1_REGISTER_ADDRESS as usize], 0);
+    assert_eq!(memory.data[DIV_REGISTER_ADDRESS as usize], 0);
+    assert_eq!(cpu.speed_switch_pause_cycles, 2050);
+}
+
+// Arms a timer interrupt that fires during the pause and switches speed
+const SPEED_SWITCH_PROGRAM:[u8;22] = [
+    0x3E, 0x04,         // LD A, 0x04
+    0xE0, 0xFF,         // LDH (IE), A
+    0x3E, 0xF0,         // LD A, 0xF0
+    0xE0, 0x05,         // LDH (TIMA), A
+    0x3E, 0x05,         // LD A, 0x05
+    0xE0, 0x07,         // LDH (TAC), A
+    0x3E, 0x01,         // LD A, 0x01
+    0xE0, 0x4D,         // LDH (KEY1), A
+    0xFB,               // EI
+    0x10, 0x00,         // STOP
+    0x00,               // NOP
+    0x18, 0xFE,         // JR -2
+];
+const AFTER_STOP_ADDRESS:u16 = 0x100 + 19;
+
+#[test]
+fn speed_switch_pause_blocks_the_cpu_and_interrupts(){
+    let mut rom = vec![0;0x8000];
+    rom[0x100..0x100 + SPEED_SWITCH_PROGRAM.len()].copy_from_slice(&SPEED_SWITCH_PROGRAM);
+    rom[0x143] = 0x80;
+    rom[HEADER_CHECKSUM_ADDRESS] = calculate_header_checksum(&rom);
+    let mut gameboy = create_gameboy(initialize_mbc(&rom, None).unwrap(), Mode::CGB);
+
+    while gameboy.cpu().program_counter != AFTER_STOP_ADDRESS{
+        gameboy.cycle_step();
+    }
+    assert!(gameboy.cpu().double_speed);
+    assert_eq!(gameboy.cpu().speed_switch_pause_cycles, 2050);
+
+    for _ in 0..2050{
+        let (_, m_cycles) = gameboy.cycle_step();
+        assert_eq!(m_cycles, 1);
+        assert_eq!(gameboy.cpu().program_counter, AFTER_STOP_ADDRESS);
+    }
+    // The timer interrupt was requested during the pause but not serviced
+    assert!(gameboy.cpu().mie);
+    assert_eq!(gameboy.peek_memory(IF_REGISTER_ADDRESS) & 0b100, 0b100);
+
+    gameboy.cycle_step();
+    assert_eq!(gameboy.cpu().program_counter, 0x50);
+}
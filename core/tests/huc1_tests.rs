@@ -0,0 +1,73 @@
+mod infrared_stub;
+
+use std::{cell::RefCell, rc::Rc};
+
+mod rom_fixture;
+
+use magenboy_core::mmu::carts::Mbc;
+use infrared_stub::{InfraredState, StubInfraredDevice};
+
+fn create_huc1()->&'static mut dyn Mbc{
+    // 32KB ram
+    return rom_fixture::create_mbc(0x4000 * 4, 0xFF, 0x3);
+}
+
+#[test]
+fn rom_banking(){
+    let mbc = create_huc1();
+    assert_eq!(mbc.read_current_bank(0), 1);
+    mbc.write_rom(0x2000, 2);
+    assert_eq!(mbc.read_current_bank(0), 2);
+    // Bank 0 is mapped as bank 1
+    mbc.write_rom(0x2000, 0);
+    assert_eq!(mbc.read_current_bank(0), 1);
+    // Wraps around the rom size
+    mbc.write_rom(0x2000, 7);
+    assert_eq!(mbc.read_current_bank(0), 3);
+}
+
+#[test]
+fn ram_is_always_mapped_and_banked(){
+    let mbc = create_huc1();
+    mbc.write_external_ram(0, 0x12);
+    mbc.write_rom(0x4000, 1);
+    mbc.write_external_ram(0, 0x34);
+    assert_eq!(mbc.read_external_ram(0), 0x34);
+    mbc.write_rom(0x4000, 0);
+    assert_eq!(mbc.read_external_ram(0), 0x12);
+    assert_eq!(mbc.get_ram()[0x2000], 0x34);
+}
+
+#[test]
+fn ir_mode_replaces_the_ram(){
+    let mbc = create_huc1();
+    mbc.write_external_ram(0, 0x12);
+    mbc.write_rom(0, 0xE);
+    // There is no device, the sensor never detects light
+    assert_eq!(mbc.read_external_ram(0), 0xC0);
+    // Turning on the IR LED does not reach the ram
+    mbc.write_external_ram(0, 0x1);
+    mbc.write_rom(0, 0x0);
+    assert_eq!(mbc.read_external_ram(0), 0x12);
+}
+
+#[test]
+fn ir_port_uses_the_infrared_device(){
+    let state = Rc::new(RefCell::new(InfraredState{led:false, light:true}));
+    let mbc = create_huc1();
+    assert!(mbc.has_infrared_port());
+    mbc.set_infrared_device(Box::leak(Box::new(StubInfraredDevice{state:state.clone()})));
+
+    // The sensor is ignored outside of IR mode
+    mbc.cycle(1);
+    mbc.write_rom(0, 0xE);
+    assert_eq!(mbc.read_external_ram(0), 0xC1);
+    mbc.write_external_ram(0, 0x1);
+    assert!(state.borrow().led);
+
+    state.borrow_mut().light = false;
+    mbc.cycle(1);
+    assert_eq!(mbc.read_external_ram(0), 0xC0);
+    mbc.write_external_ram(0, 0x0);
+    assert!(!state.borrow().led);
+}
@@ -0,0 +1,94 @@
+mod infrared_stub;
+
+use std::{cell::RefCell, rc::Rc};
+
+mod rom_fixture;
+
+use magenboy_core::{mmu::carts::Mbc, utils::GB_FREQUENCY};
+use infrared_stub::{InfraredState, StubInfraredDevice};
+
+fn create_huc3()->&'static mut dyn Mbc{
+    return rom_fixture::create_mbc(0x8000, 0xFE, 0x3);
+}
+
+fn rtc_command(mbc:&mut dyn Mbc, command:u8)->u8{
+    mbc.write_rom(0, 0xB);
+    mbc.write_external_ram(0, command);
+    mbc.write_rom(0, 0xC);
+    return mbc.read_external_ram(0);
+}
+
+fn read_time(mbc:&mut dyn Mbc)->u32{
+    rtc_command(mbc, 0x60);
+    rtc_command(mbc, 0x40);
+    rtc_command(mbc, 0x50);
+    return (0..6).fold(0, |time, i| time | (((rtc_command(mbc, 0x10) & 0xF) as u32) << (i * 4)));
+}
+
+#[test]
+fn rtc_time_can_be_set_and_read(){
+    let mbc = create_huc3();
+    // 1439 minutes (0x59F) and 2 days
+    rtc_command(mbc, 0x40);
+    rtc_command(mbc, 0x50);
+    for nibble in [0xF, 0x9, 0x5, 0x2, 0x0, 0x0]{
+        rtc_command(mbc, 0x30 | nibble);
+    }
+    rtc_command(mbc, 0x61);
+    assert_eq!(read_time(mbc), 0x2 << 12 | 1439);
+
+    mbc.cycle(GB_FREQUENCY * 60);
+    assert_eq!(read_time(mbc), 0x3 << 12);
+}
+
+#[test]
+fn ram_is_writable_only_in_read_write_mode(){
+    let mbc = create_huc3();
+    mbc.write_rom(0, 0xA);
+    mbc.write_external_ram(0, 0x12);
+    mbc.write_rom(0, 0x0);
+    mbc.write_external_ram(0, 0x34);
+    assert_eq!(mbc.read_external_ram(0), 0x12);
+    mbc.write_rom(0, 0xD);
+    assert_eq!(mbc.read_external_ram(0) & 1, 1);
+}
+
+#[test]
+fn rtc_registers_are_saved_in_the_footer(){
+    let mbc = create_huc3();
+    // Writing a value to the registers after the time (like the alarm)
+    rtc_command(mbc, 0x40);
+    rtc_command(mbc, 0x51);
+    for nibble in [0x1, 0x2, 0x3]{
+        rtc_command(mbc, 0x30 | nibble);
+    }
+    let footer = mbc.get_rtc_footer(1000).unwrap();
+
+    let other_mbc = create_huc3();
+    other_mbc.load_rtc_footer(&footer, 1000);
+    rtc_command(other_mbc, 0x40);
+    rtc_command(other_mbc, 0x51);
+    let values:Vec<u8> = (0..3).map(|_| rtc_command(other_mbc, 0x10) & 0xF).collect();
+    assert_eq!(values, [0x1, 0x2, 0x3]);
+}
+
+#[test]
+fn ir_mode_uses_the_infrared_device(){
+    let state = Rc::new(RefCell::new(InfraredState{led:false, light:true}));
+    let mbc = create_huc3();
+    assert!(mbc.has_infrared_port());
+    mbc.set_infrared_device(Box::leak(Box::new(StubInfraredDevice{state:state.clone()})));
+
+    mbc.write_rom(0, 0xE);
+    assert_eq!(mbc.read_external_ram(0), 0xC1);
+    mbc.write_external_ram(0, 0x1);
+    assert!(state.borrow().led);
+
+    state.borrow_mut().light = false;
+    mbc.cycle(1);
+    assert_eq!(mbc.read_external_ram(0), 0xC0);
+
+    // Leaving IR mode does not turn the LED off
+    mbc.write_rom(0, 0xA);
+    assert!(state.borrow().led);
+}
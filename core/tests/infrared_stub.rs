@@ -0,0 +1,19 @@
+use std::{cell::RefCell, rc::Rc};
+
+use magenboy_core::infrared::infrared_device::InfraredDevice;
+
+#[derive(Default)]
+pub struct InfraredState{
+    pub led:bool,
+    pub light:bool
+}
+
+// The state is shared with the test since the infrared port holds the device
+pub struct StubInfraredDevice{
+    pub state:Rc<RefCell<InfraredState>>
+}
+
+impl InfraredDevice for StubInfraredDevice{
+    fn set_led(&mut self, enabled:bool){self.state.borrow_mut().led = enabled}
+    fn receive(&mut self)->bool{self.state.borrow().light}
+}
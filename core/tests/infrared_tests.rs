@@ -0,0 +1,24 @@
+mod infrared_stub;
+
+use std::{cell::RefCell, rc::Rc};
+
+use magenboy_core::infrared::gb_infrared::GbInfrared;
+use infrared_stub::{InfraredState, StubInfraredDevice};
+
+#[test]
+fn rp_register_led_and_receive(){
+    let state = Rc::new(RefCell::new(InfraredState{led:false, light:true}));
+    let mut infrared = GbInfrared::new();
+    infrared.set_device(Box::leak(Box::new(StubInfraredDevice{state:state.clone()})));
+
+    // Reading is disabled
+    assert_eq!(infrared.get_rp_register(), 0b0011_1110);
+    infrared.set_rp_register(0xC1);
+    assert!(state.borrow().led);
+    assert_eq!(infrared.get_rp_register(), 0xFD);
+
+    state.borrow_mut().light = false;
+    assert_eq!(infrared.get_rp_register(), 0xFF);
+    infrared.set_rp_register(0);
+    assert!(!state.borrow().led);
+}
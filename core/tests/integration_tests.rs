@@ -193,7 +193,7 @@ fn run_integration_test_from_url(program_url:&str, frames_to_execute:u32, expect
 }
 
 fn run_integration_test(program:Vec<u8>, boot_rom:Option<Bootrom>, frames_to_execute:u32, expected_hash:u64, fail_message:String, mode:Option<Mode>){
-    let mbc:&'static mut dyn Mbc = initialize_mbc(&program, None);
+    let mbc:&'static mut dyn Mbc = initialize_mbc(&program, None).unwrap();
     let found = AtomicBool::new(false);
     let mut gameboy = match boot_rom {
         Some(b)=>GameBoy::new_with_bootrom(
@@ -274,7 +274,7 @@ fn calc_hash(rom_path:&str, boot_rom_path:Option<&str>, mode:Option<Mode>){
     
     let program = Vec::from(program);
 
-    let mbc = initialize_mbc(&program, None);
+    let mbc = initialize_mbc(&program, None).unwrap();
 
     let test_gfx_device = GetHashGfxDevice{ last_hash: 0, last_hash_counter: 0, frames_counter: 0 };
     let mut gameboy = if let Some(boot_rom_path) = boot_rom_path{
@@ -0,0 +1,80 @@
+mod rom_fixture;
+
+use magenboy_core::machine::mbc_initializer::initialize_mbc;
+
+const LOGO:[u8;0x30] = [0xCE; 0x30];
+
+// Every bank starts with its own number
+fn create_rom(size:usize, multicart:bool)->Vec<u8>{
+    // MBC1+RAM+BATTERY with 32KB ram
+    let mut rom = rom_fixture::create_rom(size, 0x3, 0x3);
+    for (bank, data) in rom.chunks_exact_mut(0x4000).enumerate(){
+        if bank % 0x10 == 0 && (multicart || bank == 0){
+            data[0x104..0x134].copy_from_slice(&LOGO);
+        }
+    }
+    return rom;
+}
+
+#[test]
+fn multicart_uses_4_bits_of_bank1(){
+    let rom = create_rom(0x10_0000, true);
+    let mbc = initialize_mbc(&rom, None).unwrap();
+    mbc.write_rom(0x2000, 0x12);
+    mbc.write_rom(0x4000, 1);
+    assert_eq!(mbc.read_current_bank(0), 0x12);
+    assert_eq!(mbc.read_bank0(0), 0);
+
+    // Mode 1 maps the games bank 0 to the bank0 area
+    mbc.write_rom(0x6000, 1);
+    assert_eq!(mbc.read_bank0(0), 0x10);
+
+    // The zero check is done on 5 bits
+    mbc.write_rom(0x2000, 0x10);
+    assert_eq!(mbc.read_current_bank(0), 0x10);
+}
+
+#[test]
+fn regular_mbc1_uses_5_bits_of_bank1(){
+    let rom = create_rom(0x10_0000, false);
+    let mbc = initialize_mbc(&rom, None).unwrap();
+    mbc.write_rom(0x2000, 0x12);
+    mbc.write_rom(0x4000, 1);
+    assert_eq!(mbc.read_current_bank(0), 0x32);
+    mbc.write_rom(0x6000, 1);
+    assert_eq!(mbc.read_bank0(0), 0x20);
+}
+
+#[test]
+fn regular_mbc1_bank2_in_both_modes(){
+    let rom = create_rom(0x20_0000, false);
+    let mbc = initialize_mbc(&rom, None).unwrap();
+    mbc.write_rom(0, 0xA);
+    mbc.write_rom(0x2000, 0x3);
+    mbc.write_rom(0x4000, 2);
+
+    // Mode 0 - BANK2 selects the upper bits of the 0x4000-0x7FFF bank only
+    assert_eq!(mbc.read_current_bank(0), 0x43);
+    assert_eq!(mbc.read_bank0(0), 0);
+    mbc.write_external_ram(0, 0x12);
+    assert_eq!(mbc.get_ram()[0], 0x12);
+
+    // Mode 1 - BANK2 selects the 0x0000-0x3FFF bank and the ram bank as well
+    mbc.write_rom(0x6000, 1);
+    assert_eq!(mbc.read_current_bank(0), 0x43);
+    assert_eq!(mbc.read_bank0(0), 0x40);
+    mbc.write_external_ram(0, 0x34);
+    assert_eq!(mbc.get_ram()[2 * 0x2000], 0x34);
+    assert_eq!(mbc.get_ram()[0], 0x12);
+}
+
+#[test]
+fn regular_mbc1_small_rom_ignores_bank2_in_mode1(){
+    let rom = create_rom(0x8_0000, false);
+    let mbc = initialize_mbc(&rom, None).unwrap();
+    mbc.write_rom(0x4000, 1);
+    mbc.write_rom(0x6000, 1);
+    // The upper bank bits are not connected to a 512KB rom
+    assert_eq!(mbc.read_bank0(0), 0);
+    assert_eq!(mbc.read_current_bank(0), 1);
+}
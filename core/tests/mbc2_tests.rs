@@ -0,0 +1,39 @@
+mod rom_fixture;
+
+use magenboy_core::mmu::carts::Mbc;
+
+fn create_mbc2()->&'static mut dyn Mbc{
+    // MBC2+BATTERY
+    return rom_fixture::create_mbc(0x4000 * 4, 0x6, 0);
+}
+
+#[test]
+fn ram_is_4_bits_and_echoed(){
+    let mbc = create_mbc2();
+    mbc.write_external_ram(0, 0x12);
+    assert_eq!(mbc.read_external_ram(0), 0xFF);    // Ram is disabled
+
+    mbc.write_rom(0, 0xA);
+    mbc.write_external_ram(0x10, 0x12);
+    assert_eq!(mbc.read_external_ram(0x10), 0xF2);
+    assert_eq!(mbc.read_external_ram(0x210), 0xF2);
+    assert_eq!(mbc.read_external_ram(0x1E10), 0xF2);
+    assert_eq!(mbc.get_ram().len(), 0x200);
+}
+
+#[test]
+fn address_bit_8_selects_the_register(){
+    let mbc = create_mbc2();
+    mbc.write_rom(0x2000, 3);
+    assert_eq!(mbc.read_current_bank(0), 1);
+    mbc.write_rom(0x2100, 3);
+    assert_eq!(mbc.read_current_bank(0), 3);
+    mbc.write_rom(0x0100, 0);
+    assert_eq!(mbc.read_current_bank(0), 1);
+
+    mbc.write_rom(0x0100, 0xA);
+    assert_eq!(mbc.read_external_ram(0), 0xFF);
+    mbc.write_rom(0x0000, 0xA);
+    mbc.write_external_ram(0, 0x5);
+    assert_eq!(mbc.read_external_ram(0), 0xF5);
+}
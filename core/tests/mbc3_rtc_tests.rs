@@ -0,0 +1,108 @@
+mod rom_fixture;
+
+use magenboy_core::{mmu::carts::*, utils::GB_FREQUENCY};
+
+const SECONDS:u8 = 0x8;
+const MINUTES:u8 = 0x9;
+const HOURS:u8 = 0xA;
+const DAYS_LOW:u8 = 0xB;
+const DAYS_HIGH:u8 = 0xC;
+
+fn create_rtc_mbc()->&'static mut dyn Mbc{
+    // MBC3+TIMER+RAM+BATTERY
+    let mbc = rom_fixture::create_mbc(0x8000, 0x10, 0x3);
+    mbc.write_rom(0, 0xA);
+    return mbc;
+}
+
+fn latch(mbc:&mut dyn Mbc){
+    mbc.write_rom(0x6000, 0);
+    mbc.write_rom(0x6000, 1);
+}
+
+fn read_rtc(mbc:&mut dyn Mbc, register:u8)->u8{
+    mbc.write_rom(0x4000, register);
+    return mbc.read_external_ram(0);
+}
+
+fn write_rtc(mbc:&mut dyn Mbc, register:u8, value:u8){
+    mbc.write_rom(0x4000, register);
+    mbc.write_external_ram(0, value);
+}
+
+#[test]
+fn rtc_ticks_and_latches(){
+    let mbc = create_rtc_mbc();
+    write_rtc(mbc, SECONDS, 59);
+    write_rtc(mbc, MINUTES, 59);
+    write_rtc(mbc, HOURS, 23);
+    write_rtc(mbc, DAYS_LOW, 0xFF);
+    write_rtc(mbc, DAYS_HIGH, 1);
+    mbc.cycle(GB_FREQUENCY);
+
+    // Not latched yet
+    assert_eq!(read_rtc(mbc, SECONDS), 59);
+    latch(mbc);
+    assert_eq!(read_rtc(mbc, SECONDS), 0);
+    assert_eq!(read_rtc(mbc, MINUTES), 0);
+    assert_eq!(read_rtc(mbc, HOURS), 0);
+    assert_eq!(read_rtc(mbc, DAYS_LOW), 0);
+    assert_eq!(read_rtc(mbc, DAYS_HIGH), 0x80);
+
+    // Latching only on the 0 -> 1 transition
+    mbc.cycle(GB_FREQUENCY);
+    mbc.write_rom(0x6000, 1);
+    assert_eq!(read_rtc(mbc, SECONDS), 0);
+    latch(mbc);
+    assert_eq!(read_rtc(mbc, SECONDS), 1);
+}
+
+#[test]
+fn rtc_halt_stops_the_clock(){
+    let mbc = create_rtc_mbc();
+    write_rtc(mbc, DAYS_HIGH, 0x40);
+    mbc.cycle(GB_FREQUENCY * 5);
+    latch(mbc);
+    assert_eq!(read_rtc(mbc, SECONDS), 0);
+}
+
+#[test]
+fn rtc_footer_catches_up_on_elapsed_time(){
+    let mbc = create_rtc_mbc();
+    write_rtc(mbc, SECONDS, 30);
+    let footer = mbc.get_rtc_footer(1000).unwrap();
+    assert_eq!(footer.len(), RTC_FOOTER_SIZE);
+
+    let mut save_data = mbc.get_ram().to_vec();
+    save_data.extend_from_slice(&footer);
+    let (ram, footer) = split_rtc_footer(&save_data);
+    assert_eq!(ram.len(), 0x8000);
+
+    let other_mbc = create_rtc_mbc();
+    other_mbc.load_rtc_footer(footer.unwrap(), 1000 + 60 * 60 * 24 + 35);
+    latch(other_mbc);
+    assert_eq!(read_rtc(other_mbc, SECONDS), 5);
+    assert_eq!(read_rtc(other_mbc, MINUTES), 1);
+    assert_eq!(read_rtc(other_mbc, HOURS), 0);
+    assert_eq!(read_rtc(other_mbc, DAYS_LOW), 1);
+}
+
+#[test]
+fn rtc_footer_catches_up_from_invalid_registers(){
+    let mbc = create_rtc_mbc();
+    write_rtc(mbc, SECONDS, 63);
+    write_rtc(mbc, MINUTES, 63);
+    write_rtc(mbc, HOURS, 31);
+    let footer = mbc.get_rtc_footer(1000).unwrap();
+
+    // The invalid registers wrap to 0 without carrying after 1 + 60 + 3600 seconds, the rest are 600 days and 5 seconds
+    let other_mbc = create_rtc_mbc();
+    other_mbc.load_rtc_footer(&footer, 1000 + 3661 + (60 * 60 * 24 * 600) + 5);
+    latch(other_mbc);
+    assert_eq!(read_rtc(other_mbc, SECONDS), 5);
+    assert_eq!(read_rtc(other_mbc, MINUTES), 0);
+    assert_eq!(read_rtc(other_mbc, HOURS), 0);
+    assert_eq!(read_rtc(other_mbc, DAYS_LOW), 88);
+    // The day counter overflowed
+    assert_eq!(read_rtc(other_mbc, DAYS_HIGH), 0x80);
+}
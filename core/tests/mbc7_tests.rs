@@ -0,0 +1,75 @@
+mod rom_fixture;
+
+use magenboy_core::{keypad::tilt_provider::TiltProvider, mmu::carts::Mbc};
+
+const EEPROM_ADDRESS:u16 = 0x80;
+const CS:u8 = 0x80;
+const CLK:u8 = 0x40;
+
+struct StubTiltProvider;
+impl TiltProvider for StubTiltProvider{
+    fn provide(&mut self)->(f32, f32) {(1.0, -1.0)}
+}
+
+fn create_mbc7()->&'static mut dyn Mbc{
+    let mbc = rom_fixture::create_mbc(0x8000, 0x22, 0);
+    mbc.write_rom(0, 0xA);
+    mbc.write_rom(0x4000, 0x40);
+    return mbc;
+}
+
+// Returns the DO bit after the rising edge
+fn clock_bit(mbc:&mut dyn Mbc, bit:bool)->bool{
+    let di = (bit as u8) << 1;
+    mbc.write_external_ram(EEPROM_ADDRESS, CS | di);
+    mbc.write_external_ram(EEPROM_ADDRESS, CS | CLK | di);
+    return mbc.read_external_ram(EEPROM_ADDRESS) & 1 != 0;
+}
+
+fn send_bits(mbc:&mut dyn Mbc, value:u16, count:u8){
+    for i in (0..count).rev(){
+        clock_bit(mbc, (value >> i) & 1 != 0);
+    }
+}
+
+fn eeprom_command(mbc:&mut dyn Mbc, command:u16){
+    mbc.write_external_ram(EEPROM_ADDRESS, 0);
+    // Start bit, 2 bits opcode and 8 bits address
+    send_bits(mbc, (1 << 10) | command, 11);
+}
+
+#[test]
+fn eeprom_write_and_read(){
+    let mbc = create_mbc7();
+    eeprom_command(mbc, 0b00_1100_0000);    // EWEN
+    eeprom_command(mbc, 0b01_0000_0011);    // WRITE address 3
+    send_bits(mbc, 0xBEEF, 16);
+    eeprom_command(mbc, 0b10_0000_0011);    // READ address 3
+    assert_eq!(mbc.read_external_ram(EEPROM_ADDRESS) & 1, 0);
+    let value = (0..16).fold(0u16, |value, _| (value << 1) | clock_bit(mbc, false) as u16);
+    assert_eq!(value, 0xBEEF);
+    assert_eq!(&mbc.get_ram()[6..8], &[0xEF, 0xBE]);
+}
+
+#[test]
+fn eeprom_ignores_writes_when_disabled(){
+    let mbc = create_mbc7();
+    eeprom_command(mbc, 0b01_0000_0000);
+    send_bits(mbc, 0x1234, 16);
+    assert_eq!(&mbc.get_ram()[0..2], &[0xFF, 0xFF]);
+}
+
+#[test]
+fn accelerometer_latches_after_erase(){
+    let mbc = create_mbc7();
+    mbc.set_tilt_provider(Box::leak(Box::new(StubTiltProvider)));
+    mbc.write_external_ram(0x10, 0xAA);
+    assert_eq!(mbc.read_external_ram(0x30), 0x80);
+
+    mbc.write_external_ram(0x00, 0x55);
+    mbc.write_external_ram(0x10, 0xAA);
+    let x = mbc.read_external_ram(0x20) as u16 | ((mbc.read_external_ram(0x30) as u16) << 8);
+    let y = mbc.read_external_ram(0x40) as u16 | ((mbc.read_external_ram(0x50) as u16) << 8);
+    assert_eq!(x, 0x81D0 + 0x70);
+    assert_eq!(y, 0x81D0 - 0x70);
+}
@@ -0,0 +1,41 @@
+mod rom_fixture;
+
+use magenboy_core::mmu::carts::camera_image_source::*;
+
+// Left half is black and right half is white
+struct HalfImageSource;
+impl CameraImageSource for HalfImageSource{
+    fn capture(&mut self, buffer:&mut [[u8; CAMERA_IMAGE_WIDTH]; CAMERA_IMAGE_HEIGHT]) {
+        for row in buffer.iter_mut(){
+            row[..CAMERA_IMAGE_WIDTH / 2].fill(0);
+            row[CAMERA_IMAGE_WIDTH / 2..].fill(0xFF);
+        }
+    }
+}
+
+#[test]
+fn capture_writes_dithered_tiles_to_ram(){
+    let mbc = rom_fixture::create_mbc(0x8000, 0xFC, 0x4);
+    mbc.set_camera_image_source(Box::leak(Box::new(HalfImageSource)));
+
+    mbc.write_rom(0x4000, 0x10);
+    // Exposure of 0x1000 with no gain keeps the image as is
+    mbc.write_external_ram(0x2, 0x10);
+    mbc.write_external_ram(0x3, 0x00);
+    for i in 0..16{
+        mbc.write_external_ram(0x6 + (i * 3), 0x40);
+        mbc.write_external_ram(0x7 + (i * 3), 0x80);
+        mbc.write_external_ram(0x8 + (i * 3), 0xC0);
+    }
+    mbc.write_external_ram(0, 1);
+    assert_eq!(mbc.read_external_ram(0) & 1, 1);
+    mbc.cycle(32446 + 512 + (16 * 0x1000));
+    assert_eq!(mbc.read_external_ram(0) & 1, 0);
+
+    mbc.write_rom(0x4000, 0);
+    // First tile is black and the last tile in the row is white
+    assert_eq!(mbc.read_external_ram(0x100), 0xFF);
+    assert_eq!(mbc.read_external_ram(0x101), 0xFF);
+    assert_eq!(mbc.read_external_ram(0x100 + (15 * 16)), 0);
+    assert_eq!(mbc.read_external_ram(0x101 + (15 * 16)), 0);
+}
@@ -0,0 +1,21 @@
+#![allow(dead_code)]    // Each test file uses only some of the helpers
+
+use magenboy_core::{machine::mbc_initializer::initialize_mbc, mmu::carts::{Mbc, MBC_RAM_SIZE_LOCATION, HEADER_CHECKSUM_ADDRESS, calculate_header_checksum}};
+
+const CARTRIDGE_TYPE_ADDRESS:usize = 0x147;
+
+// Each bank starts with its number so the tests can tell which bank is mapped
+pub fn create_rom(size:usize, cartridge_type:u8, ram_size:u8)->Vec<u8>{
+    let mut rom = vec![0;size];
+    for (bank, data) in rom.chunks_exact_mut(0x4000).enumerate(){
+        data[0] = bank as u8;
+    }
+    rom[CARTRIDGE_TYPE_ADDRESS] = cartridge_type;
+    rom[MBC_RAM_SIZE_LOCATION] = ram_size;
+    rom[HEADER_CHECKSUM_ADDRESS] = calculate_header_checksum(&rom);
+    return rom;
+}
+
+pub fn create_mbc(size:usize, cartridge_type:u8, ram_size:u8)->&'static mut dyn Mbc{
+    return initialize_mbc(&create_rom(size, cartridge_type, ram_size), None).unwrap();
+}
@@ -0,0 +1,93 @@
+mod rom_fixture;
+mod device_stubs;
+
+use device_stubs::*;
+use magenboy_core::{machine::{Mode, gameboy::SAVE_STATE_VERSION, mbc_initializer::initialize_mbc}, SaveStateError};
+
+// Turns on the LCD and fills WRAM bank 0 with an incrementing counter in an endless loop
+const PROGRAM:[u8;18] = [
+    0x3E, 0x91,         // LD A, 0x91
+    0xE0, 0x40,         // LDH (LCDC), A
+    0x21, 0x00, 0xC0,   // LD HL, 0xC000
+    0x04,               // INC B
+    0x70,               // LD (HL), B
+    0x23,               // INC HL
+    0x7C,               // LD A, H
+    0xE6, 0xCF,         // AND 0xCF
+    0xF6, 0xC0,         // OR 0xC0
+    0x67,               // LD H, A
+    0x18, 0xF5,         // JR -11
+];
+
+fn create_rom(global_checksum:u8)->Vec<u8>{
+    // The program and the global checksum are not covered by the header checksum
+    let mut rom = rom_fixture::create_rom(0x8000, 0, 0);
+    rom[0x100..0x100 + PROGRAM.len()].copy_from_slice(&PROGRAM);
+    rom[0x14F] = global_checksum;
+    return rom;
+}
+
+fn run_frames(gameboy:&mut StubGameBoy, frames:u32){
+    for _ in 0..frames{
+        gameboy.cycle_frame();
+    }
+}
+
+#[test]
+fn load_state_resumes_from_the_same_point(){
+    let rom = create_rom(0);
+    let mut gameboy = create_gameboy(initialize_mbc(&rom, None).unwrap(), Mode::DMG);
+    run_frames(&mut gameboy, 10);
+
+    let mut state = vec![0; gameboy.save_state_size()];
+    assert_eq!(gameboy.save_state(&mut state), Ok(state.len()));
+    run_frames(&mut gameboy, 7);
+    let mut expected_state = vec![0; gameboy.save_state_size()];
+    gameboy.save_state(&mut expected_state).unwrap();
+
+    gameboy.load_state(&state).unwrap();
+    run_frames(&mut gameboy, 7);
+    let mut actual_state = vec![0; gameboy.save_state_size()];
+    gameboy.save_state(&mut actual_state).unwrap();
+
+    assert_ne!(state, expected_state);
+    assert_eq!(expected_state, actual_state);
+}
+
+#[test]
+fn load_state_rejects_invalid_states(){
+    let rom = create_rom(0);
+    let mut gameboy = create_gameboy(initialize_mbc(&rom, None).unwrap(), Mode::DMG);
+    run_frames(&mut gameboy, 1);
+    let mut state = vec![0; gameboy.save_state_size()];
+    assert_eq!(gameboy.save_state(&mut state[..10]), Err(SaveStateError::BufferTooSmall));
+    gameboy.save_state(&mut state).unwrap();
+
+    assert_eq!(gameboy.load_state(&state[..state.len() - 1]), Err(SaveStateError::BufferTooSmall));
+
+    let mut bad_version_state = state.clone();
+    bad_version_state[4..6].copy_from_slice(&(SAVE_STATE_VERSION + 1).to_le_bytes());
+    assert_eq!(gameboy.load_state(&bad_version_state), Err(SaveStateError::UnsupportedVersion(SAVE_STATE_VERSION + 1)));
+
+    let mut bad_header_state = state.clone();
+    bad_header_state[0] = 0;
+    assert_eq!(gameboy.load_state(&bad_header_state), Err(SaveStateError::InvalidHeader));
+
+    let other_rom = create_rom(1);
+    let mut other_gameboy = create_gameboy(initialize_mbc(&other_rom, None).unwrap(), Mode::DMG);
+    assert_eq!(other_gameboy.load_state(&state), Err(SaveStateError::MachineMismatch));
+}
+
+#[test]
+fn save_state_size_is_constant(){
+    let rom = create_rom(0);
+    let mut gameboy = create_gameboy(initialize_mbc(&rom, None).unwrap(), Mode::DMG);
+    let size = gameboy.save_state_size();
+    // Sampling through whole frames in order to catch every PPU state with the FIFOs at different lengths
+    for step in 0..2 * 17556{
+        gameboy.cycle_step();
+        if step % 5 == 0{
+            assert_eq!(gameboy.save_state_size(), size);
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use std::{cell::RefCell, rc::Rc};
+
+use magenboy_core::{machine::Mode, serial::{gb_serial::GbSerial, serial_device::SerialDevice}};
+
+#[derive(Default)]
+struct EchoState{
+    sent:Vec<u8>,
+    pending_external:Option<u8>
+}
+
+// The state is shared with the test since the serial holds the device
+struct EchoDevice{
+    state:Rc<RefCell<EchoState>>
+}
+
+impl SerialDevice for EchoDevice{
+    fn transfer_internal(&mut self, data:u8)->u8{
+        self.state.borrow_mut().sent.push(data);
+        return !data;
+    }
+
+    fn transfer_external(&mut self, data:u8)->Option<u8>{
+        let mut state = self.state.borrow_mut();
+        let received = state.pending_external.take()?;
+        state.sent.push(data);
+        return Some(received);
+    }
+}
+
+fn create_serial_with_device(mode:Mode)->(GbSerial, Rc<RefCell<EchoState>>){
+    let state = Rc::new(RefCell::new(EchoState::default()));
+    let mut serial = GbSerial::new(mode);
+    serial.set_device(Box::leak(Box::new(EchoDevice{state:state.clone()})));
+    return (serial, state);
+}
+
+#[test]
+fn internal_clock_transfer_completes_after_8_bits(){
+    let (mut serial, state) = create_serial_with_device(Mode::DMG);
+    let mut if_register = 0;
+
+    serial.sb_register = 0x5A;
+    serial.set_sc_register(0x81);
+    serial.cycle(128 * 8 - 1, &mut if_register);
+    assert_eq!(if_register, 0);
+    assert_eq!(serial.get_sc_register(), 0xFF);
+
+    serial.cycle(1, &mut if_register);
+    assert_eq!(if_register, 0b1000);
+    assert_eq!(serial.sb_register, 0xA5);
+    assert_eq!(serial.get_sc_register(), 0x7F);
+    assert_eq!(state.borrow().sent, vec![0x5A]);
+}
+
+#[test]
+fn cgb_fast_clock_transfer(){
+    let mut serial = GbSerial::new(Mode::CGB);
+    let mut if_register = 0;
+    serial.sb_register = 0x12;
+    serial.set_sc_register(0x83);
+    assert_eq!(serial.get_sc_register(), 0xFF);
+    serial.cycle(4 * 8, &mut if_register);
+    assert_eq!(if_register, 0b1000);
+    // Nothing is connected
+    assert_eq!(serial.sb_register, 0xFF);
+    assert_eq!(serial.get_sc_register(), 0x7F);
+}
+
+#[test]
+fn external_clock_waits_for_the_partner(){
+    let (mut serial, state) = create_serial_with_device(Mode::DMG);
+    let mut if_register = 0;
+
+    serial.sb_register = 0x42;
+    serial.set_sc_register(0x80);
+    serial.cycle(128 * 8 * 10, &mut if_register);
+    assert_eq!(if_register, 0);
+
+    state.borrow_mut().pending_external = Some(0x99);
+    serial.cycle(1, &mut if_register);
+    assert_eq!(if_register, 0b1000);
+    assert_eq!(serial.sb_register, 0x99);
+    assert_eq!(state.borrow().sent, vec![0x42]);
+}
+
+// Replies to internal transfers only after a few polls, like a device over a network
+struct DelayedDevice{
+    polls_until_reply:u32
+}
+
+impl SerialDevice for DelayedDevice{
+    fn transfer_internal(&mut self, data:u8)->u8{!data}
+
+    fn transfer_external(&mut self, _data:u8)->Option<u8>{None}
+
+    fn poll_transfer_internal(&mut self, data:u8)->Option<u8>{
+        if self.polls_until_reply > 0{
+            self.polls_until_reply -= 1;
+            return None;
+        }
+        return Some(self.transfer_internal(data));
+    }
+}
+
+#[test]
+fn internal_clock_transfer_stays_pending_until_the_device_replies(){
+    let mut serial = GbSerial::new(Mode::DMG);
+    serial.set_device(Box::leak(Box::new(DelayedDevice{polls_until_reply:2})));
+    let mut if_register = 0;
+
+    serial.sb_register = 0x5A;
+    serial.set_sc_register(0x81);
+    serial.cycle(128 * 8, &mut if_register);
+    serial.cycle(1, &mut if_register);
+    assert_eq!(if_register, 0);
+    assert_eq!(serial.get_sc_register(), 0xFF);
+    assert_eq!(serial.sb_register, 0x5A);
+
+    serial.cycle(1, &mut if_register);
+    assert_eq!(if_register, 0b1000);
+    assert_eq!(serial.sb_register, 0xA5);
+    assert_eq!(serial.get_sc_register(), 0x7F);
+}
@@ -0,0 +1,93 @@
+use magenboy_core::{GfxDevice, Pixel, SGB_SCREEN_HEIGHT, SGB_SCREEN_WIDTH, ppu::{color::*, gb_ppu::{SCREEN_HEIGHT, SCREEN_WIDTH}}, sgb::gb_sgb::GbSgb};
+
+struct CaptureGfxDevice{
+    border:bool,
+    screen:Vec<Pixel>
+}
+
+impl GfxDevice for CaptureGfxDevice{
+    fn swap_buffer(&mut self, buffer:&[Pixel; SCREEN_HEIGHT * SCREEN_WIDTH]) {
+        self.screen = buffer.to_vec();
+    }
+
+    fn sgb_border_supported(&self)->bool {self.border}
+
+    fn swap_sgb_buffer(&mut self, buffer:&[Pixel; SGB_SCREEN_HEIGHT * SGB_SCREEN_WIDTH]) {
+        self.screen = buffer.to_vec();
+    }
+}
+
+fn send_packet(sgb:&mut GbSgb, packet:[u8;16]){
+    sgb.write_joyp(0x00);
+    sgb.write_joyp(0x30);
+    for i in 0..128{
+        let bit = (packet[i / 8] >> (i % 8)) & 1 != 0;
+        sgb.write_joyp(if bit {0x10} else {0x20});
+        sgb.write_joyp(0x30);
+    }
+    // Stop bit
+    sgb.write_joyp(0x20);
+    sgb.write_joyp(0x30);
+}
+
+fn sgb_pixel(color:u16)->Pixel{
+    Pixel::from(Color::from(color))
+}
+
+#[test]
+fn pal01_colorizes_the_dmg_shades(){
+    let mut sgb = GbSgb::new();
+    let mut device = CaptureGfxDevice{border:false, screen:Vec::new()};
+    // PAL01 with a single packet, color 0 and 3 colors for each palette
+    let mut packet = [0;16];
+    packet[0] = 0x01;
+    packet[1..3].copy_from_slice(&0x001Fu16.to_le_bytes());
+    packet[3..5].copy_from_slice(&0x03E0u16.to_le_bytes());
+    send_packet(&mut sgb, packet);
+
+    let mut frame = [Pixel::from(WHITE);SCREEN_HEIGHT * SCREEN_WIDTH];
+    frame[1] = Pixel::from(LIGHT_GRAY);
+    sgb.present_frame(&frame, &mut device);
+
+    assert_eq!(device.screen.len(), SCREEN_HEIGHT * SCREEN_WIDTH);
+    assert_eq!(device.screen[0], sgb_pixel(0x001F));
+    assert_eq!(device.screen[1], sgb_pixel(0x03E0));
+}
+
+#[test]
+fn border_device_receives_the_game_screen_centered(){
+    let mut sgb = GbSgb::new();
+    let mut device = CaptureGfxDevice{border:true, screen:Vec::new()};
+    let frame = [Pixel::from(BLACK);SCREEN_HEIGHT * SCREEN_WIDTH];
+    sgb.present_frame(&frame, &mut device);
+
+    assert_eq!(device.screen.len(), SGB_SCREEN_HEIGHT * SGB_SCREEN_WIDTH);
+    // Without a border the backdrop is palette 0 color 0 (white by default)
+    assert_eq!(device.screen[0], sgb_pixel(0x7FFF));
+    assert_eq!(device.screen[(40 * SGB_SCREEN_WIDTH) + 48], sgb_pixel(0));
+    assert_eq!(device.screen[(40 * SGB_SCREEN_WIDTH) + 47], sgb_pixel(0x7FFF));
+}
+
+#[test]
+fn mlt_req_reports_the_selected_controller(){
+    let mut sgb = GbSgb::new();
+    assert_eq!(sgb.read_joyp(0xFF), 0xFF);
+
+    // MLT_REQ with 2 players
+    let mut packet = [0;16];
+    packet[0] = (0x11 << 3) | 1;
+    packet[1] = 1;
+    send_packet(&mut sgb, packet);
+    assert_eq!(sgb.read_joyp(0xFF), 0xFF);
+
+    // Releasing P15 moves to the next controller
+    sgb.write_joyp(0x10);
+    sgb.write_joyp(0x30);
+    assert_eq!(sgb.read_joyp(0xFF), 0xFE);
+    // The second controller has no buttons pressed
+    assert_eq!(sgb.read_joyp(0xE0), 0xEF);
+
+    sgb.write_joyp(0x10);
+    sgb.write_joyp(0x30);
+    assert_eq!(sgb.read_joyp(0xFF), 0xFF);
+}
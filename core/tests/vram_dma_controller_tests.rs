@@ -1,4 +1,4 @@
-use magenboy_core::{mmu::{vram_dma_controller::VramDmaController, external_memory_bus::*, carts::Mbc}, ppu::{gb_ppu::*, gfx_device::*, ppu_state::PpuState}, machine::Mode};
+use magenboy_core::{mmu::{vram_dma_controller::VramDmaController, external_memory_bus::*, carts::Mbc}, ppu::{gb_ppu::*, gfx_device::*, ppu_state::PpuState}, machine::Mode, utils::save_state::*};
 
 struct StubGfxDevice;
 impl GfxDevice for StubGfxDevice{
@@ -19,6 +19,10 @@ impl Mbc for EmptyMbc{
     fn read_external_ram(&self, _:u16)->u8 {unreachable!()}
     fn write_external_ram(&mut self, _:u16, _:u8) {unreachable!()}
 }
+impl SaveState for EmptyMbc{
+    fn save_state(&self, _:&mut StateWriter) {unreachable!()}
+    fn load_state(&mut self, _:&mut StateReader)->Result<(), SaveStateError> {unreachable!()}
+}
 
 #[test]
 fn vram_dma_transfer_test(){
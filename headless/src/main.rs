@@ -0,0 +1,168 @@
+mod test_result;
+
+use std::{cell::RefCell, env, path::Path, rc::Rc};
+
+use magenboy_common::{check_for_terminal_feature_flag, get_mode, get_terminal_feature_flag_value, image_file_writer::write_image_file, mbc_handler::read_program};
+use magenboy_core::{apu::audio_device::*, keypad::joypad::Joypad, machine::mbc_initializer::initialize_mbc, ppu::{gb_ppu::{SCREEN_HEIGHT, SCREEN_WIDTH}, gfx_device::{GfxDevice, Pixel}}, GameBoy, JoypadProvider, SerialDevice};
+
+#[cfg(feature = "dbg")]
+use magenboy_core::debugger::{DebuggerCommand, DebuggerInterface, DebuggerResult};
+
+use crate::test_result::*;
+
+// 1 minute of emulated time
+const DEFAULT_FRAMES_COUNT:u32 = 60 * 60;
+// There is no vblank while the LCD is off, every step takes at least 1 M-cycle so this ends the frame in time
+const MAX_STEPS_PER_FRAME:u32 = 17556;
+
+const EXIT_CODE_PASSED:i32 = 0;
+const EXIT_CODE_FAILED:i32 = 1;
+// The rom did not report a result before the frames limit
+const EXIT_CODE_NO_RESULT:i32 = 2;
+// The rom could not be loaded, separated from failed so broken test setups are not reported as failed tests
+const EXIT_CODE_LOAD_ERROR:i32 = 3;
+
+struct FrameCaptureGfxDevice{
+    frame:Rc<RefCell<Vec<Pixel>>>
+}
+
+impl GfxDevice for FrameCaptureGfxDevice{
+    fn swap_buffer(&mut self, buffer:&[Pixel; SCREEN_HEIGHT * SCREEN_WIDTH]){
+        self.frame.borrow_mut().copy_from_slice(buffer);
+    }
+}
+
+// Records the bytes sent by the rom, nothing is connected on the other end
+struct SerialOutputDevice{
+    output:Rc<RefCell<Vec<u8>>>
+}
+
+impl SerialDevice for SerialOutputDevice{
+    fn transfer_internal(&mut self, data:u8)->u8{
+        self.output.borrow_mut().push(data);
+        return 0xFF;
+    }
+
+    fn transfer_external(&mut self, _data:u8)->Option<u8>{None}
+}
+
+struct StubAudioDevice;
+impl AudioDevice for StubAudioDevice{
+    fn push_buffer(&mut self, _buffer:&[StereoSample; BUFFER_SIZE]){}
+}
+
+struct StubJoypadProvider;
+impl JoypadProvider for StubJoypadProvider{
+    fn provide(&mut self, _joypad:&mut Joypad){}
+}
+
+// There is no terminal to attach the debugger to, the rom always runs
+#[cfg(feature = "dbg")]
+struct DetachedDebugger;
+#[cfg(feature = "dbg")]
+impl DebuggerInterface for DetachedDebugger{
+    fn should_stop(&self)->bool{false}
+    fn recv_command(&self)->DebuggerCommand{DebuggerCommand::Continue}
+    fn send_result(&self, _result:DebuggerResult){}
+}
+
+#[cfg(not(feature = "dbg"))]
+type HeadlessGameBoy = GameBoy<'static, StubJoypadProvider, StubAudioDevice, FrameCaptureGfxDevice>;
+#[cfg(feature = "dbg")]
+type HeadlessGameBoy = GameBoy<'static, StubJoypadProvider, StubAudioDevice, FrameCaptureGfxDevice, DetachedDebugger>;
+
+fn main(){
+    let args:Vec<String> = env::args().collect();
+    let program_name = args.get(1).expect("Usage: magenboy_headless [path_to_rom] [other_optional_flags]").clone();
+    let frames_count = if check_for_terminal_feature_flag(&args, "--frames"){
+        let value = get_terminal_feature_flag_value(&args, "--frames", "Error! you must specify a value for the --frames parameter");
+        value.parse().expect(format!("Error! invalid frames count: {}", value).as_str())
+    }
+    else{
+        DEFAULT_FRAMES_COUNT
+    };
+    let screenshot_path = check_for_terminal_feature_flag(&args, "--screenshot")
+        .then(|| get_terminal_feature_flag_value(&args, "--screenshot", "Error! you must specify a value for the --screenshot parameter"));
+
+    // Save files are ignored so every run starts from the same state
+    let mbc = match read_program(&program_name).and_then(|program| Ok(initialize_mbc(&program, None)?)){
+        Ok(mbc) => mbc,
+        Err(err) => {
+            eprintln!("Error loading {}: {}", program_name, err);
+            std::process::exit(EXIT_CODE_LOAD_ERROR);
+        }
+    };
+    let mode = get_mode(&args, mbc);
+
+    let frame = Rc::new(RefCell::new(vec![0; SCREEN_HEIGHT * SCREEN_WIDTH]));
+    let serial_output = Rc::new(RefCell::new(Vec::new()));
+    let mut gameboy = GameBoy::new_with_mode(mbc, StubJoypadProvider, StubAudioDevice, FrameCaptureGfxDevice{frame: frame.clone()}, mode, #[cfg(feature = "dbg")] DetachedDebugger);
+    // The device is leaked since the gameboy holds it for the rest of the program
+    gameboy.set_serial_device(Box::leak(Box::new(SerialOutputDevice{output: serial_output.clone()})));
+
+    let result = run_until_result(&mut gameboy, frames_count, &serial_output);
+
+    if let Some(path) = screenshot_path{
+        let pixels:Vec<[u8;3]> = frame.borrow().iter().map(|pixel| rgb565_to_rgb888(*pixel)).collect();
+        if let Err(err) = write_image_file(Path::new(&path), SCREEN_WIDTH, SCREEN_HEIGHT, &pixels){
+            eprintln!("Error writing the screenshot to {}: {}", path, err);
+        }
+    }
+
+    let exit_code = match result{
+        Some(TestResult::Passed(message)) => {
+            println!("Passed: {}", message);
+            EXIT_CODE_PASSED
+        }
+        Some(TestResult::Failed(message)) => {
+            println!("Failed: {}", message);
+            EXIT_CODE_FAILED
+        }
+        None => {
+            println!("No result after {} frames", frames_count);
+            let serial_output = serial_output.borrow();
+            if !serial_output.is_empty(){
+                println!("Serial output:\n{}", String::from_utf8_lossy(&serial_output));
+            }
+            EXIT_CODE_NO_RESULT
+        }
+    };
+    std::process::exit(exit_code);
+}
+
+fn run_until_result(gameboy:&mut HeadlessGameBoy, frames_count:u32, serial_output:&RefCell<Vec<u8>>)->Option<TestResult>{
+    for _ in 0..frames_count{
+        gameboy.poll_joypad_state();
+        for _ in 0..MAX_STEPS_PER_FRAME{
+            // Checking the opcode before executing it since the PC moves past it
+            let pc = gameboy.cpu().program_counter;
+            let breakpoint = !gameboy.cpu().halt && gameboy.peek_memory(pc) == MOONEYE_BREAKPOINT_OPCODE;
+            let (frame_done, _) = gameboy.cycle_step();
+            if breakpoint{
+                if let Some(result) = check_mooneye_registers(gameboy.cpu()){
+                    return Some(result);
+                }
+            }
+            if gameboy.cpu().locked{
+                return Some(TestResult::Failed(std::format!("the CPU locked up at {:#X}", gameboy.cpu().program_counter)));
+            }
+            if frame_done{
+                break;
+            }
+        }
+        let result = check_blargg_serial_output(&serial_output.borrow())
+            .or_else(|| check_blargg_memory(|address| gameboy.peek_memory(address)));
+        if result.is_some(){
+            return result;
+        }
+    }
+    return None;
+}
+
+fn rgb565_to_rgb888(pixel:Pixel)->[u8;3]{
+    let red = ((pixel >> 11) & 0x1F) as u8;
+    let green = ((pixel >> 5) & 0x3F) as u8;
+    let blue = (pixel & 0x1F) as u8;
+    // Repeating the high bits in the new low bits so white stays white
+    return [(red << 3) | (red >> 2), (green << 2) | (green >> 4), (blue << 3) | (blue >> 2)];
+}
@@ -0,0 +1,113 @@
+use magenboy_core::cpu::gb_cpu::GbCpu;
+
+// Mooneye tests execute LD B,B once done with the fibonacci numbers in the registers on success or 0x42 on failure
+pub const MOONEYE_BREAKPOINT_OPCODE:u8 = 0x40;
+const MOONEYE_PASS_SIGNATURE:[u8;6] = [3, 5, 8, 13, 21, 34];
+const MOONEYE_FAIL_VALUE:u8 = 0x42;
+
+// Blargg tests report to the cartridge ram as well as the serial port, the signature marks the report as valid
+const BLARGG_STATUS_ADDRESS:u16 = 0xA000;
+const BLARGG_SIGNATURE_ADDRESS:u16 = 0xA001;
+const BLARGG_SIGNATURE:[u8;3] = [0xDE, 0xB0, 0x61];
+const BLARGG_RUNNING_STATUS:u8 = 0x80;
+const BLARGG_TEXT_ADDRESS:u16 = 0xA004;
+// The text is null terminated, this is just a guard against garbage
+const BLARGG_TEXT_MAX_SIZE:u16 = 0x1000;
+
+#[derive(Debug, PartialEq)]
+pub enum TestResult{
+    Passed(String),
+    Failed(String)
+}
+
+/// Should be called after the CPU executed LD B,B, returns None for other uses of the breakpoint
+pub fn check_mooneye_registers(cpu:&GbCpu)->Option<TestResult>{
+    let [b, c] = cpu.bc.value().to_be_bytes();
+    let [d, e] = cpu.de.value().to_be_bytes();
+    let [h, l] = cpu.hl.value().to_be_bytes();
+    let registers = [b, c, d, e, h, l];
+    if registers == MOONEYE_PASS_SIGNATURE{
+        return Some(TestResult::Passed(String::from("mooneye pass signature")));
+    }
+    if registers.iter().all(|r| *r == MOONEYE_FAIL_VALUE){
+        return Some(TestResult::Failed(String::from("mooneye fail signature")));
+    }
+    return None;
+}
+
+/// Looks for the final "Passed" or "Failed" message Blargg tests print
+pub fn check_blargg_serial_output(output:&[u8])->Option<TestResult>{
+    let text = String::from_utf8_lossy(output);
+    if text.contains("Passed"){
+        return Some(TestResult::Passed(text.trim().to_string()));
+    }
+    if text.contains("Failed"){
+        return Some(TestResult::Failed(text.trim().to_string()));
+    }
+    return None;
+}
+
+/// The status at $A000 is 0x80 while running and the result code once done (0 is a pass)
+pub fn check_blargg_memory(mut read:impl FnMut(u16)->u8)->Option<TestResult>{
+    let signature_valid = (0..BLARGG_SIGNATURE.len() as u16).all(|i| read(BLARGG_SIGNATURE_ADDRESS + i) == BLARGG_SIGNATURE[i as usize]);
+    let status = read(BLARGG_STATUS_ADDRESS);
+    if !signature_valid || status == BLARGG_RUNNING_STATUS{
+        return None;
+    }
+    let text:Vec<u8> = (BLARGG_TEXT_ADDRESS..BLARGG_TEXT_ADDRESS + BLARGG_TEXT_MAX_SIZE)
+        .map(|address| read(address))
+        .take_while(|c| *c != 0)
+        .collect();
+    let text = String::from_utf8_lossy(&text).trim().to_string();
+    return match status{
+        0 => Some(TestResult::Passed(text)),
+        _ => Some(TestResult::Failed(std::format!("result code {}: {}", status, text)))
+    };
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn mooneye_signatures(){
+        let mut cpu = GbCpu::default();
+        *cpu.bc.value_mut() = 0x0305;
+        *cpu.de.value_mut() = 0x080D;
+        *cpu.hl.value_mut() = 0x1522;
+        assert!(matches!(check_mooneye_registers(&cpu), Some(TestResult::Passed(_))));
+
+        *cpu.bc.value_mut() = 0x4242;
+        *cpu.de.value_mut() = 0x4242;
+        *cpu.hl.value_mut() = 0x4242;
+        assert!(matches!(check_mooneye_registers(&cpu), Some(TestResult::Failed(_))));
+
+        *cpu.hl.value_mut() = 0;
+        assert_eq!(check_mooneye_registers(&cpu), None);
+    }
+
+    #[test]
+    fn blargg_serial_output(){
+        assert_eq!(check_blargg_serial_output(b"cpu_instrs\n\n01:ok  02:ok"), None);
+        assert_eq!(check_blargg_serial_output(b"01:ok\n\nPassed all tests\n"), Some(TestResult::Passed(String::from("01:ok\n\nPassed all tests"))));
+        assert!(matches!(check_blargg_serial_output(b"01:01\n\nFailed 1 tests\n"), Some(TestResult::Failed(_))));
+    }
+
+    #[test]
+    fn blargg_memory_report(){
+        let mut memory = vec![0;0x1_0000];
+        memory[0xA000] = BLARGG_RUNNING_STATUS;
+        memory[0xA004..0xA00B].copy_from_slice(b"Passed\n");
+        // No signature yet
+        assert_eq!(check_blargg_memory(|address| memory[address as usize]), None);
+
+        memory[0xA001..0xA004].copy_from_slice(&BLARGG_SIGNATURE);
+        assert_eq!(check_blargg_memory(|address| memory[address as usize]), None);
+
+        memory[0xA000] = 0;
+        assert_eq!(check_blargg_memory(|address| memory[address as usize]), Some(TestResult::Passed(String::from("Passed"))));
+
+        memory[0xA000] = 2;
+        assert!(matches!(check_blargg_memory(|address| memory[address as usize]), Some(TestResult::Failed(_))));
+    }
+}
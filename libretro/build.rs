@@ -28,7 +28,9 @@ license = "GPLv3"
 display_version = "v{version}"
 manufacturer = "Nintendo"
 systemname = "Game Boy / Color"
-description = "Cross platform Game Boy and Game Boy Color emulator"##);
+description = "Cross platform Game Boy and Game Boy Color emulator"
+savestate = "true"
+savestate_features = "deterministic""##);
 
     std::fs::write(info_filename, content).unwrap();
 }
\ No newline at end of file
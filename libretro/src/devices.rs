@@ -3,7 +3,7 @@ use std::{ffi::c_void, mem::size_of};
 use libretro_sys::*;
 
 use magenboy_common::audio::*;
-use magenboy_core::{apu::audio_device::*, keypad::{button::*, joypad::*, joypad_provider::*}, ppu::{gb_ppu::*, gfx_device::*}, GB_FREQUENCY};
+use magenboy_core::{apu::audio_device::*, keypad::{button::*, joypad::*, joypad_provider::*}, ppu::{gb_ppu::*, gfx_device::*}, GB_FREQUENCY, RumbleDevice, TiltProvider};
 
 use super::RETRO_CORE_CTX;
 
@@ -14,6 +14,37 @@ impl GfxDevice for RetroGfxDevice{
     }
 }
 
+pub struct RetroRumbleDevice{
+    interface: RumbleInterface
+}
+
+impl RetroRumbleDevice{
+    pub fn new(interface: RumbleInterface)->Self{Self{interface}}
+}
+
+impl RumbleDevice for RetroRumbleDevice{
+    fn set_rumble(&mut self, enabled:bool) {
+        let strength = if enabled {u16::MAX} else {0};
+        unsafe{
+            (self.interface.set_rumble_state)(0, RumbleEffect::Strong, strength);
+            (self.interface.set_rumble_state)(0, RumbleEffect::Weak, strength);
+        }
+    }
+}
+
+// Using the left analog stick as the accelerometer
+pub struct RetroTiltProvider;
+impl TiltProvider for RetroTiltProvider{
+    fn provide(&mut self)->(f32, f32) {
+        unsafe{
+            let input_cb = RETRO_CORE_CTX.input_cb.unwrap();
+            let x = input_cb(0, DEVICE_ANALOG, DEVICE_INDEX_ANALOG_LEFT, DEVICE_ID_ANALOG_X) as f32 / i16::MAX as f32;
+            let y = input_cb(0, DEVICE_ANALOG, DEVICE_INDEX_ANALOG_LEFT, DEVICE_ID_ANALOG_Y) as f32 / i16::MAX as f32;
+            return (x, y);
+        }
+    }
+}
+
 pub struct RetroJoypadProvider;
 impl JoypadProvider for RetroJoypadProvider{
     fn provide(&mut self, joypad:&mut Joypad) {
@@ -1,17 +1,22 @@
 mod devices;
 mod logging;
 
-use std::{ffi::{c_char, c_uint, c_void}, mem::MaybeUninit, ptr::null_mut, slice};
+use std::{ffi::{c_char, c_uint, c_void, CStr}, mem::MaybeUninit, ptr::null_mut, slice};
 
 use libretro_sys::*;
 
-use magenboy_core::{machine::{gameboy::GameBoy, mbc_initializer}, ppu::gb_ppu::*};
+use magenboy_core::{machine::{gameboy::GameBoy, mbc_initializer}, ppu::gb_ppu::*, Cheat};
 
 use crate::{devices::*, logging::*};
 
 pub struct MagenBoyRetroCore<'a>{
     gameboy: Option<GameBoy<'a, RetroJoypadProvider,  RetroAudioDevice, RetroGfxDevice>>,
     save_data_fat_ptr: Option<(*mut u8, usize)>,
+    // The state size is fixed for the loaded game, calculating it once so the frontend will always get the same size (required for run-ahead)
+    save_state_size: usize,
+    // The cartridge holds references to the devices so they must outlive the gameboy, they are dropped after it on unload
+    rumble_device: Option<RetroRumbleDevice>,
+    tilt_provider: RetroTiltProvider,
     video_cb: Option<VideoRefreshFn>,
     audio_cb: Option<AudioSampleBatchFn>,
     input_poll_cb: Option<InputPollFn>,
@@ -19,7 +24,7 @@ pub struct MagenBoyRetroCore<'a>{
     environment_cb: Option<EnvironmentFn>
 }
 pub(crate) static mut RETRO_CORE_CTX: MagenBoyRetroCore = MagenBoyRetroCore{
-    gameboy: None, save_data_fat_ptr: None, video_cb: None, audio_cb: None, input_poll_cb: None, input_cb: None, environment_cb: None,
+    gameboy: None, save_data_fat_ptr: None, save_state_size: 0, rumble_device: None, tilt_provider: RetroTiltProvider, video_cb: None, audio_cb: None, input_poll_cb: None, input_cb: None, environment_cb: None,
 };
 
 #[no_mangle]
@@ -81,13 +86,28 @@ pub unsafe extern "C" fn retro_init(){
 
 #[no_mangle]
 pub unsafe extern "C" fn retro_load_game(game_info: *const GameInfo)->bool{
+    // In case the frontend did not unload the previous game
+    retro_unload_game();
     let rom_buffer = slice::from_raw_parts::<u8>((*game_info).data as *const u8, (*game_info).size);
-    let mbc = mbc_initializer::initialize_mbc(rom_buffer, None);
+    let mbc = match mbc_initializer::initialize_mbc(rom_buffer, None){
+        Ok(mbc) => mbc,
+        Err(err) => {
+            log::error!("Failed to load the rom: {}", err);
+            return false;
+        }
+    };
+    let mut rumble_interface = MaybeUninit::<RumbleInterface>::uninit();
+    if (RETRO_CORE_CTX.environment_cb.unwrap())(ENVIRONMENT_GET_RUMBLE_INTERFACE, rumble_interface.as_mut_ptr() as *mut c_void){
+        RETRO_CORE_CTX.rumble_device = Some(RetroRumbleDevice::new(rumble_interface.assume_init()));
+        mbc.set_rumble_device(RETRO_CORE_CTX.rumble_device.as_mut().unwrap());
+    }
+    mbc.set_tilt_provider(&mut RETRO_CORE_CTX.tilt_provider);
     if mbc.has_battery(){
         RETRO_CORE_CTX.save_data_fat_ptr = Some((mbc.get_ram().as_mut_ptr(), mbc.get_ram().len()));
     }
     let mode = mbc.detect_preferred_mode();
     RETRO_CORE_CTX.gameboy = Some(GameBoy::new_with_mode(mbc, RetroJoypadProvider, RetroAudioDevice::default(), RetroGfxDevice, mode));
+    RETRO_CORE_CTX.save_state_size = RETRO_CORE_CTX.gameboy.as_ref().unwrap().save_state_size();
     
     let mut pixel_format = PixelFormat::RGB565.to_uint();
     if !(RETRO_CORE_CTX.environment_cb.unwrap())(ENVIRONMENT_SET_PIXEL_FORMAT, &mut pixel_format as *mut u32 as *mut c_void){
@@ -127,14 +147,70 @@ pub unsafe extern "C" fn retro_run(){
     RetroAudioDevice::push_audio_buffer_to_libretro();
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn retro_serialize_size()->isize{RETRO_CORE_CTX.save_state_size as isize}
+
+#[no_mangle]
+pub unsafe extern "C" fn retro_serialize(data:*mut c_void, size:isize)->bool{
+    let Some(gameboy) = RETRO_CORE_CTX.gameboy.as_ref() else {return false};
+    let buffer = slice::from_raw_parts_mut(data as *mut u8, size as usize);
+    return match gameboy.save_state(buffer){
+        Ok(_) => true,
+        Err(err) => {
+            log::error!("Failed to save state: {:?}", err);
+            false
+        }
+    };
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn retro_unserialize(data:*const c_void, size:isize)->bool{
+    let Some(gameboy) = RETRO_CORE_CTX.gameboy.as_mut() else {return false};
+    let buffer = slice::from_raw_parts(data as *const u8, size as usize);
+    return match gameboy.load_state(buffer){
+        Ok(_) => true,
+        Err(err) => {
+            log::error!("Failed to load state: {:?}", err);
+            false
+        }
+    };
+}
+
 #[no_mangle] pub extern "C" fn retro_load_game_special(_:c_uint, _:*const GameInfo, _:isize)->bool{false}
-#[no_mangle] pub extern "C" fn retro_serialize_size()->isize{0}
-#[no_mangle] pub extern "C" fn retro_serialize(_:*mut c_void, _:isize)->bool{false}
-#[no_mangle] pub extern "C" fn retro_unserialize(_:*const c_void, _:isize)->bool{false}
 #[no_mangle] pub extern "C" fn retro_deinit(){}
-#[no_mangle] pub extern "C" fn retro_unload_game(){}
+#[no_mangle]
+pub unsafe extern "C" fn retro_unload_game(){
+    // Dropping the gameboy first since it references the devices
+    RETRO_CORE_CTX.gameboy = None;
+    RETRO_CORE_CTX.rumble_device = None;
+    RETRO_CORE_CTX.save_data_fat_ptr = None;
+    RETRO_CORE_CTX.save_state_size = 0;
+}
 #[no_mangle] pub extern "C" fn retro_reset(){}
 #[no_mangle] pub extern "C" fn retro_set_audio_sample(_: AudioSampleFn){}
 #[no_mangle] pub extern "C" fn retro_set_controller_port_device(_: c_uint, _:c_uint){}
-#[no_mangle] pub extern "C" fn retro_cheat_reset(){}
-#[no_mangle] pub extern "C" fn retro_cheat_set(_: c_uint, _:bool, _:*const c_char){}
\ No newline at end of file
+#[no_mangle]
+pub unsafe extern "C" fn retro_cheat_reset(){
+    if let Some(gameboy) = RETRO_CORE_CTX.gameboy.as_mut(){
+        gameboy.clear_cheats();
+    }
+}
+
+// The frontend resets the cheats and sets all of them again on every change
+#[no_mangle]
+pub unsafe extern "C" fn retro_cheat_set(_index: c_uint, enabled:bool, code:*const c_char){
+    let Some(gameboy) = RETRO_CORE_CTX.gameboy.as_mut() else {return};
+    if !enabled || code.is_null(){
+        return;
+    }
+    let codes = CStr::from_ptr(code).to_string_lossy();
+    // A single cheat can contain several codes
+    for code in codes.split(['+', ';', ' ']).filter(|code|!code.is_empty()){
+        match Cheat::try_from(code){
+            Ok(cheat) => if !gameboy.add_cheat(cheat){
+                log::warn!("Too many cheats, ignoring: {}", code);
+            },
+            Err(_) => log::warn!("Invalid cheat code: {}", code)
+        }
+    }
+}
\ No newline at end of file
@@ -45,7 +45,13 @@ pub unsafe extern "C" fn magenboy_init(rom: *const c_char, rom_size: c_ulonglong
     poll_joypad_cb: PollJoypadProviderCallback, audio_cb:AudioDeviceCallback) -> *mut c_void {
 
     let rom:&[u8] = unsafe{ core::slice::from_raw_parts(rom as *const u8, rom_size as usize) };
-    let mbc = machine::mbc_initializer::initialize_mbc(&rom, None);
+    let mbc = match machine::mbc_initializer::initialize_mbc(&rom, None){
+        Ok(mbc) => mbc,
+        Err(err) => {
+            log::error!("Failed to load the rom: {}", err);
+            return core::ptr::null_mut();
+        }
+    };
 
     let mode = mbc.detect_preferred_mode();
     log::info!("Detected mode: {}", <Mode as Into<&str>>::into(mode));
@@ -21,7 +21,7 @@ void magenboy_init_logger(LogCallback log_cb);
 // Initialize the GameBoy instance.
 //   rom: pointer to ROM data
 //   rom_size: size of ROM data in bytes
-// Returns: a pointer to the statically allocated GameBoy instance.
+// Returns: a pointer to the statically allocated GameBoy instance, or NULL if the ROM is invalid.
 void* magenboy_init(const uint8_t* rom, uint64_t rom_size, GfxDeviceCallback gfx_cb, JoypadDeviceCallback joypad_cb, PollJoypadDeviceCallback poll_cb,
     AudioDeviceCallback audio_cb);
 
@@ -351,6 +351,12 @@ restart:
     int found_sram = try_load_sram(filepath, &found_sram_buffer, &found_sram_size);
 
     void* ctx = magenboy_init(rom_buffer, file_size, render_buffer_cb, get_joycon_state, poll_until_joycon_pressed, audio_device_cb);
+    if (ctx == NULL) {
+        printf("Failed to load ROM: %s\n", filepath);
+        free(rom_buffer);
+        free(found_sram_buffer);
+        goto restart;
+    }
 
     u8* sram_buffer = NULL;
     size_t sram_size = 0;
@@ -60,7 +60,7 @@ pub extern "C" fn main()->!{
     let rom = unsafe{&mut ROM_BUFFER};
     fs.read_file(selected_rom, rom);
     let save_data = try_read_save_file(selected_rom, &mut fs);
-    let mbc = initialize_mbc(&rom[0..selected_rom.size as usize], save_data);
+    let mbc = initialize_mbc(&rom[0..selected_rom.size as usize], save_data).unwrap_or_else(|err|core::panic!("Failed to load the rom: {}", err));
     let mode = mbc.detect_preferred_mode();
 
     let mut gameboy = GameBoy::new_with_mode(mbc, joypad_provider, magenboy_rpi::BlankAudioDevice, gfx, mode);
@@ -83,6 +83,8 @@ pub extern "C" fn main()->!{
                     log::info!("Shuting down system");
                     reset_system(mbc, fs, power_manager, ResetMode::Halt, selected_rom);
                 }
+                // Not offered in this menu
+                EmulatorMenuOption::ToggleCheats => {}
             }
         }
         gameboy.cycle_frame();
@@ -1,6 +1,6 @@
 use std::env;
 
-use magenboy_common::{check_for_terminal_feature_flag, get_terminal_feature_flag_value, init_and_run_gameboy, joypad_menu::*, menu::*, mpmc_gfx_device::MpmcGfxDevice, EMULATOR_STATE};
+use magenboy_common::{check_for_terminal_feature_flag, get_terminal_feature_flag_value, init_and_run_gameboy, joypad_menu::*, menu::*, mpmc_gfx_device::MpmcGfxDevice, mbc_handler::RomLoadError, EMULATOR_STATE};
 use magenboy_core::{ppu::{gb_ppu::{BUFFERS_NUMBER, SCREEN_WIDTH, SCREEN_HEIGHT}, gfx_device::{GfxDevice, Pixel}}, keypad::joypad_provider::JoypadProvider};
 use magenboy_rpi::{configuration::{display::*, emulation::*, joypad::*}, drivers::*, peripherals::PERIPHERALS, BlankAudioDevice, MENU_PIN_BCM};
 
@@ -54,7 +54,13 @@ fn main(){
 
             drop(r);
             EMULATOR_STATE.running.store(false, std::sync::atomic::Ordering::Relaxed);
-            emualation_thread.join().unwrap();
+            if let Err(err) = emualation_thread.join().unwrap(){
+                log::error!("Failed to load the rom: {}", err);
+                // Without the rom menu the same rom would be loaded again
+                if !check_for_terminal_feature_flag(&args, "--rom-menu"){
+                    EMULATOR_STATE.exit.store(true, std::sync::atomic::Ordering::Relaxed);
+                }
+            }
         }
     }
 
@@ -64,8 +70,8 @@ fn main(){
     }
 }
 
-fn emulation_thread_main(args: Vec<String>, program_name: String, spsc_gfx_device: MpmcGfxDevice, joypad_provider:impl JoypadProvider) {
-    init_and_run_gameboy(args, program_name, spsc_gfx_device, joypad_provider, BlankAudioDevice);
+fn emulation_thread_main(args: Vec<String>, program_name: String, spsc_gfx_device: MpmcGfxDevice, joypad_provider:impl JoypadProvider)->Result<(), RomLoadError>{
+    return init_and_run_gameboy(args, program_name, spsc_gfx_device, joypad_provider, BlankAudioDevice, None, None);
 }
 
 extern "C" fn sigint_handler(_:std::os::raw::c_int){
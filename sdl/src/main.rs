@@ -2,16 +2,18 @@ mod audio;
 mod utils;
 mod sdl_gfx_device;
 mod sdl_joypad_provider;
+mod sdl_rumble_device;
+mod sdl_tilt_provider;
 #[cfg(feature = "dbg")]
 mod terminal_debugger;
 
-use magenboy_common::{audio::{ManualAudioResampler, ResampledAudioDevice}, check_for_terminal_feature_flag, get_terminal_feature_flag_value, init_and_run_gameboy, joypad_menu::*, menu::*, mpmc_gfx_device::*, EMULATOR_STATE};
-use magenboy_core::{apu::audio_device::*, keypad::joypad::NUM_OF_KEYS, ppu::{gb_ppu::{BUFFERS_NUMBER, SCREEN_HEIGHT, SCREEN_WIDTH}, gfx_device::{GfxDevice, Pixel}}, GB_FREQUENCY};
+use magenboy_common::{audio::{ManualAudioResampler, ResampledAudioDevice}, check_for_terminal_feature_flag, get_terminal_feature_flag_value, init_and_run_gameboy, init_and_run_linked_gameboys, joypad_menu::*, menu::*, mpmc_gfx_device::*, mbc_handler::{read_cartridge_header, RomLoadError}, EMULATOR_STATE};
+use magenboy_core::{apu::audio_device::*, keypad::joypad::NUM_OF_KEYS, ppu::{gb_ppu::{BUFFERS_NUMBER, SCREEN_HEIGHT, SCREEN_WIDTH}, gfx_device::{GfxDevice, Pixel}}, GB_FREQUENCY, SGB_SCREEN_HEIGHT, SGB_SCREEN_WIDTH};
 
 use std::{env, result::Result, vec::Vec};
 use sdl2::sys::*;
 
-use crate::{sdl_gfx_device::SdlGfxDevice, audio::*, SdlAudioDevice};
+use crate::{sdl_gfx_device::SdlGfxDevice, sdl_rumble_device::SdlRumbleDevice, sdl_tilt_provider::SdlTiltProvider, audio::*, SdlAudioDevice};
 
 const TURBO_MUL:u8 = 1;
 
@@ -27,6 +29,19 @@ const KEYBOARD_MAPPING:[SDL_Scancode; NUM_OF_KEYS] = [
     SDL_Scancode::SDL_SCANCODE_RIGHT,
     SDL_Scancode::SDL_SCANCODE_LEFT
 ];
+// The second player keys in link mode
+const SECOND_KEYBOARD_MAPPING:[SDL_Scancode; NUM_OF_KEYS] = [
+    SDL_Scancode::SDL_SCANCODE_KP_1,
+    SDL_Scancode::SDL_SCANCODE_KP_0,
+    SDL_Scancode::SDL_SCANCODE_KP_ENTER,
+    SDL_Scancode::SDL_SCANCODE_KP_PLUS,
+    SDL_Scancode::SDL_SCANCODE_KP_8,
+    SDL_Scancode::SDL_SCANCODE_KP_5,
+    SDL_Scancode::SDL_SCANCODE_KP_6,
+    SDL_Scancode::SDL_SCANCODE_KP_4
+];
+// Hold to rewind, requires the --rewind flag
+const REWIND_KEY:SDL_Scancode = SDL_Scancode::SDL_SCANCODE_BACKSPACE;
 
 fn main() {
     let header = std::format!("MagenBoy v{}", magenboy_common::VERSION);
@@ -37,9 +52,20 @@ fn main() {
         Result::Err(error)=>std::panic!("error initing logger: {}", error)
     }
 
+    // Runs a second gameboy connected with a link cable and displays both screens side by side
+    let linked_program_name = check_for_terminal_feature_flag(&args, "--link-local")
+        .then(|| get_terminal_feature_flag_value(&args, "--link-local", "Error! you must specify a rom for the second gameboy"));
+    let screens_count = if linked_program_name.is_some() {2} else {1};
+    // The border is not supported when displaying linked gameboys
+    let sgb_border = check_for_terminal_feature_flag(&args, "--sgb-border") && linked_program_name.is_none();
+
     // Initialize the gfx first cause it initialize both the screen and the sdl context for the joypad
     let mut gfx_device: SdlGfxDevice = SdlGfxDevice::new(header.as_str(), SCREEN_SCALE, TURBO_MUL,
-    check_for_terminal_feature_flag(&args, "--no-vsync"), check_for_terminal_feature_flag(&args, "--full-screen"));
+    check_for_terminal_feature_flag(&args, "--no-vsync"), check_for_terminal_feature_flag(&args, "--full-screen"), screens_count, sgb_border);
+
+    // Created once since each of them opens the game controller
+    let mut rumble_device = SdlRumbleDevice::new();
+    let mut tilt_provider = SdlTiltProvider::new();
 
     while !(EMULATOR_STATE.exit.load(std::sync::atomic::Ordering::Relaxed)){
         let mut provider = sdl_joypad_provider::SdlJoypadProvider::new(KEYBOARD_MAPPING, true);
@@ -53,19 +79,37 @@ fn main() {
             args[1].clone()
         };
 
+        match read_cartridge_header(&program_name).map(|header| String::from(header.title())).filter(|title| !title.is_empty()){
+            Option::Some(title) => gfx_device.set_window_title(std::format!("{} - {}", header, title).as_str()),
+            Option::None => gfx_device.set_window_title(header.as_str())
+        }
+
         let mut emulation_menu = MagenBoyMenu::new(provider, header.clone());
 
         let (s,r) = crossbeam_channel::bounded(BUFFERS_NUMBER - 1);
-        let mpmc_device = MpmcGfxDevice::new(s);
+        let mpmc_device = if sgb_border {MpmcGfxDevice::with_sgb_border(s)} else {MpmcGfxDevice::new(s)};
+        // The second screen frames are pointers to the PPU buffers as well so the channel is bounded the same way.
+        // Without a linked gameboy nothing is sent, never() keeps the render loop from stopping on the closed channel
+        let (second_s, second_r) = crossbeam_channel::bounded(BUFFERS_NUMBER - 1);
+        let second_r = if linked_program_name.is_some() {second_r} else {crossbeam_channel::never()};
+        let second_mpmc_device = MpmcGfxDevice::new(second_s);
 
         #[cfg(feature = "dbg")]
         let (debugger_ppu_layer_sender, debugger_ppu_layer_receiver) = crossbeam_channel::bounded::<terminal_debugger::PpuLayerResult>(0);
 
+        // The cartridge requires static devices, this is ok since the emulation thread is joined before they are used again
+        let rumble_device:&'static mut SdlRumbleDevice = unsafe{&mut *(&mut rumble_device as *mut SdlRumbleDevice)};
+        let tilt_provider:&'static mut SdlTiltProvider = unsafe{&mut *(&mut tilt_provider as *mut SdlTiltProvider)};
+
         let args_clone = args.clone();
+        let linked_program_name_clone = linked_program_name.clone();
         let emualation_thread = std::thread::Builder::new()
             .name("Emualtion Thread".to_string())
             .stack_size(0x100_0000)
-            .spawn(move || emulation_thread_main(args_clone, program_name, mpmc_device, #[cfg(feature = "dbg")]debugger_ppu_layer_sender))
+            .spawn(move || match linked_program_name_clone{
+                Option::Some(linked_program_name) => linked_emulation_thread_main(args_clone, [program_name, linked_program_name], [mpmc_device, second_mpmc_device], #[cfg(feature = "dbg")]debugger_ppu_layer_sender),
+                Option::None => emulation_thread_main(args_clone, program_name, mpmc_device, rumble_device, tilt_provider, #[cfg(feature = "dbg")]debugger_ppu_layer_sender)
+            })
             .unwrap();
 
         unsafe{
@@ -78,13 +122,20 @@ fn main() {
                     else if event.type_ == SDL_EventType::SDL_KEYDOWN as u32 && event.key.keysym.scancode == SDL_Scancode::SDL_SCANCODE_ESCAPE{
                         emulation_menu.pop_game_menu(&EMULATOR_STATE, &mut gfx_device, r.clone());
                     }
+                    else if (event.type_ == SDL_EventType::SDL_KEYDOWN as u32 || event.type_ == SDL_EventType::SDL_KEYUP as u32) && event.key.keysym.scancode == REWIND_KEY{
+                        EMULATOR_STATE.rewind.store(event.type_ == SDL_EventType::SDL_KEYDOWN as u32, std::sync::atomic::Ordering::Relaxed);
+                    }
                 }
 
                 cfg_if::cfg_if! {if #[cfg(feature = "dbg")] {
                     crossbeam_channel::select! {
                         recv(r) -> msg => {
                             let Ok(buffer) = msg else {break};
-                            gfx_device.swap_buffer(&*(buffer as *const [Pixel; SCREEN_WIDTH * SCREEN_HEIGHT]));
+                            swap_emulation_buffer(&mut gfx_device, buffer);
+                        },
+                        recv(second_r) -> msg => {
+                            let Ok(buffer) = msg else {break};
+                            update_second_screen(&mut gfx_device, buffer);
                         },
                         recv(debugger_ppu_layer_receiver)-> msg => {
                             let Ok(result) = msg else {break};
@@ -93,24 +144,41 @@ fn main() {
                         }
                     }
                 }else{
-                    let Ok(buffer) = r.recv() else {break};
-                    gfx_device.swap_buffer(&*(buffer as *const [Pixel; SCREEN_WIDTH * SCREEN_HEIGHT]));
+                    crossbeam_channel::select! {
+                        recv(r) -> msg => {
+                            let Ok(buffer) = msg else {break};
+                            swap_emulation_buffer(&mut gfx_device, buffer);
+                        },
+                        recv(second_r) -> msg => {
+                            let Ok(buffer) = msg else {break};
+                            update_second_screen(&mut gfx_device, buffer);
+                        }
+                    }
                 }}
             }
 
             drop(r);
             EMULATOR_STATE.running.store(false, std::sync::atomic::Ordering::Relaxed);
-            emualation_thread.join().unwrap();
+            if let Err(err) = emualation_thread.join().unwrap(){
+                log::error!("Failed to load the rom: {}", err);
+                gfx_device.show_error_message("MagenBoy", std::format!("Failed to load the rom: {}", err).as_str());
+                // Without the rom menu the same rom would be loaded again
+                if !check_for_terminal_feature_flag(&args, "--rom-menu"){
+                    EMULATOR_STATE.exit.store(true, std::sync::atomic::Ordering::Relaxed);
+                }
+            }
         }
     }
 
+    drop(rumble_device);
+    drop(tilt_provider);
     drop(gfx_device);
 
     unsafe{SDL_Quit();}
 }
 
 // Receiving usize and not raw ptr cause in rust you cant pass a raw ptr to another thread
-fn emulation_thread_main(args: Vec<String>, program_name: String, spsc_gfx_device: MpmcGfxDevice, #[cfg(feature = "dbg")] debugger_sender: crossbeam_channel::Sender<terminal_debugger::PpuLayerResult>) {
+fn emulation_thread_main(args: Vec<String>, program_name: String, spsc_gfx_device: MpmcGfxDevice, rumble_device: &'static mut SdlRumbleDevice, tilt_provider: &'static mut SdlTiltProvider, #[cfg(feature = "dbg")] debugger_sender: crossbeam_channel::Sender<terminal_debugger::PpuLayerResult>)->Result<(), RomLoadError>{
     let mut devices: Vec::<Box::<dyn AudioDevice>> = Vec::new();
     let audio_device = SdlAudioDevice::<ManualAudioResampler>::new(44100, TURBO_MUL);
     devices.push(Box::new(audio_device));
@@ -124,5 +192,35 @@ fn emulation_thread_main(args: Vec<String>, program_name: String, spsc_gfx_devic
     let audio_devices = MultiAudioDevice::new(devices);
     let joypad_provider = sdl_joypad_provider::SdlJoypadProvider::new(KEYBOARD_MAPPING, false);
     
-    init_and_run_gameboy(args, program_name, spsc_gfx_device, joypad_provider, audio_devices, #[cfg(feature = "dbg")] terminal_debugger::TerminalDebugger::new(debugger_sender));
+    return init_and_run_gameboy(args, program_name, spsc_gfx_device, joypad_provider, audio_devices, Some(rumble_device), Some(tilt_provider), #[cfg(feature = "dbg")] terminal_debugger::TerminalDebugger::new(debugger_sender));
+}
+
+// In SGB border mode the emulation thread sends only frames with the border size
+unsafe fn swap_emulation_buffer(gfx_device:&mut SdlGfxDevice, buffer:usize){
+    if gfx_device.sgb_border_supported(){
+        gfx_device.swap_sgb_buffer(&*(buffer as *const [Pixel; SGB_SCREEN_WIDTH * SGB_SCREEN_HEIGHT]));
+    }
+    else{
+        gfx_device.swap_buffer(&*(buffer as *const [Pixel; SCREEN_WIDTH * SCREEN_HEIGHT]));
+    }
+}
+
+// The second screen is presented together with the next frame of the first one
+unsafe fn update_second_screen(gfx_device:&mut SdlGfxDevice, buffer:usize){
+    gfx_device.update_screen(1, &*(buffer as *const [Pixel; SCREEN_WIDTH * SCREEN_HEIGHT]));
+}
+
+fn linked_emulation_thread_main(args: Vec<String>, program_names: [String;2], gfx_devices: [MpmcGfxDevice;2], #[cfg(feature = "dbg")] debugger_sender: crossbeam_channel::Sender<terminal_debugger::PpuLayerResult>)->Result<(), RomLoadError>{
+    // Only the first gameboy is audible
+    let audio_devices = [
+        MultiAudioDevice::new(vec![Box::new(SdlAudioDevice::<ManualAudioResampler>::new(44100, TURBO_MUL))]),
+        MultiAudioDevice::new(Vec::new())
+    ];
+    let joypad_providers = [
+        sdl_joypad_provider::SdlJoypadProvider::new(KEYBOARD_MAPPING, false),
+        sdl_joypad_provider::SdlJoypadProvider::new(SECOND_KEYBOARD_MAPPING, false)
+    ];
+
+    return init_and_run_linked_gameboys(args, program_names, gfx_devices, joypad_providers, audio_devices,
+        #[cfg(feature = "dbg")] (terminal_debugger::TerminalDebugger::new(debugger_sender), terminal_debugger::DetachedDebugger));
 }
\ No newline at end of file
@@ -1,6 +1,6 @@
 use std::ffi::{CString, c_void};
 use sdl2::sys::*;
-use magenboy_core::{ppu::gb_ppu::{SCREEN_HEIGHT, SCREEN_WIDTH}, utils::vec2::Vec2, GfxDevice, Pixel};
+use magenboy_core::{ppu::gb_ppu::{SCREEN_HEIGHT, SCREEN_WIDTH}, utils::vec2::Vec2, GfxDevice, Pixel, SGB_SCREEN_HEIGHT, SGB_SCREEN_WIDTH};
 use super::utils::get_sdl_error_message;
 
 // The bit order is high bits -> low bits as opposed to RGB555 in the gbdev docs which is low -> high.
@@ -77,10 +77,16 @@ pub struct SdlGfxDevice{
     sdl_window:SdlWindow,
     discard:u8,
     turbo_mul:u8,
+    // The screens are rendered side by side (used to display linked gameboys)
+    screens_buffer:Vec<Pixel>,
+    screens_count:usize,
+    window_width:usize,
+    // The window has the SGB border size and screens without a border are drawn at its center
+    sgb_border:bool
 }
 
 impl SdlGfxDevice{
-    pub fn new(window_name:&str, screen_scale: usize, turbo_mul:u8, disable_vsync:bool, full_screen:bool)->Self{
+    pub fn new(window_name:&str, screen_scale: usize, turbo_mul:u8, disable_vsync:bool, full_screen:bool, screens_count:usize, sgb_border:bool)->Self{
         
         let window_flags = if full_screen{                
             // Hide cursor
@@ -91,7 +97,35 @@ impl SdlGfxDevice{
             SDL_WindowFlags::SDL_WINDOW_RESIZABLE as u32
         };
         
-        return Self{discard:0, turbo_mul, sdl_window: SdlWindow::new(window_name, Vec2{x:SCREEN_WIDTH, y:SCREEN_HEIGHT}, screen_scale, disable_vsync, window_flags)};
+        let window_size = if sgb_border {Vec2{x:SGB_SCREEN_WIDTH, y:SGB_SCREEN_HEIGHT}} else {Vec2{x:SCREEN_WIDTH * screens_count, y:SCREEN_HEIGHT}};
+        let sdl_window = SdlWindow::new(window_name, window_size, screen_scale, disable_vsync, window_flags);
+        return Self{discard:0, turbo_mul, sdl_window, screens_buffer: vec![0; window_size.x * window_size.y], screens_count, window_width: window_size.x, sgb_border};
+    }
+
+    /// Updates a screen without rendering, the next call to swap_buffer (which updates the first screen) renders all the screens
+    pub fn update_screen(&mut self, screen_index:usize, buffer:&[Pixel; SCREEN_HEIGHT * SCREEN_WIDTH]){
+        self.copy_screen(buffer, screen_index * SCREEN_WIDTH, 0);
+    }
+
+    fn copy_screen(&mut self, buffer:&[Pixel; SCREEN_HEIGHT * SCREEN_WIDTH], x:usize, y:usize){
+        for (line_index, line) in buffer.chunks_exact(SCREEN_WIDTH).enumerate(){
+            let start = ((y + line_index) * self.window_width) + x;
+            self.screens_buffer[start..start + SCREEN_WIDTH].copy_from_slice(line);
+        }
+    }
+
+    pub fn set_window_title(&mut self, title:&str){
+        let title = CString::new(title).unwrap();
+        unsafe{SDL_SetWindowTitle(self.sdl_window.window, title.as_ptr())};
+    }
+
+    pub fn show_error_message(&self, title:&str, message:&str){
+        let (title, message) = (CString::new(title).unwrap(), CString::new(message).unwrap());
+        unsafe{
+            if SDL_ShowSimpleMessageBox(SDL_MessageBoxFlags::SDL_MESSAGEBOX_ERROR as u32, title.as_ptr(), message.as_ptr(), self.sdl_window.window) != 0{
+                log::error!("Failed to show an error message: {}", get_sdl_error_message());
+            }
+        }
     }
 
     pub fn poll_event(&self)->Option<SDL_Event>{
@@ -109,6 +143,26 @@ impl SdlGfxDevice{
 
 impl GfxDevice for SdlGfxDevice{
     fn swap_buffer(&mut self, buffer:&[Pixel; SCREEN_HEIGHT * SCREEN_WIDTH]) {
+        self.discard = (self.discard + 1) % self.turbo_mul;
+        if self.discard != 0{
+            return;
+        }
+        if self.sgb_border{
+            self.copy_screen(buffer, (SGB_SCREEN_WIDTH - SCREEN_WIDTH) / 2, (SGB_SCREEN_HEIGHT - SCREEN_HEIGHT) / 2);
+            self.sdl_window.render(&self.screens_buffer);
+            return;
+        }
+        if self.screens_count == 1{
+            self.sdl_window.render(buffer);
+            return;
+        }
+        self.update_screen(0, buffer);
+        self.sdl_window.render(&self.screens_buffer);
+    }
+
+    fn sgb_border_supported(&self)->bool {self.sgb_border}
+
+    fn swap_sgb_buffer(&mut self, buffer:&[Pixel; SGB_SCREEN_HEIGHT * SGB_SCREEN_WIDTH]) {
         self.discard = (self.discard + 1) % self.turbo_mul;
         if self.discard != 0{
             return;
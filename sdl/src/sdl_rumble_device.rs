@@ -0,0 +1,43 @@
+use sdl2::sys::*;
+use magenboy_core::RumbleDevice;
+use super::utils::{get_sdl_error_message, open_game_controller, close_game_controller};
+
+// The motor is turned off explicitly, the duration is just a fallback in case it wont
+const RUMBLE_DURATION_MS:u32 = 1000;
+
+pub struct SdlRumbleDevice{
+    controller: *mut SDL_GameController
+}
+
+// SDL locks the joysticks internally so its ok to rumble from the emulation thread
+unsafe impl Send for SdlRumbleDevice{}
+
+impl SdlRumbleDevice{
+    pub fn new()->Self{
+        let controller = open_game_controller();
+        if controller.is_null(){
+            log::info!("No game controller found, rumble is disabled");
+        }
+        return Self{controller};
+    }
+}
+
+impl RumbleDevice for SdlRumbleDevice{
+    fn set_rumble(&mut self, enabled:bool) {
+        if self.controller.is_null(){
+            return;
+        }
+        let strength = if enabled {u16::MAX} else {0};
+        unsafe{
+            if SDL_GameControllerRumble(self.controller, strength, strength, RUMBLE_DURATION_MS) != 0{
+                log::warn!("Failed to rumble the controller: {}", get_sdl_error_message());
+            }
+        }
+    }
+}
+
+impl Drop for SdlRumbleDevice{
+    fn drop(&mut self) {
+        close_game_controller(self.controller);
+    }
+}
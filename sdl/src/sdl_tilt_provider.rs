@@ -0,0 +1,43 @@
+use sdl2::sys::*;
+use magenboy_core::TiltProvider;
+use super::utils::{open_game_controller, close_game_controller};
+
+// Tilting with the left stick of a game controller or with the keyboard as a fallback
+const TILT_UP_KEY:SDL_Scancode = SDL_Scancode::SDL_SCANCODE_I;
+const TILT_DOWN_KEY:SDL_Scancode = SDL_Scancode::SDL_SCANCODE_K;
+const TILT_LEFT_KEY:SDL_Scancode = SDL_Scancode::SDL_SCANCODE_J;
+const TILT_RIGHT_KEY:SDL_Scancode = SDL_Scancode::SDL_SCANCODE_L;
+
+pub struct SdlTiltProvider{
+    controller: *mut SDL_GameController
+}
+
+// SDL locks the joysticks internally and the keyboard state is updated by the main thread
+unsafe impl Send for SdlTiltProvider{}
+
+impl SdlTiltProvider{
+    pub fn new()->Self{
+        Self{controller: open_game_controller()}
+    }
+}
+
+impl TiltProvider for SdlTiltProvider{
+    fn provide(&mut self)->(f32, f32) {
+        unsafe{
+            if !self.controller.is_null(){
+                let x = SDL_GameControllerGetAxis(self.controller, SDL_GameControllerAxis::SDL_CONTROLLER_AXIS_LEFTX) as f32 / i16::MAX as f32;
+                let y = SDL_GameControllerGetAxis(self.controller, SDL_GameControllerAxis::SDL_CONTROLLER_AXIS_LEFTY) as f32 / i16::MAX as f32;
+                return (x, y);
+            }
+            let state = SDL_GetKeyboardState(std::ptr::null_mut());
+            let key_axis = |negative:SDL_Scancode, positive:SDL_Scancode| (*state.add(positive as usize) as f32) - (*state.add(negative as usize) as f32);
+            return (key_axis(TILT_LEFT_KEY, TILT_RIGHT_KEY), key_axis(TILT_UP_KEY, TILT_DOWN_KEY));
+        }
+    }
+}
+
+impl Drop for SdlTiltProvider{
+    fn drop(&mut self) {
+        close_game_controller(self.controller);
+    }
+}
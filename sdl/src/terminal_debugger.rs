@@ -2,7 +2,7 @@ use std::{io::stdin, sync::{atomic::{AtomicBool, Ordering}, Arc}, thread};
 
 use crossbeam_channel::{bounded, Sender, Receiver};
 
-use magenboy_core::{debugger::{DebuggerCommand, DebuggerInterface, DebuggerResult, PpuLayer, PPU_BUFFER_SIZE, Address, WatchMode}, Pixel};
+use magenboy_core::{debugger::{DebuggerCommand, DebuggerInterface, DebuggerResult, PpuLayer, PPU_BUFFER_SIZE, Address, WatchMode, RamSearchFilter}, Pixel};
 
 const HELP_MESSAGE:&'static str = r"Debugger commands:
 - halt(h) - start the debugging session (halt the program execution)
@@ -18,6 +18,7 @@ const HELP_MESSAGE:&'static str = r"Debugger commands:
 - remove_watch(rw) [address:bank] - delete a watch point
 - ppu_info(pi) - print info about the ppu execution state
 - ppu_layer(pl) [layer] - a debug window with one ppu layer (win, bg, spr)
+- ram_search(rs) [start/eq/ch/inc/dec/value] - snapshot WRAM, HRAM and SRAM (every bank) and filter the candidates compared to the last search
 - help - prints this help message
 ";
 
@@ -111,11 +112,25 @@ impl TerminalDebugger{
                 println!("Hit watch point: {address} at address: {pc_address} with value: {value:#X}");
                 enabled.store(true, Ordering::SeqCst);
             },
+            DebuggerResult::HitLockup(address, opcode) => {
+                println!("Illegal opcode {opcode:#X} at address: {address}, the CPU is locked up");
+                enabled.store(true, Ordering::SeqCst);
+            },
             DebuggerResult::RemovedWatch(addr) => println!("Removed watch point {addr}"),
             DebuggerResult::WatchDoNotExist(addr) => println!("Watch point {addr} do not exist"),
             DebuggerResult::PpuInfo(info) => println!("PpuInfo: \nstate: {} \nlcdc: {:#X} \nstat: {:#X} \nly: {} \nbackground [X: {}, Y: {}] \nwindow [X: {}, Y: {}], \nbank: {}",
                 info.ppu_state as u8, info.lcdc, info.stat, info.ly, info.background_pos.x, info.background_pos.y, info.window_pos.x, info.window_pos.y, info.vram_bank),
-            DebuggerResult::PpuLayer(layer, buffer) => ppu_layer_sender.send(PpuLayerResult(buffer, layer)).unwrap()
+            DebuggerResult::PpuLayer(layer, buffer) => ppu_layer_sender.send(PpuLayerResult(buffer, layer)).unwrap(),
+            DebuggerResult::RamSearch(candidates) => {
+                const MAX_PRINTED_CANDIDATES: usize = 50;
+                println!("{} candidates left", candidates.len());
+                for candidate in candidates.iter().take(MAX_PRINTED_CANDIDATES){
+                    println!("{} = {:#04X}", candidate.address, candidate.value);
+                }
+                if candidates.len() > MAX_PRINTED_CANDIDATES{
+                    println!("...");
+                }
+            }
         }
     }
     
@@ -168,6 +183,13 @@ impl TerminalDebugger{
                         Ok(layer) => sender.send(DebuggerCommand::GetPpuLayer(layer)).unwrap(),
                         Err(msg) => println!("Error getting ppu layer: {}", msg),
                     }
+                    "rs"|"ram_search"=>match buffer.get(1){
+                        Some(&"start") => sender.send(DebuggerCommand::StartRamSearch).unwrap(),
+                        _ => match parse_ram_search_filter(&buffer){
+                            Ok(filter) => sender.send(DebuggerCommand::FilterRamSearch(filter)).unwrap(),
+                            Err(msg) => println!("Error filtering ram search: {}", msg),
+                        }
+                    }
                     "skip_halt"=>sender.send(DebuggerCommand::SkipHalt).unwrap(),
                     "help"=>println!("{}", HELP_MESSAGE),
                     _=>println!("invalid input: {}", buffer[0])
@@ -214,6 +236,24 @@ fn parse_ppu_layer(buffer: &Vec<&str>)->Result<PpuLayer, String>{
     };
 }
 
+fn parse_ram_search_filter(buffer: &Vec<&str>)->Result<RamSearchFilter, String>{
+    let Some(param) = buffer.get(1) else {
+        return Result::Err(String::from("No parameter"))
+    };
+
+    return match *param{
+        "eq" => Ok(RamSearchFilter::Equal),
+        "ch" => Ok(RamSearchFilter::Changed),
+        "inc" => Ok(RamSearchFilter::Increased),
+        "dec" => Ok(RamSearchFilter::Decreased),
+        _ => {
+            let value = parse_number_string(buffer, 1)?;
+            let value = u8::try_from(value).map_err(|err|format!("Error parsing value: {}", err))?;
+            Ok(RamSearchFilter::Value(value))
+        }
+    };
+}
+
 fn parse_watch_mode(buffer: &Vec<&str>, index:usize)->Result<WatchMode, String>{
     let Some(param) = buffer.get(index) else {
         return Result::Err(String::from("No parameter"))
@@ -238,4 +278,14 @@ impl DebuggerInterface for TerminalDebugger{
     fn send_result(&self, result:DebuggerResult) {
         self.result_sender.send(result).unwrap()
     }
-}
\ No newline at end of file
+}
+/// Used by the second gameboy in link mode since the terminal is attached only to the first one
+pub struct DetachedDebugger;
+
+impl DebuggerInterface for DetachedDebugger{
+    fn should_stop(&self)->bool {false}
+
+    fn recv_command(&self)->DebuggerCommand {DebuggerCommand::Continue}
+
+    fn send_result(&self, _result:DebuggerResult) {}
+}
@@ -2,6 +2,25 @@ use std::ffi::CStr;
 
 use sdl2::{libc::c_char, sys::*};
 
+/// Returns null if there is no game controller connected
+pub fn open_game_controller()->*mut SDL_GameController{
+    unsafe{
+        if SDL_InitSubSystem(SDL_INIT_GAMECONTROLLER) != 0{
+            log::warn!("Failed to init game controllers: {}", get_sdl_error_message());
+            return std::ptr::null_mut();
+        }
+        return (0..SDL_NumJoysticks())
+            .find(|i| SDL_IsGameController(*i) == SDL_bool::SDL_TRUE)
+            .map_or(std::ptr::null_mut(), |i| SDL_GameControllerOpen(i));
+    }
+}
+
+pub fn close_game_controller(controller:*mut SDL_GameController){
+    if !controller.is_null(){
+        unsafe{SDL_GameControllerClose(controller)};
+    }
+}
+
 pub fn get_sdl_error_message()->&'static str{
     unsafe{
         let error_message:*const c_char = SDL_GetError();